@@ -0,0 +1,89 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Compression of the data of the messages, see [`FloodsubConfig::compression`].
+//!
+//! [`FloodsubConfig::compression`]: crate::FloodsubConfig::compression
+
+use crate::Compression;
+#[cfg(feature = "gzip")]
+use std::io::Write;
+use std::io::{self, Read};
+
+/// Compresses `data` with the given algorithm.
+pub(crate) fn compress(compression: Compression, data: &[u8]) -> io::Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(data.to_vec()),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => zstd::stream::encode_all(data, 0),
+        #[cfg(not(feature = "gzip"))]
+        Compression::Gzip => Err(unsupported(compression)),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => Err(unsupported(compression)),
+    }
+}
+
+/// Decompresses `data` compressed with the given algorithm, failing if the result exceeds
+/// `max_len` bytes.
+pub(crate) fn decompress(
+    compression: Compression,
+    data: &[u8],
+    max_len: usize,
+) -> io::Result<Vec<u8>> {
+    let decoder: io::Result<Box<dyn Read + '_>> = match compression {
+        Compression::None => return Ok(data.to_vec()),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Ok(Box::new(flate2::read::GzDecoder::new(data))),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => zstd::stream::read::Decoder::new(data).map(|d| Box::new(d) as _),
+        #[cfg(not(feature = "gzip"))]
+        Compression::Gzip => Err(unsupported(compression)),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => Err(unsupported(compression)),
+    };
+    // Read one byte more than allowed to detect data that is too large, without decompressing
+    // all of it.
+    let mut decompressed = Vec::new();
+    decoder?
+        .take(max_len as u64 + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() > max_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decompressed data exceeds {max_len} bytes"),
+        ));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn unsupported(compression: Compression) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{compression:?} compression is not enabled"),
+    )
+}
@@ -0,0 +1,299 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Exact tracking of the most recently received messages, complementing or replacing the
+//! probabilistic `received` filter of the behaviour.
+
+use crate::protocol::{DedupKey, FloodsubMessage, MessageId};
+use crate::topic::Topic;
+use instant::Instant;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+/// Set of the ids of the last `capacity` messages inserted into it.
+pub(crate) struct ExactWindow {
+    capacity: usize,
+    /// Ids in insertion order, with when they were inserted.
+    order: VecDeque<(MessageId, Instant)>,
+    keys: HashSet<MessageId>,
+}
+
+impl ExactWindow {
+    pub(crate) fn new(capacity: usize) -> Self {
+        ExactWindow {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            keys: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// Returns true if the window is disabled, i.e. has a capacity of 0.
+    pub(crate) fn is_disabled(&self) -> bool {
+        self.capacity == 0
+    }
+
+    /// Returns true if `message` is part of the window.
+    pub(crate) fn contains(&self, message: &FloodsubMessage) -> bool {
+        self.keys.contains(&message.id())
+    }
+
+    /// Inserts `message` into the window, evicting the oldest entry if it is full.
+    pub(crate) fn insert(&mut self, message: &FloodsubMessage) {
+        if self.is_disabled() {
+            return;
+        }
+        let key = message.id();
+        if !self.keys.insert(key.clone()) {
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some((oldest, _)) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        self.order.push_back((key, Instant::now()));
+    }
+
+    /// Changes the capacity of the window, evicting the oldest entries over it.
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.order.len() > capacity {
+            if let Some((oldest, _)) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+    }
+
+    /// Removes all entries from the window.
+    pub(crate) fn clear(&mut self) {
+        self.order.clear();
+        self.keys.clear();
+    }
+
+    /// Returns when the oldest entry of the window was inserted, if any.
+    pub(crate) fn oldest(&self) -> Option<Instant> {
+        self.order.front().map(|(_, inserted)| *inserted)
+    }
+}
+
+/// Set of the messages inserted into it within the last `ttl`, identified by a 64-bit hash of
+/// their [`DedupKey`].
+pub(crate) struct TimeCache {
+    ttl: Duration,
+    /// Hashes in insertion order, with when they expire.
+    order: VecDeque<(u64, Instant)>,
+    keys: HashSet<u64>,
+}
+
+impl TimeCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        TimeCache {
+            ttl,
+            order: VecDeque::new(),
+            keys: HashSet::new(),
+        }
+    }
+
+    /// Inserts the message identified by `key` into the cache. Returns false if it was already
+    /// part of it.
+    pub(crate) fn test_and_add(&mut self, key: &DedupKey<'_>, now: Instant) -> bool {
+        let key = hash(key);
+        if !self.keys.insert(key) {
+            return false;
+        }
+        self.order.push_back((key, now + self.ttl));
+        true
+    }
+
+    /// Returns true if the message identified by `key` is part of the cache.
+    pub(crate) fn contains(&self, key: &DedupKey<'_>) -> bool {
+        self.keys.contains(&hash(key))
+    }
+
+    /// Removes the entries expired at `now`.
+    pub(crate) fn expire(&mut self, now: Instant) {
+        while let Some((key, expiry)) = self.order.front() {
+            if *expiry > now {
+                break;
+            }
+            self.keys.remove(key);
+            self.order.pop_front();
+        }
+    }
+
+    /// Returns when the oldest entry of the cache expires, if any.
+    pub(crate) fn next_expiry(&self) -> Option<Instant> {
+        self.order.front().map(|(_, expiry)| *expiry)
+    }
+
+    /// Returns when the oldest entry of the cache was inserted, if any.
+    pub(crate) fn oldest(&self) -> Option<Instant> {
+        self.next_expiry().map(|expiry| expiry - self.ttl)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Removes all entries from the cache.
+    pub(crate) fn clear(&mut self) {
+        self.order.clear();
+        self.keys.clear();
+    }
+}
+
+/// [`ExactWindow`]s sharing a total capacity between the weighted topics and a pool for the other
+/// topics, proportionally to their weight and traffic.
+pub(crate) struct WeightedWindows {
+    capacity: usize,
+    weights: HashMap<Topic, f64>,
+    /// Windows of the weighted topics, and of the pool under `None`.
+    groups: HashMap<Option<Topic>, WeightedGroup>,
+    /// Number of insertions since the windows were last resized.
+    insertions: usize,
+}
+
+struct WeightedGroup {
+    window: ExactWindow,
+    weight: f64,
+    /// Number of insertions since the windows were last resized.
+    traffic: u64,
+}
+
+/// Weight of the pool of the topics without a weight.
+const POOL_WEIGHT: f64 = 1.0;
+
+impl WeightedWindows {
+    pub(crate) fn new(capacity: usize, weights: HashMap<Topic, f64>) -> Self {
+        let mut groups = weights
+            .iter()
+            .map(|(topic, weight)| (Some(topic.clone()), *weight))
+            .chain(std::iter::once((None, POOL_WEIGHT)))
+            .map(|(group, weight)| {
+                let group_state = WeightedGroup {
+                    window: ExactWindow::new(0),
+                    weight,
+                    traffic: 0,
+                };
+                (group, group_state)
+            })
+            .collect::<HashMap<_, _>>();
+        let total = groups.values().map(|group| group.weight).sum::<f64>();
+        for group in groups.values_mut() {
+            group
+                .window
+                .set_capacity(share(capacity, group.weight, total));
+        }
+        WeightedWindows {
+            capacity,
+            weights,
+            groups,
+            insertions: 0,
+        }
+    }
+
+    /// Returns true if the windows are disabled, i.e. have a total capacity of 0.
+    pub(crate) fn is_disabled(&self) -> bool {
+        self.capacity == 0
+    }
+
+    /// Returns true if `message` is part of the window of its first topic.
+    pub(crate) fn contains(&self, message: &FloodsubMessage) -> bool {
+        self.groups
+            .get(&self.group_of(message))
+            .map_or(false, |group| group.window.contains(message))
+    }
+
+    /// Inserts `message` into the window of its first topic, resizing the windows once as many
+    /// messages as they hold were inserted.
+    pub(crate) fn insert(&mut self, message: &FloodsubMessage) {
+        if self.is_disabled() {
+            return;
+        }
+        let key = self.group_of(message);
+        if let Some(group) = self.groups.get_mut(&key) {
+            group.window.insert(message);
+            group.traffic += 1;
+        }
+        self.insertions += 1;
+        if self.insertions >= self.capacity {
+            self.resize();
+        }
+    }
+
+    /// Returns when the oldest entry of the windows was inserted, if any.
+    pub(crate) fn oldest(&self) -> Option<Instant> {
+        self.groups
+            .values()
+            .filter_map(|group| group.window.oldest())
+            .min()
+    }
+
+    /// Removes all entries from the windows.
+    pub(crate) fn clear(&mut self) {
+        for group in self.groups.values_mut() {
+            group.window.clear();
+        }
+    }
+
+    fn group_of(&self, message: &FloodsubMessage) -> Option<Topic> {
+        message
+            .topics
+            .first()
+            .filter(|topic| self.weights.contains_key(*topic))
+            .cloned()
+    }
+
+    /// Shares the capacity proportionally to the weight and traffic of each window. Windows
+    /// without traffic keep room for a single message.
+    fn resize(&mut self) {
+        self.insertions = 0;
+        let total = self
+            .groups
+            .values()
+            .map(|group| group.weight * group.traffic as f64)
+            .sum::<f64>();
+        if total <= 0.0 {
+            return;
+        }
+        for group in self.groups.values_mut() {
+            let capacity = share(self.capacity, group.weight * group.traffic as f64, total);
+            group.window.set_capacity(capacity.max(1));
+            group.traffic = 0;
+        }
+    }
+}
+
+/// Returns the 64-bit hash identifying a message in a [`TimeCache`].
+fn hash(key: &DedupKey<'_>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the share of `capacity` corresponding to `part` out of `total`.
+fn share(capacity: usize, part: f64, total: f64) -> usize {
+    if total <= 0.0 {
+        return 0;
+    }
+    (capacity as f64 * part / total) as usize
+}
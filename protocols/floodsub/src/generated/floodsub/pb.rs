@@ -18,6 +18,7 @@ use super::super::*;
 pub struct RPC {
     pub subscriptions: Vec<floodsub::pb::mod_RPC::SubOpts>,
     pub publish: Vec<floodsub::pb::Message>,
+    pub sync: Option<floodsub::pb::mod_RPC::SubscriptionSync>,
 }
 
 impl<'a> MessageRead<'a> for RPC {
@@ -27,6 +28,7 @@ impl<'a> MessageRead<'a> for RPC {
             match r.next_tag(bytes) {
                 Ok(10) => msg.subscriptions.push(r.read_message::<floodsub::pb::mod_RPC::SubOpts>(bytes)?),
                 Ok(18) => msg.publish.push(r.read_message::<floodsub::pb::Message>(bytes)?),
+                Ok(82) => msg.sync = Some(r.read_message::<floodsub::pb::mod_RPC::SubscriptionSync>(bytes)?),
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -40,11 +42,13 @@ impl MessageWrite for RPC {
         0
         + self.subscriptions.iter().map(|s| 1 + sizeof_len((s).get_size())).sum::<usize>()
         + self.publish.iter().map(|s| 1 + sizeof_len((s).get_size())).sum::<usize>()
+        + self.sync.as_ref().map_or(0, |m| 1 + sizeof_len((m).get_size()))
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
         for s in &self.subscriptions { w.write_with_tag(10, |w| w.write_message(s))?; }
         for s in &self.publish { w.write_with_tag(18, |w| w.write_message(s))?; }
+        if let Some(ref s) = self.sync { w.write_with_tag(82, |w| w.write_message(s))?; }
         Ok(())
     }
 }
@@ -89,6 +93,50 @@ impl MessageWrite for SubOpts {
     }
 }
 
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SubscriptionSync {
+    pub digest: Option<Vec<u8>>,
+    pub request_full: Option<bool>,
+    pub full: Option<bool>,
+    pub partial: Option<bool>,
+}
+
+impl<'a> MessageRead<'a> for SubscriptionSync {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.digest = Some(r.read_bytes(bytes)?.to_owned()),
+                Ok(16) => msg.request_full = Some(r.read_bool(bytes)?),
+                Ok(24) => msg.full = Some(r.read_bool(bytes)?),
+                Ok(32) => msg.partial = Some(r.read_bool(bytes)?),
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for SubscriptionSync {
+    fn get_size(&self) -> usize {
+        0
+        + self.digest.as_ref().map_or(0, |m| 1 + sizeof_len((m).len()))
+        + self.request_full.as_ref().map_or(0, |m| 1 + sizeof_varint(*(m) as u64))
+        + self.full.as_ref().map_or(0, |m| 1 + sizeof_varint(*(m) as u64))
+        + self.partial.as_ref().map_or(0, |m| 1 + sizeof_varint(*(m) as u64))
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.digest { w.write_with_tag(10, |w| w.write_bytes(&**s))?; }
+        if let Some(ref s) = self.request_full { w.write_with_tag(16, |w| w.write_bool(*s))?; }
+        if let Some(ref s) = self.full { w.write_with_tag(24, |w| w.write_bool(*s))?; }
+        if let Some(ref s) = self.partial { w.write_with_tag(32, |w| w.write_bool(*s))?; }
+        Ok(())
+    }
+}
+
 }
 
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -98,6 +146,11 @@ pub struct Message {
     pub data: Option<Vec<u8>>,
     pub seqno: Option<Vec<u8>>,
     pub topic_ids: Vec<String>,
+    pub signature: Option<Vec<u8>>,
+    pub key: Option<Vec<u8>>,
+    pub provenance: Vec<Vec<u8>>,
+    pub ttl: Option<u32>,
+    pub compression: Option<u32>,
 }
 
 impl<'a> MessageRead<'a> for Message {
@@ -109,6 +162,11 @@ impl<'a> MessageRead<'a> for Message {
                 Ok(18) => msg.data = Some(r.read_bytes(bytes)?.to_owned()),
                 Ok(26) => msg.seqno = Some(r.read_bytes(bytes)?.to_owned()),
                 Ok(34) => msg.topic_ids.push(r.read_string(bytes)?.to_owned()),
+                Ok(42) => msg.signature = Some(r.read_bytes(bytes)?.to_owned()),
+                Ok(50) => msg.key = Some(r.read_bytes(bytes)?.to_owned()),
+                Ok(58) => msg.provenance.push(r.read_bytes(bytes)?.to_owned()),
+                Ok(64) => msg.ttl = Some(r.read_uint32(bytes)?),
+                Ok(72) => msg.compression = Some(r.read_uint32(bytes)?),
                 Ok(t) => { r.read_unknown(bytes, t)?; }
                 Err(e) => return Err(e),
             }
@@ -124,6 +182,11 @@ impl MessageWrite for Message {
         + self.data.as_ref().map_or(0, |m| 1 + sizeof_len((m).len()))
         + self.seqno.as_ref().map_or(0, |m| 1 + sizeof_len((m).len()))
         + self.topic_ids.iter().map(|s| 1 + sizeof_len((s).len())).sum::<usize>()
+        + self.signature.as_ref().map_or(0, |m| 1 + sizeof_len((m).len()))
+        + self.key.as_ref().map_or(0, |m| 1 + sizeof_len((m).len()))
+        + self.provenance.iter().map(|s| 1 + sizeof_len((s).len())).sum::<usize>()
+        + self.ttl.as_ref().map_or(0, |m| 1 + sizeof_varint(*(m) as u64))
+        + self.compression.as_ref().map_or(0, |m| 1 + sizeof_varint(*(m) as u64))
     }
 
     fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
@@ -131,6 +194,11 @@ impl MessageWrite for Message {
         if let Some(ref s) = self.data { w.write_with_tag(18, |w| w.write_bytes(&**s))?; }
         if let Some(ref s) = self.seqno { w.write_with_tag(26, |w| w.write_bytes(&**s))?; }
         for s in &self.topic_ids { w.write_with_tag(34, |w| w.write_string(&**s))?; }
+        if let Some(ref s) = self.signature { w.write_with_tag(42, |w| w.write_bytes(&**s))?; }
+        if let Some(ref s) = self.key { w.write_with_tag(50, |w| w.write_bytes(&**s))?; }
+        for s in &self.provenance { w.write_with_tag(58, |w| w.write_bytes(&**s))?; }
+        if let Some(ref s) = self.ttl { w.write_with_tag(64, |w| w.write_uint32(*s))?; }
+        if let Some(ref s) = self.compression { w.write_with_tag(72, |w| w.write_uint32(*s))?; }
         Ok(())
     }
 }
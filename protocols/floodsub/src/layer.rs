@@ -18,26 +18,50 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use crate::compression;
+use crate::dedup::{ExactWindow, TimeCache, WeightedWindows};
+#[cfg(feature = "metrics")]
+use crate::metrics::recorder;
+use crate::metrics::{DeliveryRate, FloodsubMetrics, TopicStats};
 use crate::protocol::{
-    FloodsubMessage, FloodsubProtocol, FloodsubRpc, FloodsubSubscription,
-    FloodsubSubscriptionAction,
+    inline_public_key, subscription_digest, FloodsubMessage, FloodsubProtocol, FloodsubRpc,
+    FloodsubSubscription, FloodsubSubscriptionAction, FloodsubSubscriptionSync, MessageId,
+    MAX_MESSAGE_LEN_BYTES,
+};
+use crate::router::{self, TopicRouter};
+use crate::spill::SpillQueue;
+use crate::topic::{Topic, TopicMatcher};
+use crate::{
+    CallsAfterShutdown, Compression, DedupStrategy, DeliveryOverflowPolicy, EndpointPreference,
+    FilterFullPolicy, FloodsubConfig, MessageAuthenticity, MessageCheck, MultiTopicFanout,
+    QueueFullPolicy, Role, SequenceNumberWraparound, SequenceNumbers, SpoofedSourcePolicy,
+    MAX_SEQUENCE_NUMBER_LEN, MIN_SEQUENCE_NUMBER_LEN,
 };
-use crate::topic::Topic;
-use crate::FloodsubConfig;
 use cuckoofilter::{CuckooError, CuckooFilter};
 use fnv::FnvHashSet;
-use libp2p_core::{Endpoint, Multiaddr};
+use futures::FutureExt;
+use futures_timer::Delay;
+use instant::Instant;
+use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::behaviour::{ConnectionClosed, ConnectionEstablished, FromSwarm};
 use libp2p_swarm::{
     dial_opts::DialOpts, ConnectionDenied, ConnectionId, NetworkBehaviour, NetworkBehaviourAction,
     NotifyHandler, OneShotHandler, PollParameters, THandler, THandlerInEvent, THandlerOutEvent,
 };
-use log::warn;
+use log::{debug, warn};
+use rand::RngCore;
 use smallvec::SmallVec;
-use std::collections::hash_map::{DefaultHasher, HashMap};
-use std::task::{Context, Poll};
+use std::cmp::Ordering;
+use std::collections::hash_map::{DefaultHasher, Entry, HashMap};
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, SystemTime};
 use std::{collections::VecDeque, iter};
+#[cfg(feature = "tokio")]
+use tokio::sync::broadcast;
 
 /// Network behaviour that handles the floodsub protocol.
 pub struct Floodsub {
@@ -46,6 +70,9 @@ pub struct Floodsub {
 
     config: FloodsubConfig,
 
+    /// Source of the messages we publish, see [`FloodsubConfig::message_authenticity`].
+    publish_source: PeerId,
+
     /// List of peers to send messages to.
     target_peers: FnvHashSet<PeerId>,
 
@@ -54,13 +81,206 @@ pub struct Floodsub {
     //       opened substreams
     connected_peers: HashMap<PeerId, SmallVec<[Topic; 8]>>,
 
+    /// Number of connected peers subscribed to each topic, kept in sync with
+    /// [`Floodsub::connected_peers`].
+    subscriber_counts: HashMap<Topic, usize>,
+
     // List of topics we're subscribed to. Necessary to filter out messages that we receive
     // erroneously.
     subscribed_topics: SmallVec<[Topic; 16]>,
 
+    /// Families of topics we're subscribed to, see [`Floodsub::subscribe_matching`].
+    topic_matchers: Vec<TopicMatcher>,
+
     // We keep track of the messages we received (in the format `hash(source ID, seq_no)`) so that
     // we don't dispatch the same message twice if we receive it twice on the network.
     received: CuckooFilter<DefaultHasher>,
+
+    /// Messages received within [`FloodsubConfig::duplicate_cache_time`], used instead of
+    /// [`Floodsub::received`] if set.
+    duplicate_cache: Option<TimeCache>,
+
+    /// Checks performed on the received messages, in order, as resolved from
+    /// [`FloodsubConfig::message_checks`].
+    message_checks: Vec<MessageCheck>,
+
+    /// Exact ids of the last messages we received, confirming the duplicates reported by
+    /// [`Floodsub::received`]. Only used if [`FloodsubConfig::exact_dedup_window`] is non-zero.
+    recent_ids: WeightedWindows,
+
+    /// Ids of the last messages we published, to tell echoes of them from messages spoofing our
+    /// peer id, see [`FloodsubConfig::spoofed_source_policy`].
+    published_ids: ExactWindow,
+
+    /// Changes to our own subscriptions that have not been announced yet, if
+    /// [`FloodsubConfig::subscription_debounce`] is set.
+    pending_subscriptions: Vec<FloodsubSubscription>,
+
+    /// Changes to our own subscriptions made while handling a received RPC, announced along with
+    /// the messages forwarded because of that RPC, see [`Floodsub::process_messages`].
+    deferred_announcements: Option<Vec<FloodsubSubscription>>,
+
+    /// When to announce [`Floodsub::pending_subscriptions`].
+    pending_subscriptions_deadline: Option<Instant>,
+
+    /// The last messages sent or received for each topic, forwarded to peers that subscribe to
+    /// the topic afterwards. Only used if [`FloodsubConfig::recent_messages_per_topic`] is
+    /// non-zero.
+    recent_messages: HashMap<Topic, VecDeque<FloodsubMessage>>,
+
+    /// Statistics about the behaviour.
+    metrics: FloodsubMetrics,
+
+    /// Number of RPCs carrying messages queued in [`Floodsub::events`] for each peer.
+    queued_rpcs: HashMap<PeerId, usize>,
+
+    /// Tokens left to each peer whose bucket isn't full, if
+    /// [`FloodsubConfig::max_messages_per_peer_per_sec`] is set.
+    rate_limit_tokens: HashMap<PeerId, f64>,
+
+    /// When the buckets of [`Floodsub::rate_limit_tokens`] were last refilled.
+    rate_limit_refill: Instant,
+
+    /// Decides which of the subscribed peers messages are sent to.
+    router: Box<dyn TopicRouter>,
+
+    /// Sequence number of the last message we published, if
+    /// [`FloodsubConfig::sequence_numbers`] is [`SequenceNumbers::Monotonic`].
+    sequence_number_counter: u64,
+
+    /// Highest sequence number received from each source, and when to forget about it. Only
+    /// used if [`FloodsubConfig::validate_sequence_monotonicity`] is set.
+    highest_sequence_numbers: HashMap<PeerId, (Vec<u8>, Instant)>,
+
+    /// When to remove the expired entries of [`Floodsub::highest_sequence_numbers`].
+    next_sequence_tracking_sweep: Option<Instant>,
+
+    /// Until when we don't announce our subscriptions, if
+    /// [`FloodsubConfig::startup_announce_delay`] is set.
+    startup_announce_deadline: Option<Instant>,
+
+    /// When to send the next digest of our subscriptions, if
+    /// [`FloodsubConfig::subscription_sync_interval`] is set.
+    next_subscription_sync: Option<Instant>,
+
+    /// When the first event queued since we were last polled was queued without waking the task,
+    /// to detect that we are stuck, see [`FloodsubConfig::idle_check_interval`].
+    unwoken_since: Option<Instant>,
+
+    /// State of the peers we recently disconnected from, restored if they reconnect soon enough.
+    /// Only used if [`FloodsubConfig::subscription_resume_window`] is set.
+    resumable_peers: HashMap<PeerId, ResumableState>,
+
+    /// When to remove the expired entries of [`Floodsub::resumable_peers`].
+    next_resumable_sweep: Option<Instant>,
+
+    /// When we recently connected to each peer, if [`FloodsubConfig::flap_threshold`] is set.
+    connection_history: HashMap<PeerId, VecDeque<Instant>>,
+
+    /// Peers considered flapping, and when their cooldown ends.
+    flapping_peers: HashMap<PeerId, Instant>,
+
+    /// Channel through which the messages are delivered, if any.
+    #[cfg(feature = "tokio")]
+    message_sender: Option<broadcast::Sender<FloodsubMessage>>,
+
+    /// Applied to every message right before it is delivered to us, see
+    /// [`Floodsub::set_delivery_transform`].
+    delivery_transform: Option<Box<dyn FnMut(FloodsubMessage) -> Option<FloodsubMessage> + Send>>,
+
+    /// Function validating received messages, see [`Floodsub::set_message_validator`].
+    message_validator: Option<Box<MessageValidator>>,
+
+    /// Messages delivered to us during the current window for each topic, if
+    /// [`FloodsubConfig::max_deliveries_per_topic`] is set.
+    delivery_windows: HashMap<Topic, DeliveryWindow>,
+
+    /// Messages received from the network, and the peer each was received from, waiting to be
+    /// processed because of [`FloodsubConfig::message_processing_budget`].
+    deferred_messages: VecDeque<(PeerId, FloodsubMessage)>,
+
+    /// Peers that receive all the messages of the topics they are subscribed to, see
+    /// [`Floodsub::pin_peer`].
+    pinned_peers: HashSet<PeerId>,
+
+    /// Peers whose RPCs are ignored and which we don't send messages to, see
+    /// [`Floodsub::blacklist_peer`].
+    blacklisted_peers: HashSet<PeerId>,
+
+    /// Flush intervals set with [`Floodsub::set_peer_flush_interval`].
+    peer_flush_intervals: HashMap<PeerId, Duration>,
+
+    /// Messages waiting to be sent to each peer, and when to send them.
+    outbound_batches: HashMap<PeerId, (Vec<FloodsubMessage>, Instant)>,
+
+    /// When a message was last sent or received on each of the topics we are subscribed to. Only
+    /// used if [`FloodsubConfig::unsubscribe_idle_topics`] is set.
+    topic_activity: HashMap<Topic, Instant>,
+
+    /// Sources whose messages are delivered and forwarded ahead of the others.
+    priority_sources: HashSet<PeerId>,
+
+    /// Number of events at the front of [`Floodsub::events`] that concern messages of
+    /// [`Floodsub::priority_sources`].
+    priority_events: usize,
+
+    /// Number of RPCs carrying subscriptions in [`Floodsub::events`] for each peer, if
+    /// [`FloodsubConfig::subscriptions_before_messages`] is set.
+    queued_subscription_rpcs: HashMap<PeerId, usize>,
+
+    /// Ids of the messages to trace the next time they are received, see
+    /// [`Floodsub::trace_message`].
+    traced_messages: HashSet<MessageId>,
+
+    /// Whether [`Floodsub::shutdown`] was called.
+    shutting_down: bool,
+
+    /// Verdicts the [`DeliveryConfirmation`]s report the verdicts of the application on, with the
+    /// source of the messages. See [`FloodsubConfig::confirm_deliveries`].
+    verdicts: Arc<Mutex<Vec<(PeerId, MessageVerdict)>>>,
+
+    /// RPCs queued over [`FloodsubConfig::events_spill_threshold`], created when first needed.
+    spill: Option<SpillQueue>,
+
+    /// Topics we subscribed to that are not established yet, with the peers that acknowledged
+    /// the subscription. See [`FloodsubConfig::subscription_quorum`].
+    unestablished_topics: HashMap<Topic, HashSet<PeerId>>,
+
+    /// RPCs yielded to the handler of each connection and not acknowledged yet, if
+    /// [`FloodsubConfig::subscription_quorum`] is set.
+    outbound_acks: HashMap<(PeerId, ConnectionId), OutboundAcks>,
+
+    /// Subscription changes received from each peer during the current window, if
+    /// [`FloodsubConfig::max_subscription_updates_per_peer`] is set.
+    subscription_rates: HashMap<PeerId, SubscriptionRate>,
+
+    /// Topics of the parts of all the subscriptions of each peer received so far, until the last
+    /// part, see [`FloodsubSubscriptionSync::Partial`].
+    full_subscription_parts: HashMap<PeerId, Vec<Topic>>,
+
+    /// Peers some subscriptions of which we ignored because of
+    /// [`FloodsubConfig::max_subscriptions_per_peer`]. Our view of their subscriptions never
+    /// matches their digests, so we don't reconcile it.
+    truncated_peers: HashSet<PeerId>,
+
+    /// Score of the connected peers that misbehaved, see [`Floodsub::peer_score`].
+    peer_scores: HashMap<PeerId, f64>,
+
+    /// The connections to each peer, oldest first, with our role in them and their address.
+    peer_connections: HashMap<PeerId, SmallVec<[(ConnectionId, ConnectedPoint); 2]>>,
+
+    /// Role in the connections of the peers messages are forwarded to first.
+    endpoint_preference: EndpointPreference,
+
+    /// Timer waking us up for the next deadline returned by [`Floodsub::next_deadline`].
+    timer: Delay,
+
+    /// Messages to publish later, with their topic, by when they are due, see
+    /// [`Floodsub::publish_at`].
+    scheduled_messages: BTreeMap<Instant, Vec<(Topic, Vec<u8>)>>,
+
+    /// Waker of the task that last polled us while we had nothing to yield, woken once there is.
+    waker: Option<Waker>,
 }
 
 impl Floodsub {
@@ -70,45 +290,269 @@ impl Floodsub {
     }
 
     /// Creates a `Floodsub` with the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`FloodsubConfig::sequence_number_len`] is out of bounds.
     pub fn from_config(config: FloodsubConfig) -> Self {
+        assert!(
+            (MIN_SEQUENCE_NUMBER_LEN..=MAX_SEQUENCE_NUMBER_LEN)
+                .contains(&config.sequence_number_len),
+            "sequence_number_len must be between {MIN_SEQUENCE_NUMBER_LEN} and {MAX_SEQUENCE_NUMBER_LEN}"
+        );
+
+        let router = router::from_policy(&config.forward_policy);
+        let recent_ids = WeightedWindows::new(
+            config.exact_dedup_window,
+            config.dedup_topic_weights.clone(),
+        );
+        let received = CuckooFilter::with_capacity(config.cuckoo_filter_capacity);
+        let duplicate_cache = config.duplicate_cache_time.map(TimeCache::new);
+        let metrics = FloodsubMetrics {
+            delivery_rate: DeliveryRate::new(config.delivery_rate_tracking_window),
+            ..Default::default()
+        };
+        let published_ids = ExactWindow::new(config.published_ids_window);
+        let mut message_checks = Vec::new();
+        for check in config
+            .message_checks
+            .iter()
+            .chain(&MessageCheck::DEFAULT_ORDER)
+        {
+            if !message_checks.contains(check) {
+                message_checks.push(*check);
+            }
+        }
+        let subscribed_topics = SmallVec::with_capacity(config.subscribed_topics_capacity);
+        let startup_announce_deadline = config.startup_announce_delay.map(|delay| {
+            let jitter = config
+                .startup_announce_jitter
+                .mul_f64(rand::random::<f64>());
+            Instant::now() + delay + jitter
+        });
+        let next_subscription_sync = config
+            .subscription_sync_interval
+            .map(|interval| Instant::now() + interval);
+        let publish_source = match &config.message_authenticity {
+            MessageAuthenticity::Signed(keypair) => keypair.public().to_peer_id(),
+            MessageAuthenticity::Author(peer_id) => *peer_id,
+            MessageAuthenticity::Anonymous => config.local_peer_id,
+        };
         Floodsub {
             events: VecDeque::new(),
             config,
+            publish_source,
             target_peers: FnvHashSet::default(),
             connected_peers: HashMap::new(),
-            subscribed_topics: SmallVec::new(),
-            received: CuckooFilter::new(),
+            subscriber_counts: HashMap::new(),
+            subscribed_topics,
+            topic_matchers: Vec::new(),
+            received,
+            duplicate_cache,
+            recent_ids,
+            published_ids,
+            message_checks,
+            pending_subscriptions: Vec::new(),
+            deferred_announcements: None,
+            pending_subscriptions_deadline: None,
+            recent_messages: HashMap::new(),
+            metrics,
+            queued_rpcs: HashMap::new(),
+            rate_limit_tokens: HashMap::new(),
+            rate_limit_refill: Instant::now(),
+            router,
+            // Start from the current time so that sequence numbers keep increasing across
+            // restarts.
+            sequence_number_counter: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default(),
+            highest_sequence_numbers: HashMap::new(),
+            next_sequence_tracking_sweep: None,
+            next_subscription_sync,
+            unwoken_since: None,
+            startup_announce_deadline,
+            resumable_peers: HashMap::new(),
+            next_resumable_sweep: None,
+            connection_history: HashMap::new(),
+            flapping_peers: HashMap::new(),
+            #[cfg(feature = "tokio")]
+            message_sender: None,
+            delivery_transform: None,
+            message_validator: None,
+            delivery_windows: HashMap::new(),
+            deferred_messages: VecDeque::new(),
+            pinned_peers: HashSet::new(),
+            blacklisted_peers: HashSet::new(),
+            peer_flush_intervals: HashMap::new(),
+            outbound_batches: HashMap::new(),
+            topic_activity: HashMap::new(),
+            priority_sources: HashSet::new(),
+            priority_events: 0,
+            queued_subscription_rpcs: HashMap::new(),
+            shutting_down: false,
+            traced_messages: HashSet::new(),
+            verdicts: Arc::default(),
+            spill: None,
+            unestablished_topics: HashMap::new(),
+            outbound_acks: HashMap::new(),
+            peer_connections: HashMap::new(),
+            subscription_rates: HashMap::new(),
+            full_subscription_parts: HashMap::new(),
+            truncated_peers: HashSet::new(),
+            peer_scores: HashMap::new(),
+            endpoint_preference: EndpointPreference::NoPreference,
+            timer: Delay::new(Duration::ZERO),
+            scheduled_messages: BTreeMap::new(),
+            waker: None,
         }
     }
 
+    /// Delivers the messages we receive through the given broadcast channel instead of as
+    /// [`FloodsubEvent::Message`]s, decoupling their consumption from polling the swarm.
+    ///
+    /// Every receiver of the channel gets every message; receivers that fall behind by more than
+    /// the capacity of the channel are notified with
+    /// [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged). Messages are
+    /// dropped while the channel has no receiver.
+    #[cfg(feature = "tokio")]
+    pub fn with_message_sender(mut self, sender: broadcast::Sender<FloodsubMessage>) -> Self {
+        self.message_sender = Some(sender);
+        self
+    }
+
+    /// Returns the configuration of the behaviour.
+    pub fn config(&self) -> &FloodsubConfig {
+        &self.config
+    }
+
+    /// Returns the peer id of the local node, as configured.
+    ///
+    /// This is the source of the messages we publish, unless
+    /// [`FloodsubConfig::message_authenticity`] says otherwise.
+    pub fn local_peer_id(&self) -> &PeerId {
+        &self.config.local_peer_id
+    }
+
+    /// Sets the sequence number of the last message we published.
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_sequence_number_counter(&mut self, counter: u64) {
+        self.sequence_number_counter = counter;
+    }
+
+    /// Returns true if we are connected to `peer_id`.
+    #[cfg(feature = "testing")]
+    pub(crate) fn is_connected(&self, peer_id: &PeerId) -> bool {
+        self.connected_peers.contains_key(peer_id)
+    }
+
     /// Add a node to the list of nodes to propagate messages to.
     #[inline]
     pub fn add_node_to_partial_view(&mut self, peer_id: PeerId) {
+        let newly_added = self.target_peers.insert(peer_id);
+
         // Send our topics to this node if we're already connected to it.
         if self.connected_peers.contains_key(&peer_id) {
-            for topic in self.subscribed_topics.iter().cloned() {
-                self.events
-                    .push_back(NetworkBehaviourAction::NotifyHandler {
-                        peer_id,
-                        handler: NotifyHandler::Any,
-                        event: FloodsubRpc {
-                            messages: Vec::new(),
-                            subscriptions: vec![FloodsubSubscription {
-                                topic,
-                                action: FloodsubSubscriptionAction::Subscribe,
-                            }],
-                        },
-                    });
-            }
+            self.send_subscription_dump(peer_id);
         }
 
-        if self.target_peers.insert(peer_id) {
-            self.events.push_back(NetworkBehaviourAction::Dial {
-                opts: DialOpts::peer_id(peer_id).build(),
-            });
+        if newly_added {
+            self.push_event(
+                NetworkBehaviourAction::Dial {
+                    opts: DialOpts::peer_id(peer_id).build(),
+                },
+                false,
+            );
         }
     }
 
+    /// Sets a function applied to every message right before it is delivered to us, for example to
+    /// decrypt or decompress its data. Messages for which it returns `None` are not delivered.
+    ///
+    /// Only local delivery is affected: messages are always forwarded to other peers as received.
+    pub fn set_delivery_transform(
+        &mut self,
+        transform: impl FnMut(FloodsubMessage) -> Option<FloodsubMessage> + Send + 'static,
+    ) {
+        self.delivery_transform = Some(Box::new(transform));
+    }
+
+    /// Sets a function validating every message received, with the remote we received it from,
+    /// e.g. to check that its source matches the remote. Messages for which it returns `false`
+    /// are dropped before being recorded as received, delivered or forwarded.
+    pub fn set_message_validator(
+        &mut self,
+        validator: impl Fn(&PeerId, &FloodsubMessage) -> bool + Send + 'static,
+    ) {
+        self.message_validator = Some(Box::new(validator));
+    }
+
+    /// Pins a peer, so that it receives all the messages of the topics it is subscribed to.
+    ///
+    /// Pinned peers are exempted from the choices of the [`TopicRouter`], from the bounds of
+    /// [`FloodsubConfig::max_queued_rpcs_per_peer`] and
+    /// [`FloodsubConfig::max_messages_per_peer_per_sec`], and from being put on hold for flapping.
+    /// This is meant for the few peers that must not miss any message, such as archival nodes.
+    pub fn pin_peer(&mut self, peer_id: PeerId) {
+        self.pinned_peers.insert(peer_id);
+    }
+
+    /// Unpins a peer previously pinned with [`Floodsub::pin_peer`].
+    ///
+    /// Returns true if the peer was pinned.
+    pub fn unpin_peer(&mut self, peer_id: &PeerId) -> bool {
+        self.pinned_peers.remove(peer_id)
+    }
+
+    /// Blacklists a peer: the messages and subscriptions it sends us are ignored, and we don't send
+    /// it messages, which takes precedence over [`Floodsub::pin_peer`]. The connection is kept
+    /// open, and it still receives our subscriptions.
+    pub fn blacklist_peer(&mut self, peer_id: PeerId) {
+        self.blacklisted_peers.insert(peer_id);
+    }
+
+    /// Removes a peer from the blacklist, see [`Floodsub::blacklist_peer`]. Subscriptions it sent
+    /// while blacklisted are not recovered.
+    ///
+    /// Returns true if the peer was blacklisted.
+    pub fn whitelist_peer(&mut self, peer_id: &PeerId) -> bool {
+        self.blacklisted_peers.remove(peer_id)
+    }
+
+    /// Sets how long to collect the messages for a peer before sending them in a single RPC,
+    /// overriding [`FloodsubConfig::rpc_flush_interval`] for this peer. A zero interval sends
+    /// messages right away.
+    ///
+    /// Longer intervals suit high-latency peers, for which batching saves more bandwidth than it
+    /// costs in latency.
+    pub fn set_peer_flush_interval(&mut self, peer_id: PeerId, interval: Duration) {
+        self.peer_flush_intervals.insert(peer_id, interval);
+    }
+
+    /// Sets whether messages are forwarded to the peers we dialed before the peers that dialed us,
+    /// or the other way around.
+    ///
+    /// Peers with connections of both kinds are treated as having the preferred one. The order
+    /// is otherwise the one of the [`TopicRouter`].
+    pub fn set_forwarding_endpoint_preference(&mut self, preference: EndpointPreference) {
+        self.endpoint_preference = preference;
+    }
+
+    /// Sets the sources whose messages are delivered to us and forwarded to other peers before any
+    /// other pending message, in the order they were received.
+    ///
+    /// Subscription changes are not affected.
+    pub fn set_priority_sources(&mut self, sources: HashSet<PeerId>) {
+        self.priority_sources = sources;
+    }
+
+    /// Replaces the [`TopicRouter`] deciding which of the subscribed peers messages are sent to.
+    ///
+    /// This overrides [`FloodsubConfig::forward_policy`].
+    pub fn set_router(&mut self, router: impl TopicRouter) {
+        self.router = Box::new(router);
+    }
+
     /// Remove a node from the list of nodes to propagate messages to.
     #[inline]
     pub fn remove_node_from_partial_view(&mut self, peer_id: &PeerId) {
@@ -119,200 +563,2819 @@ impl Floodsub {
     ///
     /// Returns true if the subscription worked. Returns false if we were already subscribed.
     pub fn subscribe(&mut self, topic: Topic) -> bool {
+        if matches!(self.config.role, Role::PublishOnly | Role::Relay) {
+            debug!(
+                "Not subscribing to {:?} as our role doesn't subscribe",
+                topic.id()
+            );
+            return false;
+        }
+        if self.shutting_down {
+            debug!(
+                "Not subscribing to {:?} as we are shutting down",
+                topic.id()
+            );
+            return self.config.calls_after_shutdown == CallsAfterShutdown::Ignore;
+        }
         if self.subscribed_topics.iter().any(|t| t.id() == topic.id()) {
             return false;
         }
 
-        for peer in self.connected_peers.keys() {
-            self.events
-                .push_back(NetworkBehaviourAction::NotifyHandler {
-                    peer_id: *peer,
-                    handler: NotifyHandler::Any,
-                    event: FloodsubRpc {
-                        messages: Vec::new(),
-                        subscriptions: vec![FloodsubSubscription {
-                            topic: topic.clone(),
-                            action: FloodsubSubscriptionAction::Subscribe,
-                        }],
-                    },
-                });
+        self.announce_subscriptions(vec![FloodsubSubscription {
+            topic: topic.clone(),
+            action: FloodsubSubscriptionAction::Subscribe,
+        }]);
+        self.add_subscription(topic);
+        true
+    }
+
+    /// Subscribes to several topics, announcing the new subscriptions to each connected peer in
+    /// a single RPC.
+    ///
+    /// Returns the number of topics we were not subscribed to yet. See [`Floodsub::subscribe`].
+    pub fn subscribe_many(&mut self, topics: impl IntoIterator<Item = Topic>) -> usize {
+        if matches!(self.config.role, Role::PublishOnly | Role::Relay) {
+            debug!("Not subscribing to any topic as our role doesn't subscribe");
+            return 0;
+        }
+        if self.shutting_down {
+            debug!("Not subscribing to any topic as we are shutting down");
+            return match self.config.calls_after_shutdown {
+                CallsAfterShutdown::Fail => 0,
+                CallsAfterShutdown::Ignore => topics.into_iter().count(),
+            };
         }
 
-        self.subscribed_topics.push(topic);
+        let mut subscriptions = Vec::new();
+        for topic in topics {
+            if self.subscribed_topics.iter().any(|t| t.id() == topic.id()) {
+                continue;
+            }
+            self.add_subscription(topic.clone());
+            subscriptions.push(FloodsubSubscription {
+                topic,
+                action: FloodsubSubscriptionAction::Subscribe,
+            });
+        }
+        let count = subscriptions.len();
+        if count > 0 {
+            self.announce_subscriptions(subscriptions);
+        }
+        count
+    }
+
+    /// Subscribes to all the topics matching `matcher`, including the ones we don't know about
+    /// yet.
+    ///
+    /// Floodsub only announces exact topics, so we subscribe to each matching topic as we learn
+    /// about it from the subscriptions of the connected peers, starting with the current ones.
+    /// Messages received with a matching topic are delivered to us even if we didn't subscribe
+    /// to that topic yet.
+    ///
+    /// Returns true if the subscription worked. Returns false if we were already subscribed to
+    /// this family of topics.
+    pub fn subscribe_matching(&mut self, matcher: TopicMatcher) -> bool {
+        if matches!(self.config.role, Role::PublishOnly | Role::Relay) {
+            debug!("Not subscribing to {matcher:?} as our role doesn't subscribe");
+            return false;
+        }
+        if self.shutting_down {
+            debug!("Not subscribing to {matcher:?} as we are shutting down");
+            return self.config.calls_after_shutdown == CallsAfterShutdown::Ignore;
+        }
+        if self.topic_matchers.contains(&matcher) {
+            return false;
+        }
+
+        let known = self
+            .subscriber_counts
+            .keys()
+            .filter(|topic| matcher.matches(topic))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.topic_matchers.push(matcher);
+        self.subscribe_many(known);
         true
     }
 
+    /// Stops subscribing to the new topics matching `matcher`. We stay subscribed to the topics
+    /// we already subscribed to through it.
+    ///
+    /// Returns true if we were subscribed to this family of topics.
+    pub fn unsubscribe_matching(&mut self, matcher: &TopicMatcher) -> bool {
+        match self.topic_matchers.iter().position(|m| m == matcher) {
+            Some(pos) => {
+                self.topic_matchers.remove(pos);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Unsubscribes from a topic.
     ///
     /// Note that this only requires the topic name.
     ///
     /// Returns true if we were subscribed to this topic.
     pub fn unsubscribe(&mut self, topic: Topic) -> bool {
-        let pos = match self.subscribed_topics.iter().position(|t| *t == topic) {
+        if !self.remove_subscription(&topic) {
+            return false;
+        }
+
+        self.announce_subscriptions(vec![FloodsubSubscription {
+            topic,
+            action: FloodsubSubscriptionAction::Unsubscribe,
+        }]);
+
+        true
+    }
+
+    /// Unsubscribes from all our topics, announcing it to each connected peer in a single RPC.
+    ///
+    /// Returns the number of topics we unsubscribed from.
+    pub fn unsubscribe_all(&mut self) -> usize {
+        let count = self.subscribed_topics.len();
+        self.set_subscriptions(iter::empty());
+        count
+    }
+
+    /// Unsubscribes from all our topics, announcing it to the connected peers right away, and
+    /// stops subscribing and publishing from then on, see
+    /// [`FloodsubConfig::calls_after_shutdown`].
+    ///
+    /// Keep polling the behaviour for the announcements to be sent before closing the
+    /// connections. Calling this again does nothing.
+    pub fn shutdown(&mut self) {
+        if self.shutting_down {
+            return;
+        }
+        self.unsubscribe_all();
+        if !self.pending_subscriptions.is_empty() {
+            self.flush_pending_subscriptions();
+        }
+        self.shutting_down = true;
+    }
+
+    /// Subscribes to the topics of `desired` we're not subscribed to yet, and unsubscribes from
+    /// the topics we're subscribed to that are not part of `desired`.
+    ///
+    /// Returns true if our subscriptions changed. Calling this again with the same topics does
+    /// nothing. See [`Floodsub::set_subscriptions`].
+    pub fn ensure_subscriptions(&mut self, desired: &[Topic]) -> bool {
+        self.set_subscriptions(desired.iter().cloned())
+    }
+
+    /// Replaces our subscriptions with `topics`, announcing the net change to each connected peer
+    /// in a single RPC.
+    ///
+    /// Returns true if our subscriptions changed.
+    pub fn set_subscriptions(&mut self, topics: impl IntoIterator<Item = Topic>) -> bool {
+        if matches!(self.config.role, Role::PublishOnly | Role::Relay) {
+            debug!("Not subscribing to any topic as our role doesn't subscribe");
+            return false;
+        }
+        if self.shutting_down {
+            debug!("Not changing our subscriptions as we are shutting down");
+            return self.config.calls_after_shutdown == CallsAfterShutdown::Ignore;
+        }
+        let mut desired = Vec::<Topic>::new();
+        for topic in topics {
+            if !desired.iter().any(|t| t.id() == topic.id()) {
+                desired.push(topic);
+            }
+        }
+
+        let stale = self
+            .subscribed_topics
+            .iter()
+            .filter(|topic| !desired.iter().any(|t| t.id() == topic.id()))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut changes = Vec::new();
+        for topic in stale {
+            self.remove_subscription(&topic);
+            changes.push(FloodsubSubscription {
+                topic,
+                action: FloodsubSubscriptionAction::Unsubscribe,
+            });
+        }
+        for topic in desired {
+            if self.subscribed_topics.iter().any(|t| t.id() == topic.id()) {
+                continue;
+            }
+            self.add_subscription(topic.clone());
+            changes.push(FloodsubSubscription {
+                topic,
+                action: FloodsubSubscriptionAction::Subscribe,
+            });
+        }
+
+        if changes.is_empty() {
+            return false;
+        }
+        self.announce_subscriptions(changes);
+        true
+    }
+
+    /// Adds a topic we're not subscribed to yet to our subscriptions, without announcing it.
+    fn add_subscription(&mut self, topic: Topic) {
+        if self.config.track_unique_topics {
+            self.metrics.unique_topics.observe(&topic);
+        }
+        if self.config.unsubscribe_idle_topics {
+            self.topic_activity.insert(topic.clone(), Instant::now());
+        }
+        if self.config.subscription_quorum.is_some() {
+            self.unestablished_topics
+                .insert(topic.clone(), HashSet::new());
+        }
+        self.subscribed_topics.push(topic);
+    }
+
+    /// Removes a topic from our subscriptions, without announcing it. Returns false if we weren't
+    /// subscribed to it.
+    fn remove_subscription(&mut self, topic: &Topic) -> bool {
+        let pos = match self.subscribed_topics.iter().position(|t| t == topic) {
             Some(pos) => pos,
             None => return false,
         };
 
         self.subscribed_topics.remove(pos);
+        self.topic_activity.remove(topic);
+        self.unestablished_topics.remove(topic);
+        self.metrics.delivery_rate.forget(topic);
+        true
+    }
 
-        for peer in self.connected_peers.keys() {
-            self.events
-                .push_back(NetworkBehaviourAction::NotifyHandler {
-                    peer_id: *peer,
-                    handler: NotifyHandler::Any,
-                    event: FloodsubRpc {
-                        messages: Vec::new(),
-                        subscriptions: vec![FloodsubSubscription {
-                            topic: topic.clone(),
-                            action: FloodsubSubscriptionAction::Unsubscribe,
-                        }],
-                    },
-                });
+    /// Announces changes of our own subscriptions to all connected peers, either right away in a
+    /// single RPC per peer or, if [`FloodsubConfig::subscription_debounce`] is set, once the
+    /// debounce window has elapsed.
+    fn announce_subscriptions(&mut self, subscriptions: Vec<FloodsubSubscription>) {
+        if self.startup_announce_deadline.is_some() {
+            // All our subscriptions are announced once the startup delay has elapsed.
+            return;
         }
 
-        true
+        let window = match self.config.subscription_debounce {
+            Some(window) => window,
+            None => {
+                if let Some(deferred) = &mut self.deferred_announcements {
+                    deferred.extend(subscriptions);
+                    return;
+                }
+                for peer_id in self.connected_peers.keys().copied().collect::<Vec<_>>() {
+                    self.send_subscriptions(peer_id, subscriptions.clone());
+                }
+                return;
+            }
+        };
+
+        for subscription in subscriptions {
+            // A pending change with the opposite action for the same topic cancels out.
+            if let Some(pos) = self
+                .pending_subscriptions
+                .iter()
+                .position(|s| s.topic == subscription.topic)
+            {
+                self.pending_subscriptions.remove(pos);
+            } else {
+                self.pending_subscriptions.push(subscription);
+            }
+        }
+
+        if self.pending_subscriptions.is_empty() {
+            self.pending_subscriptions_deadline = None;
+        } else if self.pending_subscriptions_deadline.is_none() {
+            self.pending_subscriptions_deadline = Some(Instant::now() + window);
+        }
     }
 
-    /// Publishes a message to the network, if we're subscribed to the topic only.
-    pub fn publish(&mut self, topic: impl Into<Topic>, data: impl Into<Vec<u8>>) {
-        self.publish_many(iter::once(topic), data)
+    /// Sends the debounced subscription changes to all connected peers, in a single RPC per peer.
+    fn flush_pending_subscriptions(&mut self) {
+        self.pending_subscriptions_deadline = None;
+        if self.pending_subscriptions.is_empty() {
+            return;
+        }
+
+        let subscriptions = std::mem::take(&mut self.pending_subscriptions);
+        for peer_id in self.connected_peers.keys().copied().collect::<Vec<_>>() {
+            self.send_subscriptions(peer_id, subscriptions.clone());
+        }
     }
 
-    /// Publishes a message to the network, even if we're not subscribed to the topic.
-    pub fn publish_any(&mut self, topic: impl Into<Topic>, data: impl Into<Vec<u8>>) {
-        self.publish_many_any(iter::once(topic), data)
+    /// Sends an RPC announcing the given changes of our subscriptions to a peer.
+    fn send_subscriptions(&mut self, peer_id: PeerId, subscriptions: Vec<FloodsubSubscription>) {
+        self.send_subscriptions_with_sync(peer_id, subscriptions, None);
     }
 
-    /// Publishes a message with multiple topics to the network.
+    /// Sends an RPC announcing the given changes of our subscriptions to a peer, along with
+    /// information for reconciling our views of each other's subscriptions.
     ///
-    ///
-    /// > **Note**: Doesn't do anything if we're not subscribed to any of the topics.
-    pub fn publish_many(
+    /// The changes are split over several RPCs if they don't fit in a single one.
+    fn send_subscriptions_with_sync(
         &mut self,
-        topic: impl IntoIterator<Item = impl Into<Topic>>,
-        data: impl Into<Vec<u8>>,
+        peer_id: PeerId,
+        subscriptions: Vec<FloodsubSubscription>,
+        mut sync: Option<FloodsubSubscriptionSync>,
     ) {
-        self.publish_many_inner(topic, data, true)
+        // The receiver only forgets about the topics missing from all our subscriptions once it
+        // received the last part of them.
+        let full = sync == Some(FloodsubSubscriptionSync::Full);
+        let mut chunk = Vec::new();
+        let mut chunk_len = 0;
+        for subscription in subscriptions {
+            let len = subscription.topic.id().len() + MAX_SUBSCRIPTION_OVERHEAD;
+            if !chunk.is_empty() && chunk_len + len > MAX_SUBSCRIPTIONS_LEN {
+                let chunk_sync = if full {
+                    Some(FloodsubSubscriptionSync::Partial)
+                } else {
+                    sync.take()
+                };
+                self.send_subscription_rpc(peer_id, std::mem::take(&mut chunk), chunk_sync);
+                chunk_len = 0;
+            }
+            chunk.push(subscription);
+            chunk_len += len;
+        }
+        self.send_subscription_rpc(peer_id, chunk, sync);
     }
 
-    /// Publishes a message with multiple topics to the network, even if we're not subscribed to any of the topics.
-    pub fn publish_many_any(
+    fn send_subscription_rpc(
         &mut self,
-        topic: impl IntoIterator<Item = impl Into<Topic>>,
-        data: impl Into<Vec<u8>>,
+        peer_id: PeerId,
+        subscriptions: Vec<FloodsubSubscription>,
+        sync: Option<FloodsubSubscriptionSync>,
     ) {
-        self.publish_many_inner(topic, data, false)
+        let rpc = FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions,
+            sync,
+        };
+        if self.config.record_rpc_sizes {
+            let size = rpc.encoded_len();
+            self.metrics.subscription_rpc_sizes.observe(size);
+            #[cfg(feature = "metrics")]
+            recorder::subscription_rpc_size(size);
+        }
+        self.push_event(
+            NetworkBehaviourAction::NotifyHandler {
+                peer_id,
+                handler: NotifyHandler::Any,
+                event: rpc,
+            },
+            false,
+        );
     }
 
-    fn publish_many_inner(
-        &mut self,
-        topic: impl IntoIterator<Item = impl Into<Topic>>,
-        data: impl Into<Vec<u8>>,
-        check_self_subscriptions: bool,
-    ) {
-        let message = FloodsubMessage {
-            source: self.config.local_peer_id,
-            data: data.into(),
-            // If the sequence numbers are predictable, then an attacker could flood the network
-            // with packets with the predetermined sequence numbers and absorb our legitimate
-            // messages. We therefore use a random number.
-            sequence_number: rand::random::<[u8; 20]>().to_vec(),
-            topics: topic.into_iter().map(Into::into).collect(),
-        };
+    /// Performs all the time-based work that is due at `now`.
+    fn on_tick(&mut self, now: Instant) {
+        if let Some(cache) = self.duplicate_cache.as_mut() {
+            cache.expire(now);
+        }
 
-        let self_subscribed = self
-            .subscribed_topics
-            .iter()
-            .any(|t| message.topics.iter().any(|u| t == u));
-        if self_subscribed {
-            if let Err(e @ CuckooError::NotEnoughSpace) = self.received.add(&message) {
-                warn!(
-                    "Message was added to 'received' Cuckoofilter but some \
-                     other message was removed as a consequence: {}",
-                    e,
-                );
+        if matches!(self.pending_subscriptions_deadline, Some(deadline) if deadline <= now) {
+            self.flush_pending_subscriptions();
+        }
+
+        if matches!(self.startup_announce_deadline, Some(deadline) if deadline <= now) {
+            self.startup_announce_deadline = None;
+            for peer_id in self.connected_peers.keys().copied().collect::<Vec<_>>() {
+                if !self.flapping_peers.contains_key(&peer_id) {
+                    self.send_subscription_dump(peer_id);
+                }
             }
-            if self.config.subscribe_local_messages {
-                self.events.push_back(NetworkBehaviourAction::GenerateEvent(
-                    FloodsubEvent::Message(message.clone()),
-                ));
+        }
+
+        if matches!(self.next_subscription_sync, Some(sync) if sync <= now) {
+            self.send_subscription_digests();
+            self.next_subscription_sync = self
+                .config
+                .subscription_sync_interval
+                .map(|interval| now + interval);
+        }
+
+        if let Some(max) = self.config.max_deliveries_per_topic {
+            let window_len = self.config.delivery_rate_window;
+            let mut released = Vec::new();
+            self.delivery_windows.retain(|_, window| {
+                if now.duration_since(window.start) < window_len {
+                    return true;
+                }
+                window.start = now;
+                let count = max.min(window.buffered.len());
+                window.delivered = count;
+                released.extend(window.buffered.drain(..count));
+                // Forget about the topics without pending messages. Their next message opens a
+                // new window.
+                count > 0
+            });
+            for message in released {
+                self.emit_message(message);
             }
         }
-        // Don't publish the message if we have to check subscriptions
-        // and we're not subscribed ourselves to any of the topics.
+
+        if let Some(max) = self.config.max_subscription_updates_per_peer {
+            let window_len = self.config.subscription_update_window;
+            let mut released = Vec::new();
+            self.subscription_rates.retain(|peer_id, rate| {
+                if now.duration_since(rate.start) < window_len {
+                    return true;
+                }
+                rate.start = now;
+                let count = max.min(rate.coalesced.len());
+                rate.updates = count;
+                if count > 0 {
+                    released.push((*peer_id, rate.coalesced.drain(..count).collect::<Vec<_>>()));
+                }
+                count > 0
+            });
+            for (peer_id, subscriptions) in released {
+                self.apply_subscriptions(peer_id, subscriptions);
+            }
+        }
+
+        self.flush_outbound_batches(now);
+
+        if self.config.unsubscribe_idle_topics {
+            let timeout = self.config.idle_topic_timeout;
+            let idle = self
+                .topic_activity
+                .iter()
+                .filter(|(_, last_activity)| now.duration_since(**last_activity) >= timeout)
+                .map(|(topic, _)| topic.clone())
+                .collect::<Vec<_>>();
+            for topic in idle {
+                debug!("Unsubscribing from idle topic {:?}", topic.id());
+                self.unsubscribe(topic.clone());
+                self.push_event(
+                    NetworkBehaviourAction::GenerateEvent(FloodsubEvent::TopicIdle { topic }),
+                    false,
+                );
+            }
+        }
+
+        if matches!(self.next_resumable_sweep, Some(sweep) if sweep <= now) {
+            self.resumable_peers.retain(|_, state| state.expires > now);
+            self.next_resumable_sweep = self
+                .resumable_peers
+                .values()
+                .map(|state| state.expires)
+                .min();
+        }
+
+        let stable_peers = self
+            .flapping_peers
+            .iter()
+            .filter(|(_, cooldown_end)| **cooldown_end <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        // Forget about the peers that didn't connect within the window.
+        let flap_window = self.config.flap_window;
+        self.connection_history.retain(
+            |_, history| matches!(history.back(), Some(t) if now.duration_since(*t) < flap_window),
+        );
+
+        for peer_id in stable_peers {
+            self.flapping_peers.remove(&peer_id);
+            if self.connected_peers.contains_key(&peer_id) {
+                self.send_subscription_dump(peer_id);
+            }
+        }
+
+        if matches!(self.next_sequence_tracking_sweep, Some(sweep) if sweep <= now) {
+            self.highest_sequence_numbers
+                .retain(|_, (_, expiry)| *expiry > now);
+            self.next_sequence_tracking_sweep = self
+                .highest_sequence_numbers
+                .values()
+                .map(|(_, expiry)| *expiry)
+                .min();
+        }
+
+        self.publish_due_messages(now);
+    }
+
+    /// Performs the periodic maintenance that is due right away, e.g. flushing debounced
+    /// subscriptions and batches, expiring tracked state or releasing rate-limited messages,
+    /// instead of waiting for the timer driven by polling.
+    ///
+    /// The resulting events are yielded by the next poll.
+    pub fn run_housekeeping(&mut self) {
+        self.on_tick(Instant::now());
+    }
+
+    /// Warns if events were left queued for [`FloodsubConfig::idle_check_interval`] or longer
+    /// without the task that polls us being woken, see [`Floodsub::wake`].
+    ///
+    /// Only called when polling, since the timer doesn't run while there is nothing to do, so
+    /// that the warning is emitted once something else wakes the task up.
+    fn check_idle(&mut self, now: Instant) {
+        let interval = match self.config.idle_check_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let idle = match self.unwoken_since {
+            Some(since) => now.saturating_duration_since(since),
+            None => return,
+        };
+        if self.events.is_empty() || idle < interval {
+            return;
+        }
+
+        let mut warnings = vec![format!(
+            "{} events were queued without waking the task, which wasn't polled for {idle:?}",
+            self.events.len()
+        )];
+        let orphaned = self
+            .events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    NetworkBehaviourAction::NotifyHandler { peer_id, .. }
+                        if !self.connected_peers.contains_key(peer_id)
+                )
+            })
+            .count();
+        if orphaned > 0 {
+            warnings.push(format!("{orphaned} RPCs are queued for disconnected peers"));
+        }
+        for warning in warnings {
+            warn!("{warning}");
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Warning(warning)),
+                false,
+            );
+        }
+    }
+
+    /// Returns the earliest point in time at which [`Floodsub::on_tick`] has work to do.
+    fn next_deadline(&self) -> Option<Instant> {
+        [
+            self.pending_subscriptions_deadline,
+            self.next_sequence_tracking_sweep,
+            self.next_resumable_sweep,
+            self.duplicate_cache
+                .as_ref()
+                .and_then(TimeCache::next_expiry),
+            self.topic_activity
+                .values()
+                .min()
+                .map(|last_activity| *last_activity + self.config.idle_topic_timeout),
+            self.outbound_batches
+                .values()
+                .map(|(_, deadline)| *deadline)
+                .min(),
+            self.flapping_peers.values().min().copied(),
+            self.connection_history
+                .values()
+                .filter_map(VecDeque::back)
+                .min()
+                .map(|connected| *connected + self.config.flap_window),
+            self.next_subscription_sync,
+            self.startup_announce_deadline,
+            self.delivery_windows
+                .values()
+                .filter(|window| !window.buffered.is_empty())
+                .map(|window| window.start + self.config.delivery_rate_window)
+                .min(),
+            self.subscription_rates
+                .values()
+                .filter(|rate| !rate.coalesced.is_empty())
+                .map(|rate| rate.start + self.config.subscription_update_window)
+                .min(),
+            self.scheduled_messages.keys().next().copied(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    /// Returns the topics we are subscribed to, including the ones whose subscription isn't
+    /// announced or acknowledged yet.
+    pub fn topics(&self) -> impl Iterator<Item = &Topic> {
+        self.subscribed_topics.iter()
+    }
+
+    /// Returns true if we are subscribed to a topic, see [`Floodsub::topics`].
+    pub fn is_subscribed(&self, topic: &Topic) -> bool {
+        self.subscribed_topics.contains(topic)
+    }
+
+    /// Returns the label of a topic we are subscribed to, as given when subscribing, see
+    /// [`Topic::with_label`].
+    ///
+    /// Only the id of `topic` is looked up, so that the label of the topic of a received message
+    /// can be recovered.
+    pub fn topic_label(&self, topic: &Topic) -> Option<&str> {
+        self.subscribed_topics
+            .iter()
+            .find(|t| *t == topic)
+            .and_then(Topic::label)
+    }
+
+    /// Returns, for each of the given topics, whether we are subscribed to it.
+    ///
+    /// The returned list has the same length and ordering as `topics`.
+    pub fn subscription_status(&self, topics: &[Topic]) -> Vec<bool> {
+        topics
+            .iter()
+            .map(|topic| self.subscribed_topics.iter().any(|t| t == topic))
+            .collect()
+    }
+
+    /// Returns the number of connected peers subscribed to a topic, in constant time.
+    pub fn subscriber_count(&self, topic: &Topic) -> usize {
+        self.subscriber_counts.get(topic).copied().unwrap_or(0)
+    }
+
+    /// Returns the connected peers subscribed to a topic.
+    pub fn peers_subscribed_to<'a>(&'a self, topic: &'a Topic) -> impl Iterator<Item = &'a PeerId> {
+        self.connected_peers
+            .iter()
+            .filter(move |(_, topics)| topics.contains(topic))
+            .map(|(peer_id, _)| peer_id)
+    }
+
+    /// Returns the peers we are connected to, whether or not they sent us their subscriptions.
+    pub fn connected_peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.connected_peers.keys()
+    }
+
+    /// Returns the number of peers we are connected to.
+    pub fn num_connected_peers(&self) -> usize {
+        self.connected_peers.len()
+    }
+
+    /// Records that a connected peer is subscribed to a topic, as if it sent us the subscription,
+    /// e.g. to bootstrap from another source of truth. Does nothing if we're not connected to
+    /// the peer.
+    pub fn add_peer_subscription(&mut self, peer_id: PeerId, topic: Topic) {
+        self.set_peer_subscription(peer_id, topic, FloodsubSubscriptionAction::Subscribe);
+    }
+
+    /// Records that a connected peer is no longer subscribed to a topic, as if it sent us the
+    /// unsubscription. Does nothing if we're not connected to the peer.
+    pub fn remove_peer_subscription(&mut self, peer_id: &PeerId, topic: &Topic) {
+        self.set_peer_subscription(
+            *peer_id,
+            topic.clone(),
+            FloodsubSubscriptionAction::Unsubscribe,
+        );
+    }
+
+    fn set_peer_subscription(
+        &mut self,
+        peer_id: PeerId,
+        topic: Topic,
+        action: FloodsubSubscriptionAction,
+    ) {
+        if !self.connected_peers.contains_key(&peer_id) {
+            debug!("Not changing the subscriptions of {peer_id}, we are not connected to it");
+            return;
+        }
+        self.apply_subscriptions(peer_id, vec![FloodsubSubscription { action, topic }]);
+    }
+
+    /// Returns a cheap snapshot of the state of the behaviour, suitable for liveness probes.
+    pub fn health(&self) -> FloodsubHealth {
+        FloodsubHealth {
+            connected_peers: self.num_connected_peers(),
+            subscribed_topics: self.subscribed_topics.len(),
+            topics_with_subscribers: self.subscriber_counts.len(),
+            pending_events: self.events.len() + self.spill.as_ref().map_or(0, SpillQueue::len),
+            received_filter_len: self.received_cache_len(),
+        }
+    }
+
+    /// Returns the number of RPCs queued for a peer and not handed to its handler yet, to detect
+    /// a peer whose handler stalled. RPCs spilled to disk, see
+    /// [`FloodsubConfig::events_spill_threshold`], aren't counted.
+    pub fn pending_events_for(&self, peer_id: &PeerId) -> usize {
+        self.events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    NetworkBehaviourAction::NotifyHandler { peer_id: p, .. } if p == peer_id
+                )
+            })
+            .count()
+    }
+
+    /// Returns the number of messages currently tracked to detect duplicates, by the cache of
+    /// [`FloodsubConfig::duplicate_cache_time`] if set, or else by the probabilistic filter, whose
+    /// capacity is [`FloodsubConfig::cuckoo_filter_capacity`].
+    pub fn received_cache_len(&self) -> usize {
+        self.duplicate_cache
+            .as_ref()
+            .map_or_else(|| self.received.len(), TimeCache::len)
+    }
+
+    /// Forgets about all the messages received so far, so that they are processed again if they
+    /// are received again, e.g. after a long partition or between the iterations of a test.
+    pub fn clear_received_cache(&mut self) {
+        self.received = CuckooFilter::with_capacity(self.config.cuckoo_filter_capacity);
+        if let Some(cache) = self.duplicate_cache.as_mut() {
+            cache.clear();
+        }
+        self.recent_ids.clear();
+    }
+
+    /// Returns true if the behaviour has nothing left to do: polling it yields nothing, and
+    /// nothing will be yielded until it is called or receives an RPC or a connection event.
+    ///
+    /// A behaviour with periodic work, e.g. [`FloodsubConfig::subscription_sync_interval`] or
+    /// [`FloodsubConfig::unsubscribe_idle_topics`], doesn't become quiescent.
+    pub fn is_quiescent(&self) -> bool {
+        self.events.is_empty()
+            && self.spill.as_ref().map_or(true, SpillQueue::is_empty)
+            && self.deferred_messages.is_empty()
+            && self.next_deadline().is_none()
+    }
+
+    /// Returns how far back received messages are reliably remembered for detecting duplicates,
+    /// i.e. the age of the oldest message tracked by [`FloodsubConfig::duplicate_cache_time`] or,
+    /// if unset, by [`FloodsubConfig::exact_dedup_window`].
+    ///
+    /// The exact window shrinks as traffic grows. Returns `None` if the cache or window is
+    /// disabled or empty, since the horizon of the probabilistic filter used otherwise isn't
+    /// known.
+    pub fn effective_dedup_window(&self) -> Option<Duration> {
+        match &self.duplicate_cache {
+            Some(cache) => cache.oldest(),
+            None => self.recent_ids.oldest(),
+        }
+        .map(|oldest| Instant::now().saturating_duration_since(oldest))
+    }
+
+    /// Returns the statistics collected by the behaviour.
+    pub fn metrics(&self) -> &FloodsubMetrics {
+        &self.metrics
+    }
+
+    /// Returns the message counts of a topic, if
+    /// [`FloodsubConfig::track_topic_stats`] is set and any message of the topic was seen.
+    pub fn topic_stats(&self, topic: &Topic) -> Option<&TopicStats> {
+        self.metrics.topics.get(topic)
+    }
+
+    /// Publishes a message to the network, if we're subscribed to the topic only, unless
+    /// [`FloodsubConfig::allow_publish_without_subscription`] is set.
+    pub fn publish(
+        &mut self,
+        topic: impl Into<Topic>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), PublishError> {
+        self.publish_many(iter::once(topic), data).map(|_| ())
+    }
+
+    /// Publishes a message to the network, if we're subscribed to the topic only.
+    ///
+    /// On success, returns the peers for which a message had to be dropped because their
+    /// outbound queue was full or they exceeded their rate limit, see
+    /// [`FloodsubConfig::max_queued_rpcs_per_peer`] and
+    /// [`FloodsubConfig::max_messages_per_peer_per_sec`]. Publishers may want to slow down when
+    /// this is not empty.
+    pub fn try_publish(
+        &mut self,
+        topic: impl Into<Topic>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<Vec<PeerId>, PublishError> {
+        self.publish_many_inner(iter::once(topic), data, None, true, None)
+            .map(|published| published.dropped)
+    }
+
+    /// Publishes a message to the network, if we're subscribed to the topic only, sending it only
+    /// to those of the given peers that are subscribed to the topic.
+    ///
+    /// The [`TopicRouter`] is bypassed. The message is still recorded as seen, so that it isn't
+    /// processed again if the receivers relay it to other peers, and back to us.
+    pub fn publish_to(
+        &mut self,
+        topic: impl Into<Topic>,
+        data: impl Into<Vec<u8>>,
+        peers: &[PeerId],
+    ) -> Result<(), PublishError> {
+        self.publish_many_inner(iter::once(topic), data, None, true, Some(peers))
+            .map(|_| ())
+    }
+
+    /// Publishes a message to the network like [`Floodsub::publish`], with the given sequence
+    /// number instead of the next one of our counter.
+    ///
+    /// The message is recorded as seen with this sequence number, so that receiving it again, or
+    /// a message of the same source reusing the sequence number, is detected as a duplicate.
+    pub fn publish_with_sequence(
+        &mut self,
+        topic: impl Into<Topic>,
+        data: impl Into<Vec<u8>>,
+        sequence_number: Vec<u8>,
+    ) -> Result<(), PublishError> {
+        self.publish_many_inner(iter::once(topic), data, Some(sequence_number), true, None)
+            .map(|_| ())
+    }
+
+    /// Publishes a message to the network like [`Floodsub::publish`] once `at` is reached, e.g. to
+    /// coordinate broadcasts. Messages due at the same time are published in the order they were
+    /// scheduled.
+    ///
+    /// Whether we're subscribed to the topic and any peer is, is only checked once the message is
+    /// due; if the message can't be published then, a [`FloodsubEvent::ScheduledPublishFailed`] is
+    /// emitted.
+    pub fn publish_at(&mut self, topic: impl Into<Topic>, data: impl Into<Vec<u8>>, at: Instant) {
+        self.scheduled_messages
+            .entry(at)
+            .or_default()
+            .push((topic.into(), data.into()));
+        // The timer of the task polling us may have to fire sooner.
+        self.wake();
+    }
+
+    /// Publishes the messages scheduled with [`Floodsub::publish_at`] that are due at `now`.
+    pub(crate) fn publish_due_messages(&mut self, now: Instant) {
+        let due = self
+            .scheduled_messages
+            .range(..=now)
+            .map(|(at, _)| *at)
+            .collect::<Vec<_>>();
+        let messages = due
+            .iter()
+            .filter_map(|at| self.scheduled_messages.remove(at))
+            .flatten()
+            .collect::<Vec<_>>();
+        for (topic, data) in messages {
+            if let Err(error) = self.publish(topic.clone(), data) {
+                debug!(
+                    "Failed to publish scheduled message on {:?}: {error}",
+                    topic.id()
+                );
+                self.push_event(
+                    NetworkBehaviourAction::GenerateEvent(FloodsubEvent::ScheduledPublishFailed {
+                        topic,
+                        error,
+                    }),
+                    false,
+                );
+            }
+        }
+    }
+
+    /// Publishes a message to the network, even if we're not subscribed to the topic.
+    pub fn publish_any(
+        &mut self,
+        topic: impl Into<Topic>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), PublishError> {
+        self.publish_many_any(iter::once(topic), data)
+    }
+
+    /// Publishes a message with multiple topics to the network.
+    ///
+    /// Fails with [`PublishError::NotSubscribed`] if we're not subscribed to any of the topics,
+    /// unless [`FloodsubConfig::allow_publish_without_subscription`] is set, and with
+    /// [`PublishError::NoTopics`] if there is no topic. Nothing is published if it fails.
+    ///
+    /// On success, returns the number of distinct peers the message was queued for, which doesn't
+    /// include the peers it was dropped for, see [`Floodsub::try_publish`].
+    pub fn publish_many(
+        &mut self,
+        topic: impl IntoIterator<Item = impl Into<Topic>>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<usize, PublishError> {
+        self.publish_many_inner(topic, data, None, true, None)
+            .map(|published| published.queued)
+    }
+
+    /// Publishes a message with multiple topics to the network, even if we're not subscribed to any of the topics.
+    pub fn publish_many_any(
+        &mut self,
+        topic: impl IntoIterator<Item = impl Into<Topic>>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(), PublishError> {
+        self.publish_many_inner(topic, data, None, false, None)
+            .map(|_| ())
+    }
+
+    fn publish_many_inner(
+        &mut self,
+        topic: impl IntoIterator<Item = impl Into<Topic>>,
+        data: impl Into<Vec<u8>>,
+        sequence_number: Option<Vec<u8>>,
+        check_self_subscriptions: bool,
+        only_peers: Option<&[PeerId]>,
+    ) -> Result<Published, PublishError> {
+        let topics = topic.into_iter().map(Into::into).collect();
+        self.publish_inner(check_self_subscriptions, only_peers, |floodsub| {
+            floodsub.build_message(topics, data.into(), sequence_number)
+        })
+    }
+
+    /// Publishes a message built by another node, e.g. received through another transport, as
+    /// is: unlike [`Floodsub::publish`], its source, sequence number and topics are kept, and it
+    /// isn't signed nor compressed.
+    ///
+    /// Fails with [`PublishError::Duplicate`] if the message was already published or received,
+    /// and with [`PublishError::NotSubscribed`] if we're not subscribed to any of its topics,
+    /// unless [`FloodsubConfig::allow_publish_without_subscription`] is set.
+    pub fn publish_message(&mut self, message: FloodsubMessage) -> Result<(), PublishError> {
+        self.publish_inner(true, None, |floodsub| {
+            if message.topics.is_empty() {
+                debug!("Not publishing message of {} without topic", message.source);
+                return Err(PublishError::NoTopics);
+            }
+            if message.data.len() > floodsub.config.max_transmit_size {
+                debug!(
+                    "Not publishing message of {} bytes, over the maximum of {} bytes",
+                    message.data.len(),
+                    floodsub.config.max_transmit_size
+                );
+                return Err(PublishError::MessageTooLarge);
+            }
+            if floodsub.is_known(&message) {
+                debug!(
+                    "Not publishing message of {} received before",
+                    message.source
+                );
+                return Err(PublishError::Duplicate);
+            }
+            Ok(message)
+        })
+        .map(|_| ())
+    }
+
+    /// Builds a message we publish, with the next sequence number of our counter unless one is
+    /// given.
+    fn build_message(
+        &mut self,
+        topics: Vec<Topic>,
+        data: Vec<u8>,
+        sequence_number: Option<Vec<u8>>,
+    ) -> Result<FloodsubMessage, PublishError> {
+        if topics.is_empty() {
+            debug!("Not publishing message without topic");
+            return Err(PublishError::NoTopics);
+        }
+        if data.len() > self.config.max_transmit_size {
+            debug!(
+                "Not publishing message of {} bytes, over the maximum of {} bytes",
+                data.len(),
+                self.config.max_transmit_size
+            );
+            return Err(PublishError::MessageTooLarge);
+        }
+
+        let (data, compression) = self.compress(data);
+
+        let mut message = FloodsubMessage {
+            source: self.publish_source,
+            data,
+            sequence_number: match sequence_number {
+                Some(sequence_number) => sequence_number,
+                None => self.next_sequence_number(),
+            },
+            topics,
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: self.config.default_ttl,
+            compression,
+        };
+        if let MessageAuthenticity::Signed(keypair) = &self.config.message_authenticity {
+            let signature = keypair
+                .sign(&message.signing_bytes())
+                .map_err(|_| PublishError::SigningFailed)?;
+            message.signature = Some(signature);
+            if inline_public_key(&message.source).is_none() {
+                message.key = Some(keypair.public().to_protobuf_encoding());
+            }
+        }
+        Ok(message)
+    }
+
+    /// Publishes the message returned by `build`, once checked that we can publish.
+    fn publish_inner(
+        &mut self,
+        mut check_self_subscriptions: bool,
+        only_peers: Option<&[PeerId]>,
+        build: impl FnOnce(&mut Self) -> Result<FloodsubMessage, PublishError>,
+    ) -> Result<Published, PublishError> {
+        if self.shutting_down {
+            debug!("Not publishing message as we are shutting down");
+            return match self.config.calls_after_shutdown {
+                CallsAfterShutdown::Fail => Err(PublishError::ShuttingDown),
+                CallsAfterShutdown::Ignore => Ok(Published::default()),
+            };
+        }
+        if self.events_full() {
+            debug!("Not publishing message as too many events are pending");
+            return Err(PublishError::Backpressure);
+        }
+        if self.config.allow_publish_without_subscription {
+            check_self_subscriptions = false;
+        }
+        match self.config.role {
+            Role::Full => {}
+            Role::PublishOnly => {
+                // We can't subscribe to the topics we publish to.
+                check_self_subscriptions = false;
+            }
+            Role::SubscribeOnly => return Err(PublishError::SubscribeOnly),
+            Role::Relay => return Err(PublishError::Relay),
+        }
+
+        let message = build(self)?;
+        let rpc = FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        };
+        if rpc.encoded_len() > MAX_MESSAGE_LEN_BYTES {
+            return Err(PublishError::MessageTooLarge);
+        }
+        let message = rpc.messages.into_iter().next().expect("one message");
+
+        let self_subscribed = self.is_subscribed_to_any(&message);
+        // Don't publish the message if we have to check subscriptions
+        // and we're not subscribed ourselves to any of the topics.
         if check_self_subscriptions && !self_subscribed {
+            return Err(PublishError::NotSubscribed);
+        }
+
+        let parts = self.split_by_topic(message.clone());
+        let mut targets = Vec::with_capacity(parts.len());
+        for part in &parts {
+            targets.push(match only_peers {
+                Some(peers) => self
+                    .subscribed_peers(part, None)
+                    .into_iter()
+                    .filter(|peer_id| peers.contains(peer_id))
+                    .collect(),
+                None => self.forward_targets(part, None),
+            });
+        }
+        // Messages delivered to us are published even if no peer is subscribed.
+        let delivered_locally = self.config.subscribe_local_messages
+            && parts.iter().any(|part| self.is_subscribed_to_any(part));
+        if targets.iter().all(Vec::is_empty) && !delivered_locally {
+            return Err(PublishError::NoPeers);
+        }
+
+        if self_subscribed {
+            if let Some(cache) = self.duplicate_cache.as_mut() {
+                cache.test_and_add(
+                    &message.dedup_key(self.config.dedup_strategy),
+                    Instant::now(),
+                );
+            } else if let Err(CuckooError::NotEnoughSpace) = self
+                .received
+                .add(&message.dedup_key(self.config.dedup_strategy))
+            {
+                if self.config.filter_full_policy == FilterFullPolicy::Fail {
+                    // The message was added nonetheless, and another one evicted.
+                    self.received
+                        .delete(&message.dedup_key(self.config.dedup_strategy));
+                    debug!("Not publishing message, the duplicates filter is full");
+                    return Err(PublishError::FilterFull);
+                }
+                self.record_filter_eviction();
+            }
+        }
+
+        self.published_ids.insert(&message);
+        self.metrics.messages_published += 1;
+        #[cfg(feature = "metrics")]
+        recorder::message_published();
+        self.record_topic_stats(&message, |stats| stats.published += 1);
+        if self.config.track_unique_topics {
+            for topic in &message.topics {
+                self.metrics.unique_topics.observe(topic);
+            }
+        }
+        if self_subscribed {
+            self.record_topic_activity(&message);
+            self.recent_ids.insert(&message);
+        }
+
+        self.remember_recent(&message);
+        if parts.len() > 1 {
+            self.record_parts_received(&parts);
+        }
+
+        let mut queued = Vec::new();
+        let mut dropped = Vec::new();
+        for (message, targets) in parts.into_iter().zip(targets) {
+            if self.config.subscribe_local_messages && self.is_subscribed_to_any(&message) {
+                self.deliver(message.clone());
+            }
+
+            // Send to peers we know are subscribed to the topic.
+            self.record_fanout_clones(&message, targets.len());
+            for peer_id in targets {
+                if self.send_messages(peer_id, vec![message.clone()], Vec::new()) {
+                    if !queued.contains(&peer_id) {
+                        queued.push(peer_id);
+                    }
+                } else if !dropped.contains(&peer_id) {
+                    dropped.push(peer_id);
+                }
+            }
+        }
+        Ok(Published {
+            queued: queued.len(),
+            dropped,
+        })
+    }
+
+    /// Updates the [`TopicStats`] of each topic of `message`, if
+    /// [`FloodsubConfig::track_topic_stats`] is set.
+    fn record_topic_stats(&mut self, message: &FloodsubMessage, update: impl Fn(&mut TopicStats)) {
+        if self.config.track_topic_stats {
+            for topic in &message.topics {
+                update(self.metrics.topics.entry(topic.clone()).or_default());
+            }
+        }
+    }
+
+    /// Records the bytes of `message` cloned to send it to `targets` peers, if
+    /// [`FloodsubConfig::track_fanout_clones`] is set.
+    fn record_fanout_clones(&mut self, message: &FloodsubMessage, targets: usize) {
+        if self.config.track_fanout_clones {
+            let bytes = (message.data.len() * targets) as u64;
+            self.metrics.fanout_cloned_bytes += bytes;
+            #[cfg(feature = "metrics")]
+            recorder::fanout_cloned_bytes(bytes);
+        }
+    }
+
+    /// Returns true if `message` was published or received before, as far as the duplicates
+    /// filter or cache knows.
+    fn is_known(&self, message: &FloodsubMessage) -> bool {
+        let key = message.dedup_key(self.config.dedup_strategy);
+        match &self.duplicate_cache {
+            Some(cache) => cache.contains(&key),
+            None => self.received.contains(&key),
+        }
+    }
+
+    /// Returns true if we're subscribed to any of the topics of `message`, exactly or through a
+    /// [`TopicMatcher`].
+    fn is_subscribed_to_any(&self, message: &FloodsubMessage) -> bool {
+        message.topics.iter().any(|topic| {
+            self.subscribed_topics.contains(topic)
+                || self.topic_matchers.iter().any(|m| m.matches(topic))
+        })
+    }
+
+    /// Splits a message into the messages delivered and forwarded according to
+    /// [`FloodsubConfig::multi_topic_fanout`].
+    ///
+    /// With [`MultiTopicFanout::PerTopic`], the parts are recorded as received so that their
+    /// echoes are dropped as duplicates.
+    fn fan_out(&mut self, message: FloodsubMessage) -> Vec<FloodsubMessage> {
+        let parts = self.split_by_topic(message);
+        if parts.len() > 1 {
+            self.record_parts_received(&parts);
+        }
+        parts
+    }
+
+    /// Splits a message into one message for each of its topics if
+    /// [`FloodsubConfig::multi_topic_fanout`] is [`MultiTopicFanout::PerTopic`].
+    fn split_by_topic(&self, message: FloodsubMessage) -> Vec<FloodsubMessage> {
+        if self.config.multi_topic_fanout == MultiTopicFanout::Union || message.topics.len() <= 1 {
+            return vec![message];
+        }
+
+        let topics = message.topics.iter().fold(Vec::new(), |mut topics, topic| {
+            if !topics.contains(topic) {
+                topics.push(topic.clone());
+            }
+            topics
+        });
+        topics
+            .into_iter()
+            .map(|topic| FloodsubMessage {
+                topics: vec![topic],
+                ..message.clone()
+            })
+            .collect()
+    }
+
+    /// Records the parts of a split message as received, so that their echoes are dropped as
+    /// duplicates.
+    fn record_parts_received(&mut self, parts: &[FloodsubMessage]) {
+        for part in parts {
+            if let Some(cache) = self.duplicate_cache.as_mut() {
+                cache.test_and_add(&part.dedup_key(self.config.dedup_strategy), Instant::now());
+            } else if let Err(CuckooError::NotEnoughSpace) = self
+                .received
+                .test_and_add(&part.dedup_key(self.config.dedup_strategy))
+            {
+                self.record_filter_eviction();
+            }
+        }
+    }
+
+    /// Processes messages received from the network, along with the peer each was received
+    /// from: delivers them to us and forwards them to the subscribed peers.
+    ///
+    /// `announcements` are changes to our subscriptions to announce to all the connected peers,
+    /// which are sent in the same RPC as the messages forwarded to them.
+    fn process_messages(
+        &mut self,
+        messages: impl IntoIterator<Item = (PeerId, FloodsubMessage)>,
+        announcements: Vec<FloodsubSubscription>,
+    ) {
+        // List of messages we're going to propagate on the network, in a single RPC per peer
+        // along with our announcements, so that every peer gets at most one RPC per RPC received.
+        let mut rpcs_to_dispatch: Vec<(PeerId, FloodsubRpc)> = Vec::new();
+
+        for (propagation_source, message) in messages {
+            // Traced messages are only traced the first time they are processed.
+            let trace_id = if self.traced_messages.is_empty() {
+                None
+            } else {
+                Some(message.id()).filter(|id| self.traced_messages.remove(id))
+            };
+            self.trace(&trace_id, TraceStep::Received { propagation_source });
+            self.metrics.messages_received += 1;
+            #[cfg(feature = "metrics")]
+            recorder::message_received();
+            self.record_topic_stats(&message, |stats| stats.received += 1);
+
+            if message.data.len() > self.config.max_transmit_size {
+                debug!(
+                    "Dropping message of {} bytes from {propagation_source}, over the maximum of {} bytes",
+                    message.data.len(),
+                    self.config.max_transmit_size
+                );
+                self.trace(&trace_id, TraceStep::TooLarge);
+                continue;
+            }
+
+            if message.topics.is_empty() {
+                // It can't be delivered nor forwarded, don't waste room in the duplicates filter.
+                debug!("Dropping message without topic from {propagation_source}");
+                self.trace(&trace_id, TraceStep::NoTopics);
+                continue;
+            }
+
+            if matches!(
+                self.config.message_authenticity,
+                MessageAuthenticity::Signed(_)
+            ) && !message.verify_signature()
+            {
+                debug!("Dropping message from {propagation_source} with an invalid signature");
+                self.trace(&trace_id, TraceStep::InvalidSignature);
+                continue;
+            }
+
+            if self.config.track_unique_topics {
+                for topic in &message.topics {
+                    self.metrics.unique_topics.observe(topic);
+                }
+            }
+
+            if message.source == self.publish_source && self.published_ids.contains(&message) {
+                // We sent it to all the peers subscribed to its topics already.
+                debug!("Ignoring message of ours echoed by {propagation_source}");
+                self.trace(&trace_id, TraceStep::Echo);
+                continue;
+            }
+
+            // Messages claiming to be ours that we didn't publish would loop back otherwise.
+            if message.source == self.publish_source {
+                debug!("Received message from {propagation_source} spoofing our peer id");
+                match self.config.spoofed_source_policy {
+                    SpoofedSourcePolicy::Drop => {}
+                    SpoofedSourcePolicy::DropAndPenalize { penalty } => {
+                        self.penalize(propagation_source, penalty)
+                    }
+                    SpoofedSourcePolicy::DropAndReport => self.push_event(
+                        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SpoofedSource {
+                            peer_id: propagation_source,
+                        }),
+                        false,
+                    ),
+                }
+                self.trace(&trace_id, TraceStep::SpoofedSource);
+                continue;
+            }
+
+            if let Some(validator) = &self.message_validator {
+                if !validator(&propagation_source, &message) {
+                    debug!(
+                        "Dropping message of {} from {propagation_source} failing validation",
+                        message.source
+                    );
+                    self.trace(&trace_id, TraceStep::Invalid);
+                    continue;
+                }
+            }
+
+            let failed = (0..self.message_checks.len()).find(|i| match self.message_checks[*i] {
+                MessageCheck::Dedup => !self.check_duplicate(&message),
+                MessageCheck::SequenceMonotonicity => {
+                    !self.check_sequence_number(&message, propagation_source)
+                }
+            });
+            if let Some(i) = failed {
+                let step = match self.message_checks[i] {
+                    MessageCheck::Dedup => TraceStep::Duplicate,
+                    MessageCheck::SequenceMonotonicity => TraceStep::StaleSequenceNumber,
+                };
+                self.trace(&trace_id, step);
+                continue;
+            }
+
+            self.remember_recent(&message);
+
+            for mut message in self.fan_out(message) {
+                // Add the message to be dispatched to the user.
+                let self_subscribed = self.is_subscribed_to_any(&message);
+                if self_subscribed {
+                    self.record_topic_activity(&message);
+                    self.trace(
+                        &trace_id,
+                        TraceStep::Delivered {
+                            topics: message.topics.clone(),
+                        },
+                    );
+                    self.deliver(message.clone());
+                }
+
+                // Propagate the message to everyone else who is subscribed to any of the topics,
+                // unless it went through as many hops as allowed.
+                message.ttl = message.ttl.saturating_sub(1);
+                let targets = if message.ttl == 0 {
+                    debug!(
+                        "Not forwarding message of {} whose TTL expired",
+                        message.source
+                    );
+                    self.trace(&trace_id, TraceStep::TtlExpired);
+                    Vec::new()
+                } else {
+                    self.forward_targets(&message, Some(&propagation_source))
+                };
+                self.trace(
+                    &trace_id,
+                    TraceStep::Forwarded {
+                        topics: message.topics.clone(),
+                        peers: targets.clone(),
+                    },
+                );
+                if !self_subscribed && !targets.is_empty() {
+                    self.metrics.messages_relayed += 1;
+                    #[cfg(feature = "metrics")]
+                    recorder::message_relayed();
+                }
+                if !self_subscribed && targets.is_empty() && self.config.emit_undeliverable_messages
+                {
+                    self.push_event(
+                        NetworkBehaviourAction::GenerateEvent(
+                            FloodsubEvent::MessageUndeliverable {
+                                message_id: message.id(),
+                                topics: message.topics.clone(),
+                            },
+                        ),
+                        false,
+                    );
+                }
+                if self.config.record_provenance
+                    && !targets.is_empty()
+                    && message.provenance.len() < self.config.max_provenance_len
+                {
+                    message.provenance.push(self.config.local_peer_id);
+                }
+                self.record_fanout_clones(&message, targets.len());
+                if !targets.is_empty() {
+                    self.record_topic_stats(&message, |stats| stats.forwarded += 1);
+                }
+                self.metrics.messages_propagated += targets.len() as u64;
+                #[cfg(feature = "metrics")]
+                recorder::messages_propagated(targets.len() as u64);
+                for peer_id in targets {
+                    if let Some(pos) = rpcs_to_dispatch.iter().position(|(p, _)| *p == peer_id) {
+                        rpcs_to_dispatch[pos].1.messages.push(message.clone());
+                    } else {
+                        rpcs_to_dispatch.push((
+                            peer_id,
+                            FloodsubRpc {
+                                subscriptions: Vec::new(),
+                                messages: vec![message.clone()],
+                                sync: None,
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+
+        if !announcements.is_empty() {
+            for peer_id in self.connected_peers.keys() {
+                match rpcs_to_dispatch.iter_mut().find(|(p, _)| p == peer_id) {
+                    Some((_, rpc)) => rpc.subscriptions = announcements.clone(),
+                    None => rpcs_to_dispatch.push((
+                        *peer_id,
+                        FloodsubRpc {
+                            subscriptions: announcements.clone(),
+                            messages: Vec::new(),
+                            sync: None,
+                        },
+                    )),
+                }
+            }
+        }
+        for (peer_id, rpc) in rpcs_to_dispatch {
+            self.send_messages(peer_id, rpc.messages, rpc.subscriptions);
+        }
+        // Make room for the messages delivered to the application.
+        self.make_room_for_events();
+    }
+
+    /// Emits a [`FloodsubEvent::Trace`] for every decision made about the next message received
+    /// with the given id, e.g. to find out why it wasn't delivered. Tracing stops once that
+    /// message is processed; later copies of it aren't traced.
+    pub fn trace_message(&mut self, message_id: MessageId) {
+        self.traced_messages.insert(message_id);
+    }
+
+    /// Emits a [`FloodsubEvent::Trace`] if the message being processed is traced.
+    fn trace(&mut self, message_id: &Option<MessageId>, step: TraceStep) {
+        if let Some(message_id) = message_id {
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Trace {
+                    message_id: message_id.clone(),
+                    step,
+                }),
+                false,
+            );
+        }
+    }
+
+    /// Returns true if `message` wasn't received before, and records it as received.
+    fn check_duplicate(&mut self, message: &FloodsubMessage) -> bool {
+        if let Some(cache) = self.duplicate_cache.as_mut() {
+            let added = cache.test_and_add(
+                &message.dedup_key(self.config.dedup_strategy),
+                Instant::now(),
+            );
+            if !added {
+                self.metrics.messages_deduplicated += 1;
+                #[cfg(feature = "metrics")]
+                recorder::duplicate_message();
+            }
+            return added;
+        }
+
+        // Use `self.received` to skip the messages that we have already received in the past.
+        // Note that this can result in false positives.
+        match self
+            .received
+            .test_and_add(&message.dedup_key(self.config.dedup_strategy))
+        {
+            Ok(true) => {} // Message  was added.
+            // Message already existed, or is a false positive that we can detect.
+            Ok(false) => {
+                // The exact window tracks message ids, which don't identify messages by content.
+                if self.recent_ids.is_disabled()
+                    || self.config.dedup_strategy == DedupStrategy::ContentHash
+                    || self.recent_ids.contains(message)
+                {
+                    self.metrics.messages_deduplicated += 1;
+                    #[cfg(feature = "metrics")]
+                    recorder::duplicate_message();
+                    return false;
+                }
+                self.metrics.dedup_false_positives += 1;
+                #[cfg(feature = "metrics")]
+                recorder::dedup_false_positive();
+            }
+            // Message added, but some other removed. Deliver it nonetheless.
+            Err(CuckooError::NotEnoughSpace) => self.record_filter_eviction(),
+        }
+
+        self.recent_ids.insert(message);
+        true
+    }
+
+    /// Records that recording a message in `self.received` evicted another message, which is
+    /// then processed again if it is received again.
+    fn record_filter_eviction(&mut self) {
+        self.metrics.filter_evictions += 1;
+        #[cfg(feature = "metrics")]
+        recorder::filter_eviction();
+        warn!(
+            "Duplicates filter is full, a message was evicted to record another one; \
+             consider increasing `FloodsubConfig::cuckoo_filter_capacity`"
+        );
+    }
+
+    /// Returns false if the sequence number of `message` isn't higher than the one of a previous
+    /// message of the same source, if [`FloodsubConfig::validate_sequence_monotonicity`] is set.
+    fn check_sequence_number(
+        &mut self,
+        message: &FloodsubMessage,
+        propagation_source: PeerId,
+    ) -> bool {
+        if !self.config.validate_sequence_monotonicity || self.check_sequence_monotonicity(message)
+        {
+            return true;
+        }
+
+        debug!(
+            "Dropping message from {} with non-increasing sequence number",
+            message.source
+        );
+        self.push_event(
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SuspiciousSource {
+                source: message.source,
+                propagation_source,
+            }),
+            false,
+        );
+        false
+    }
+
+    /// Processes up to `budget` of the messages deferred because of
+    /// [`FloodsubConfig::message_processing_budget`].
+    fn process_deferred_messages(&mut self, budget: usize) {
+        // Always make progress, even with a budget of zero.
+        let count = budget.max(1).min(self.deferred_messages.len());
+        let messages = self.deferred_messages.drain(..count).collect::<Vec<_>>();
+        self.process_messages(messages, Vec::new());
+    }
+
+    /// Sends the given messages to a peer, either right away or, if a flush interval applies to
+    /// the peer, as part of the next batch. Changes to our subscriptions are sent right away, in
+    /// the same RPC as the messages if possible.
+    ///
+    /// Returns `false` if a message had to be dropped because the peer exceeded its rate limit or
+    /// its outbound queue is full, see [`Floodsub::rate_limit`] and [`Floodsub::queue_messages`].
+    fn send_messages(
+        &mut self,
+        peer_id: PeerId,
+        mut messages: Vec<FloodsubMessage>,
+        subscriptions: Vec<FloodsubSubscription>,
+    ) -> bool {
+        let within_limit = self.rate_limit(peer_id, &mut messages) == 0;
+        if messages.is_empty() {
+            if !subscriptions.is_empty() {
+                self.send_subscriptions(peer_id, subscriptions);
+            }
+            return within_limit;
+        }
+        let interval = self
+            .peer_flush_intervals
+            .get(&peer_id)
+            .copied()
+            .or(self.config.rpc_flush_interval)
+            .filter(|interval| !interval.is_zero());
+        match interval {
+            Some(interval) => {
+                let batch = self
+                    .outbound_batches
+                    .entry(peer_id)
+                    .or_insert_with(|| (Vec::new(), Instant::now() + interval));
+                batch.0.extend(messages);
+                if !subscriptions.is_empty() {
+                    self.send_subscriptions(peer_id, subscriptions);
+                }
+                within_limit
+            }
+            None => self.queue_messages(peer_id, messages, subscriptions) && within_limit,
+        }
+    }
+
+    /// Removes the messages for a peer over [`FloodsubConfig::max_messages_per_peer_per_sec`],
+    /// taking a token from its bucket for each of the others.
+    ///
+    /// Returns the number of messages removed.
+    fn rate_limit(&mut self, peer_id: PeerId, messages: &mut Vec<FloodsubMessage>) -> usize {
+        let rate = match self.config.max_messages_per_peer_per_sec {
+            Some(rate) if !self.pinned_peers.contains(&peer_id) => rate,
+            _ => return 0,
+        };
+        let tokens = self
+            .rate_limit_tokens
+            .entry(peer_id)
+            .or_insert_with(|| f64::from(rate));
+        let allowed = (*tokens as usize).min(messages.len());
+        *tokens -= allowed as f64;
+        let excess = messages.len() - allowed;
+        if excess > 0 {
+            debug!("Dropping {excess} message(s) for {peer_id} over its rate limit");
+            messages.truncate(allowed);
+        }
+        excess
+    }
+
+    /// Refills the buckets of [`FloodsubConfig::max_messages_per_peer_per_sec`] for the time
+    /// elapsed since the last refill, and forgets about the full ones.
+    fn refill_rate_limits(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.rate_limit_refill);
+        self.rate_limit_refill = now;
+        let rate = match self.config.max_messages_per_peer_per_sec {
+            Some(rate) => f64::from(rate),
+            None => return,
+        };
+        let refill = elapsed.as_secs_f64() * rate;
+        self.rate_limit_tokens.retain(|_, tokens| {
+            *tokens = (*tokens + refill).min(rate);
+            *tokens < rate
+        });
+    }
+
+    /// Sends the batches of messages whose flush interval has elapsed.
+    fn flush_outbound_batches(&mut self, now: Instant) {
+        let due = self
+            .outbound_batches
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        for peer_id in due {
+            if let Some((messages, _)) = self.outbound_batches.remove(&peer_id) {
+                self.queue_messages(peer_id, messages, Vec::new());
+            }
+        }
+    }
+
+    /// Queues an RPC carrying the given messages and changes to our subscriptions for a peer.
+    ///
+    /// If the outbound queue of the peer is full, either the messages or the oldest ones queued
+    /// for the peer are dropped according to [`FloodsubConfig::queue_full_policy`]; `false` is
+    /// returned in that case. The subscriptions are sent anyway.
+    fn queue_messages(
+        &mut self,
+        peer_id: PeerId,
+        messages: Vec<FloodsubMessage>,
+        subscriptions: Vec<FloodsubSubscription>,
+    ) -> bool {
+        let has_room = match self.reserve_queued_rpc(peer_id) {
+            Some(has_room) => has_room,
+            None => {
+                if !subscriptions.is_empty() {
+                    self.send_subscriptions(peer_id, subscriptions);
+                }
+                return false;
+            }
+        };
+        let priority = messages
+            .iter()
+            .any(|message| self.priority_sources.contains(&message.source));
+        self.push_event(
+            NetworkBehaviourAction::NotifyHandler {
+                peer_id,
+                handler: NotifyHandler::Any,
+                event: FloodsubRpc {
+                    subscriptions,
+                    messages,
+                    sync: None,
+                },
+            },
+            priority,
+        );
+        has_room
+    }
+
+    /// Makes room for an RPC carrying messages in the outbound queue of a peer.
+    ///
+    /// Returns `None` if the RPC must be dropped, and whether the queue had room otherwise.
+    fn reserve_queued_rpc(&mut self, peer_id: PeerId) -> Option<bool> {
+        if self.events_full() {
+            debug!("Too many pending events, dropping message for {peer_id}");
+            return None;
+        }
+        let queued = self.queued_rpcs.entry(peer_id).or_default();
+        let mut has_room = true;
+        let max_queued = if self.pinned_peers.contains(&peer_id) {
+            None
+        } else {
+            self.config.max_queued_rpcs_per_peer
+        };
+        if let Some(max) = max_queued {
+            if *queued >= max {
+                has_room = false;
+                match self.config.queue_full_policy {
+                    QueueFullPolicy::DropNewest => {
+                        debug!("Outbound queue of {peer_id} is full, dropping message");
+                        return None;
+                    }
+                    QueueFullPolicy::DropOldest => {
+                        debug!("Outbound queue of {peer_id} is full, dropping oldest message");
+                        let oldest = self.events.iter().position(|event| {
+                            matches!(
+                                event,
+                                NetworkBehaviourAction::NotifyHandler { peer_id: p, event: rpc, .. }
+                                    if *p == peer_id
+                                        && !rpc.messages.is_empty()
+                                        && rpc.subscriptions.is_empty()
+                            )
+                        });
+                        match oldest {
+                            Some(pos) => {
+                                self.events.remove(pos);
+                                *queued -= 1;
+                                if pos < self.priority_events {
+                                    self.priority_events -= 1;
+                                }
+                            }
+                            // The queued RPCs of the peer are all spilled to disk, where they
+                            // can't be removed, or carry subscriptions.
+                            None => return None,
+                        }
+                    }
+                }
+            }
+        }
+
+        *queued += 1;
+        Some(has_room)
+    }
+
+    /// Queues an event, after the other priority events if `priority` is true.
+    ///
+    /// RPCs over [`FloodsubConfig::events_spill_threshold`] are spilled to disk, except for
+    /// priority ones.
+    fn push_event(
+        &mut self,
+        event: NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>,
+        priority: bool,
+    ) {
+        self.count_subscription_rpc(&event);
+        self.wake();
+        if priority {
+            self.events.insert(self.priority_events, event);
+            self.priority_events += 1;
+            self.make_room_for_events();
+            return;
+        }
+
+        let event = match (event, self.config.events_spill_threshold) {
+            // Subscriptions are kept in memory to be reordered before messages, see
+            // `Floodsub::pop_event`.
+            (NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }, Some(threshold))
+                if (self.events.len() >= threshold
+                    || self.spill.as_ref().map_or(false, |spill| !spill.is_empty()))
+                    && (!self.config.subscriptions_before_messages
+                        || event.subscriptions.is_empty()) =>
+            {
+                match self.spill_rpc(peer_id, event) {
+                    Ok(()) => return,
+                    Err((e, rpc)) => {
+                        warn!("Failed to spill RPC for {peer_id} to disk: {e}");
+                        NetworkBehaviourAction::NotifyHandler {
+                            peer_id,
+                            handler: NotifyHandler::Any,
+                            event: rpc,
+                        }
+                    }
+                }
+            }
+            (event, _) => event,
+        };
+        self.events.push_back(event);
+        self.make_room_for_events();
+    }
+
+    /// Returns true if [`Floodsub::events`] reached [`FloodsubConfig::max_pending_events`].
+    fn events_full(&self) -> bool {
+        self.config
+            .max_pending_events
+            .map_or(false, |max| self.events.len() >= max)
+    }
+
+    /// Drops the newest queued RPCs carrying messages while [`Floodsub::events`] exceeds
+    /// [`FloodsubConfig::max_pending_events`].
+    fn make_room_for_events(&mut self) {
+        let max = match self.config.max_pending_events {
+            Some(max) => max,
+            None => return,
+        };
+        while self.events.len() > max {
+            let newest = self.events.iter().rposition(|event| {
+                matches!(
+                    event,
+                    NetworkBehaviourAction::NotifyHandler { event: rpc, .. }
+                        if !rpc.messages.is_empty() && rpc.subscriptions.is_empty()
+                )
+            });
+            let pos = match newest {
+                Some(pos) => pos,
+                // Only events that can't be dropped are left.
+                None => return,
+            };
+            if pos < self.priority_events {
+                self.priority_events -= 1;
+            }
+            if let Some(NetworkBehaviourAction::NotifyHandler { peer_id, .. }) =
+                self.events.remove(pos)
+            {
+                debug!("Too many pending events, dropping message for {peer_id}");
+                self.forget_queued_rpc(&peer_id);
+            }
+        }
+    }
+
+    /// Records that an RPC carrying messages for a peer left [`Floodsub::events`].
+    fn forget_queued_rpc(&mut self, peer_id: &PeerId) {
+        if let Some(queued) = self.queued_rpcs.get_mut(peer_id) {
+            *queued -= 1;
+            if *queued == 0 {
+                self.queued_rpcs.remove(peer_id);
+            }
+        }
+    }
+
+    /// Wakes the task that last polled us, if it is waiting for us to have something to yield.
+    ///
+    /// Events queued while handling swarm events are yielded by the next poll anyway, but the
+    /// ones queued by the methods called by the application, e.g. when publishing, would
+    /// otherwise wait until something else wakes the task up. If there is no task to wake, we
+    /// record since when, see [`Floodsub::check_idle`].
+    fn wake(&mut self) {
+        match self.waker.take() {
+            Some(waker) => {
+                waker.wake();
+                self.unwoken_since = None;
+            }
+            None => {
+                self.unwoken_since.get_or_insert_with(Instant::now);
+            }
+        }
+    }
+
+    /// Counts an RPC carrying subscriptions about to be queued in [`Floodsub::events`], if
+    /// [`FloodsubConfig::subscriptions_before_messages`] is set.
+    fn count_subscription_rpc(
+        &mut self,
+        event: &NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>,
+    ) {
+        if let NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } = event {
+            if self.config.subscriptions_before_messages && !event.subscriptions.is_empty() {
+                *self.queued_subscription_rpcs.entry(*peer_id).or_default() += 1;
+            }
+        }
+    }
+
+    /// Removes the next event to yield from [`Floodsub::events`].
+    ///
+    /// If [`FloodsubConfig::subscriptions_before_messages`] is set and the next event sends
+    /// messages to a peer for which subscriptions are queued, the first of these subscriptions
+    /// is yielded instead.
+    fn pop_event(&mut self) -> Option<NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>> {
+        let event = self.events.pop_front()?;
+        let was_priority = self.priority_events > 0;
+        self.priority_events = self.priority_events.saturating_sub(1);
+
+        let peer_id = match &event {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if !event.messages.is_empty()
+                    && event.subscriptions.is_empty()
+                    && self.queued_subscription_rpcs.contains_key(peer_id) =>
+            {
+                *peer_id
+            }
+            _ => return Some(event),
+        };
+        let pos = self.events.iter().position(|e| {
+            matches!(
+                e,
+                NetworkBehaviourAction::NotifyHandler { peer_id: p, event: rpc, .. }
+                    if *p == peer_id && !rpc.subscriptions.is_empty()
+            )
+        });
+        let subscriptions = match pos.and_then(|pos| {
+            if pos < self.priority_events {
+                self.priority_events -= 1;
+            }
+            self.events.remove(pos)
+        }) {
+            Some(subscriptions) => subscriptions,
+            None => return Some(event),
+        };
+        self.events.push_front(event);
+        if was_priority {
+            self.priority_events += 1;
+        }
+        Some(subscriptions)
+    }
+
+    /// Appends an RPC to the spill file, creating it if needed. The RPC is given back on error.
+    fn spill_rpc(
+        &mut self,
+        peer_id: PeerId,
+        rpc: FloodsubRpc,
+    ) -> Result<(), (io::Error, FloodsubRpc)> {
+        if self.spill.is_none() {
+            match SpillQueue::create(&self.config.events_spill_path) {
+                Ok(spill) => self.spill = Some(spill),
+                Err(e) => return Err((e, rpc)),
+            }
+        }
+        let spill = self.spill.as_mut().expect("created above");
+        spill.push(&peer_id, rpc.clone()).map_err(|e| (e, rpc))
+    }
+
+    /// Records an RPC handed over to the handler of a peer, for
+    /// [`FloodsubConfig::subscription_quorum`].
+    ///
+    /// Returns the connection the RPC must be sent on, so that its acknowledgement can be told
+    /// apart from the ones of the other connections, or `None` if the peer isn't connected.
+    fn on_rpc_yielded(&mut self, peer_id: PeerId, rpc: &FloodsubRpc) -> Option<ConnectionId> {
+        let connection_id = self.peer_connections.get(&peer_id)?.first()?.0;
+        let acks = self
+            .outbound_acks
+            .entry((peer_id, connection_id))
+            .or_default();
+        acks.yielded += 1;
+        let topics = rpc
+            .subscriptions
+            .iter()
+            .filter(|sub| {
+                sub.action == FloodsubSubscriptionAction::Subscribe
+                    && self.unestablished_topics.contains_key(&sub.topic)
+            })
+            .map(|sub| sub.topic.clone())
+            .collect::<Vec<_>>();
+        if !topics.is_empty() {
+            acks.pending.push_back((acks.yielded, topics));
+        }
+        Some(connection_id)
+    }
+
+    /// Handles the acknowledgement by the handler of a connection that an RPC was sent.
+    ///
+    /// The handler doesn't tell which RPC was sent, so RPCs are considered to be sent in the
+    /// order they were yielded to the connection. RPCs the handler failed to send are never
+    /// acknowledged and are forgotten when the connection closes.
+    fn on_rpc_sent(&mut self, peer_id: PeerId, connection_id: ConnectionId) {
+        let quorum = match self.config.subscription_quorum {
+            Some(quorum) => quorum,
+            None => return,
+        };
+        let acks = match self.outbound_acks.get_mut(&(peer_id, connection_id)) {
+            Some(acks) => acks,
+            None => return,
+        };
+        acks.sent += 1;
+        let mut established = Vec::new();
+        while matches!(acks.pending.front(), Some((index, _)) if *index <= acks.sent) {
+            let (_, topics) = acks.pending.pop_front().expect("Checked above");
+            for topic in topics {
+                let reached = match self.unestablished_topics.get_mut(&topic) {
+                    Some(acknowledged) => {
+                        acknowledged.insert(peer_id);
+                        acknowledged.len() >= quorum
+                    }
+                    None => false,
+                };
+                if reached {
+                    self.unestablished_topics.remove(&topic);
+                    established.push(topic);
+                }
+            }
+        }
+        for topic in established {
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SubscriptionEstablished {
+                    topic,
+                }),
+                false,
+            );
+        }
+    }
+
+    /// Moves spilled RPCs back to [`Floodsub::events`] until it reaches
+    /// [`FloodsubConfig::events_spill_threshold`].
+    fn reload_spilled_rpcs(&mut self) {
+        let (spill, threshold) = match (&mut self.spill, self.config.events_spill_threshold) {
+            (Some(spill), Some(threshold)) => (spill, threshold),
+            _ => return,
+        };
+        while self.events.len() < threshold {
+            match spill.pop() {
+                Ok(Some((peer_id, rpc))) => {
+                    self.events
+                        .push_back(NetworkBehaviourAction::NotifyHandler {
+                            peer_id,
+                            handler: NotifyHandler::Any,
+                            event: rpc,
+                        })
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Failed to reload spilled RPCs, dropping them: {e}");
+                    if let Err(e) = spill.clear() {
+                        warn!("Failed to clear spill file: {e}");
+                    }
+                    // Only count the RPCs that are still queued.
+                    self.queued_rpcs.clear();
+                    for event in &self.events {
+                        if let NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } = event
+                        {
+                            if !event.messages.is_empty() {
+                                *self.queued_rpcs.entry(*peer_id).or_default() += 1;
+                            }
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Hands a message over to the local application, either as a [`FloodsubEvent::Message`] or
+    /// through the channel set with [`Floodsub::with_message_sender`].
+    fn deliver(&mut self, message: FloodsubMessage) {
+        let message = match self.decompress(message) {
+            Some(message) => message,
+            None => return,
+        };
+        let message = match self.delivery_transform.as_mut() {
+            Some(transform) => match transform(message) {
+                Some(message) => message,
+                None => return,
+            },
+            None => message,
+        };
+
+        if let (Some(max), Some(topic)) = (
+            self.config.max_deliveries_per_topic,
+            message.topics.first().cloned(),
+        ) {
+            let now = Instant::now();
+            let window = self
+                .delivery_windows
+                .entry(topic)
+                .or_insert_with(|| DeliveryWindow {
+                    start: now,
+                    delivered: 0,
+                    buffered: VecDeque::new(),
+                });
+            if now.duration_since(window.start) >= self.config.delivery_rate_window {
+                window.start = now;
+                window.delivered = 0;
+            }
+            if window.delivered >= max || !window.buffered.is_empty() {
+                match self.config.delivery_overflow_policy {
+                    DeliveryOverflowPolicy::Buffer { capacity }
+                        if window.buffered.len() < capacity =>
+                    {
+                        window.buffered.push_back(message)
+                    }
+                    _ => debug!("Dropping message over the local delivery rate limit"),
+                }
+                return;
+            }
+            window.delivered += 1;
+        }
+
+        self.emit_message(message);
+    }
+
+    /// Compresses the data of a message we publish according to
+    /// [`FloodsubConfig::compression`], unless that doesn't make it smaller. Returns the data to
+    /// send and the algorithm it is compressed with.
+    fn compress(&self, data: Vec<u8>) -> (Vec<u8>, Compression) {
+        let compression = self.config.compression;
+        if compression == Compression::None {
+            return (data, compression);
+        }
+        match compression::compress(compression, &data) {
+            Ok(compressed) if compressed.len() < data.len() => (compressed, compression),
+            Ok(_) => (data, Compression::None),
+            Err(e) => {
+                debug!("Failed to compress message, sending it uncompressed: {e}");
+                (data, Compression::None)
+            }
+        }
+    }
+
+    /// Decompresses the data of a message to deliver. Returns `None` if it can't be.
+    fn decompress(&self, mut message: FloodsubMessage) -> Option<FloodsubMessage> {
+        if message.compression == Compression::None {
+            return Some(message);
+        }
+        match compression::decompress(
+            message.compression,
+            &message.data,
+            self.config.max_transmit_size,
+        ) {
+            Ok(data) => {
+                message.data = data;
+                message.compression = Compression::None;
+                Some(message)
+            }
+            Err(e) => {
+                debug!(
+                    "Not delivering message of {}, failed to decompress it: {e}",
+                    message.source
+                );
+                None
+            }
+        }
+    }
+
+    /// Yields a message to the local application.
+    fn emit_message(&mut self, message: FloodsubMessage) {
+        #[cfg(feature = "metrics")]
+        recorder::message_delivered();
+        if self.config.track_delivery_rate {
+            let subscribed_topics = &self.subscribed_topics;
+            self.metrics.delivery_rate.observe(
+                message
+                    .topics
+                    .iter()
+                    .filter(|topic| subscribed_topics.contains(topic)),
+                Instant::now(),
+            );
+        }
+
+        #[cfg(feature = "tokio")]
+        if let Some(sender) = &self.message_sender {
+            if sender.send(message).is_err() {
+                debug!("Dropping message as the message channel has no receiver");
+            }
+            return;
+        }
+
+        let priority = self.priority_sources.contains(&message.source);
+        let event = if self.config.confirm_deliveries {
+            let confirmation = DeliveryConfirmation {
+                source: message.source,
+                verdicts: Arc::downgrade(&self.verdicts),
+            };
+            FloodsubEvent::ConfirmableMessage {
+                message,
+                confirmation,
+            }
+        } else {
+            FloodsubEvent::Message(message)
+        };
+        self.push_event(NetworkBehaviourAction::GenerateEvent(event), priority);
+    }
+
+    /// Records activity on the topics of `message` we are subscribed to, if
+    /// [`FloodsubConfig::unsubscribe_idle_topics`] is set.
+    fn record_topic_activity(&mut self, message: &FloodsubMessage) {
+        if !self.config.unsubscribe_idle_topics {
+            return;
+        }
+        let now = Instant::now();
+        for topic in &message.topics {
+            if let Some(last_activity) = self.topic_activity.get_mut(topic) {
+                *last_activity = now;
+            }
+        }
+    }
+
+    /// Keeps `message` in the per-topic buffers of recent messages, if
+    /// [`FloodsubConfig::recent_messages_per_topic`] is non-zero.
+    ///
+    /// Only topics that we or one of the connected peers are subscribed to are buffered, so that
+    /// remotes cannot make us allocate buffers for arbitrary topics.
+    fn remember_recent(&mut self, message: &FloodsubMessage) {
+        let capacity = self.config.recent_messages_per_topic;
+        if capacity == 0 {
+            return;
+        }
+
+        for topic in &message.topics {
+            let has_subscribers = self.subscribed_topics.contains(topic)
+                || self.subscriber_counts.contains_key(topic);
+            if !has_subscribers {
+                continue;
+            }
+
+            let buffer = self.recent_messages.entry(topic.clone()).or_default();
+            if buffer.len() == capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back(message.clone());
+        }
+    }
+
+    /// Returns the peers a message should be sent to, as decided by the [`TopicRouter`] among the
+    /// subscribed peers, plus the subscribed pinned peers. The peer passed as `exclude` is never
+    /// part of the result.
+    fn forward_targets(
+        &mut self,
+        message: &FloodsubMessage,
+        exclude: Option<&PeerId>,
+    ) -> Vec<PeerId> {
+        let candidates = self.subscribed_peers(message, exclude);
+        let pinned = candidates
+            .iter()
+            .filter(|peer_id| self.pinned_peers.contains(peer_id))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut targets = self.router.route(message, candidates);
+        for peer_id in pinned {
+            if !targets.contains(&peer_id) {
+                targets.push(peer_id);
+            }
+        }
+
+        let preferred = match self.endpoint_preference {
+            EndpointPreference::NoPreference => return targets,
+            EndpointPreference::Outbound => Endpoint::Dialer,
+            EndpointPreference::Inbound => Endpoint::Listener,
+        };
+        // Stable sort, preferred peers first.
+        targets.sort_by_key(|peer_id| {
+            !self
+                .peer_connections
+                .get(peer_id)
+                .map_or(false, |connections| {
+                    connections
+                        .iter()
+                        .any(|(_, point)| point.to_endpoint() == preferred)
+                })
+        });
+        targets
+    }
+
+    /// Returns the peers that are eligible for receiving a message, other than `exclude`, ordered
+    /// by their byte representation for the messages to be sent in a deterministic order.
+    fn subscribed_peers(&self, message: &FloodsubMessage, exclude: Option<&PeerId>) -> Vec<PeerId> {
+        let mut peers = self
+            .connected_peers
+            .iter()
+            .filter(|(peer_id, sub_topics)| {
+                Some(*peer_id) != exclude
+                    // Peer must not be the source of the message, who has it already.
+                    && **peer_id != message.source
+                    // Peer must not be blacklisted.
+                    && !self.blacklisted_peers.contains(peer_id)
+                    // Peer must be in a communication list.
+                    && self.target_peers.contains(peer_id)
+                    // Peer must not be on hold for flapping, unless pinned.
+                    && (!self.flapping_peers.contains_key(peer_id)
+                        || self.pinned_peers.contains(peer_id))
+                    // Peer must be subscribed for the topic.
+                    && sub_topics
+                        .iter()
+                        .any(|t| message.topics.iter().any(|u| t == u))
+            })
+            .map(|(peer_id, _)| *peer_id)
+            .collect::<Vec<_>>();
+        peers.sort_by_cached_key(PeerId::to_bytes);
+        peers
+    }
+
+    fn next_sequence_number(&mut self) -> Vec<u8> {
+        let len = self.config.sequence_number_len;
+        match self.config.sequence_numbers {
+            SequenceNumbers::Random => {
+                // If the sequence numbers are predictable, then an attacker could flood the
+                // network with packets with the predetermined sequence numbers and absorb our
+                // legitimate messages. We therefore use a random number.
+                let mut sequence_number = vec![0; len];
+                rand::thread_rng().fill_bytes(&mut sequence_number);
+                sequence_number
+            }
+            SequenceNumbers::Monotonic => {
+                // The counter wraps around once it overflows the sequence numbers, or itself if
+                // they are larger.
+                let counter_len = len.min(8);
+                let mask = u64::MAX >> (64 - 8 * counter_len);
+                self.sequence_number_counter = self.sequence_number_counter.wrapping_add(1) & mask;
+                let counter = self.sequence_number_counter.to_be_bytes();
+                let mut sequence_number = vec![0; len - counter_len];
+                sequence_number.extend_from_slice(&counter[8 - counter_len..]);
+                if sequence_number.iter().all(|byte| *byte == 0) {
+                    return self.on_sequence_numbers_wrapped(sequence_number);
+                }
+                sequence_number
+            }
+        }
+    }
+
+    /// Applies [`FloodsubConfig::sequence_number_wraparound`] once the counter of the sequence
+    /// numbers wrapped around, and returns the sequence number to use for the next message.
+    fn on_sequence_numbers_wrapped(&mut self, sequence_number: Vec<u8>) -> Vec<u8> {
+        warn!("Sequence numbers of published messages wrapped around");
+        match self.config.sequence_number_wraparound {
+            SequenceNumberWraparound::Warn => {}
+            SequenceNumberWraparound::Report => self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SequenceNumbersWrapped),
+                false,
+            ),
+            SequenceNumberWraparound::SwitchToRandom => {
+                self.config.sequence_numbers = SequenceNumbers::Random;
+                return self.next_sequence_number();
+            }
+            SequenceNumberWraparound::ResetDedup => self.clear_received_cache(),
+        }
+        sequence_number
+    }
+
+    /// Records the sequence number of a message and returns whether it is higher than the one of
+    /// all the messages previously received from the same source.
+    fn check_sequence_monotonicity(&mut self, message: &FloodsubMessage) -> bool {
+        let expires = Instant::now() + self.config.sequence_tracking_ttl;
+        match self.highest_sequence_numbers.entry(message.source) {
+            Entry::Occupied(mut entry) => {
+                let (highest, expiry) = entry.get_mut();
+                if cmp_sequence_numbers(&message.sequence_number, highest) != Ordering::Greater {
+                    return false;
+                }
+                *highest = message.sequence_number.clone();
+                *expiry = expires;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((message.sequence_number.clone(), expires));
+            }
+        }
+        self.next_sequence_tracking_sweep.get_or_insert(expires);
+        true
+    }
+
+    fn on_connection_established(
+        &mut self,
+        ConnectionEstablished {
+            peer_id,
+            connection_id,
+            endpoint,
+            other_established,
+            ..
+        }: ConnectionEstablished,
+    ) {
+        self.peer_connections
+            .entry(peer_id)
+            .or_default()
+            .push((connection_id, endpoint.clone()));
+
+        if other_established > 0 {
+            // We only care about the first time a peer connects.
+            return;
+        }
+        self.push_event(
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::PeerConnected(peer_id)),
+            false,
+        );
+
+        let now = Instant::now();
+        let resumed = self
+            .resumable_peers
+            .remove(&peer_id)
+            .filter(|state| state.expires > now);
+
+        // The peer is tracked already if it sent subscriptions before we were notified of the
+        // connection, see `FloodsubConfig::lazy_peer_insert`.
+        let tracked = self.connected_peers.contains_key(&peer_id);
+        if !tracked {
+            self.connected_peers.insert(peer_id, SmallVec::new());
+            if self.config.emit_topology_changes {
+                self.push_event(
+                    NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                        TopologyChange::PeerConnected { peer_id },
+                    )),
+                    false,
+                );
+            }
+        }
+
+        let announced = match resumed {
+            // The subscriptions the peer sent take precedence over the ones we remember.
+            Some(state) if !tracked => {
+                if self.config.emit_topology_changes {
+                    for topic in &state.topics {
+                        self.push_event(
+                            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                                TopologyChange::Subscribed {
+                                    peer_id,
+                                    topic: topic.clone(),
+                                },
+                            )),
+                            false,
+                        );
+                    }
+                }
+                for topic in &state.topics {
+                    *self.subscriber_counts.entry(topic.clone()).or_default() += 1;
+                }
+                self.connected_peers.insert(peer_id, state.topics);
+                state.announced
+            }
+            _ => None,
+        };
+
+        // We need to send our subscriptions to the newly-connected node, unless it is flapping.
+        if !self.detect_flapping(peer_id, now) {
+            match announced {
+                Some(announced) => self.send_subscription_diff(peer_id, &announced),
+                None => self.send_subscription_dump(peer_id),
+            }
+        }
+    }
+
+    /// Sends to `peer_id` the changes to our subscriptions since we announced `announced` to it,
+    /// if any.
+    fn send_subscription_diff(&mut self, peer_id: PeerId, announced: &[Topic]) {
+        if !self.target_peers.contains(&peer_id) || self.startup_announce_deadline.is_some() {
+            return;
+        }
+        let subscribed = self
+            .subscribed_topics
+            .iter()
+            .filter(|topic| !announced.contains(topic))
+            .map(|topic| FloodsubSubscription {
+                topic: topic.clone(),
+                action: FloodsubSubscriptionAction::Subscribe,
+            });
+        let unsubscribed = announced
+            .iter()
+            .filter(|topic| !self.subscribed_topics.contains(topic))
+            .map(|topic| FloodsubSubscription {
+                topic: topic.clone(),
+                action: FloodsubSubscriptionAction::Unsubscribe,
+            });
+        let subscriptions = subscribed.chain(unsubscribed).collect::<Vec<_>>();
+        if !subscriptions.is_empty() {
+            self.send_subscriptions(peer_id, subscriptions);
+        }
+    }
+
+    /// Records a new connection to `peer_id` and returns whether the peer is flapping, in which
+    /// case its cooldown is (re)started.
+    fn detect_flapping(&mut self, peer_id: PeerId, now: Instant) -> bool {
+        let threshold = match self.config.flap_threshold {
+            Some(threshold) => threshold,
+            None => return false,
+        };
+
+        let history = self.connection_history.entry(peer_id).or_default();
+        while matches!(history.front(), Some(t) if now.duration_since(*t) > self.config.flap_window)
+        {
+            history.pop_front();
+        }
+        history.push_back(now);
+        if history.len() < threshold && !self.flapping_peers.contains_key(&peer_id) {
+            return false;
+        }
+
+        let cooldown_end = now + self.config.flap_cooldown;
+        if self.flapping_peers.insert(peer_id, cooldown_end).is_none() {
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::PeerFlapping { peer_id }),
+                false,
+            );
+        }
+        true
+    }
+
+    /// Updates the topics of a connected peer according to the subscription changes it sent.
+    fn apply_subscriptions(&mut self, peer_id: PeerId, subscriptions: Vec<FloodsubSubscription>) {
+        if peer_id == self.config.local_peer_id {
+            // Our own subscriptions reflected back to us, we know them already.
+            debug!("Ignoring subscriptions received from our own peer id");
+            return;
+        }
+
+        // Recent messages to send to the remote because it subscribed to new topics.
+        let mut recent_to_send = Vec::new();
+        // Topics we learned about that match one of our matchers.
+        let mut matching_topics = Vec::new();
+
+        for subscription in subscriptions {
+            if self.config.track_unique_topics {
+                self.metrics.unique_topics.observe(&subscription.topic);
+            }
+            let remote_peer_topics = self.connected_peers
+                .get_mut(&peer_id)
+                .expect("connected_peers is kept in sync with the peers we are connected to; we are guaranteed to only receive events from connected peers; QED");
+            match subscription.action {
+                FloodsubSubscriptionAction::Subscribe => {
+                    if !remote_peer_topics.contains(&subscription.topic)
+                        && self
+                            .config
+                            .max_subscriptions_per_peer
+                            .map_or(false, |max| remote_peer_topics.len() >= max)
+                    {
+                        debug!(
+                            "Ignoring subscription of {peer_id} to {}, over the maximum number of topics",
+                            subscription.topic.id()
+                        );
+                        self.truncated_peers.insert(peer_id);
+                        continue;
+                    }
+                    if !remote_peer_topics.contains(&subscription.topic) {
+                        remote_peer_topics.push(subscription.topic.clone());
+                        *self
+                            .subscriber_counts
+                            .entry(subscription.topic.clone())
+                            .or_default() += 1;
+                        if self
+                            .topic_matchers
+                            .iter()
+                            .any(|m| m.matches(&subscription.topic))
+                        {
+                            matching_topics.push(subscription.topic.clone());
+                        }
+                        if self.config.emit_topology_changes {
+                            self.push_event(
+                                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                                    TopologyChange::Subscribed {
+                                        peer_id,
+                                        topic: subscription.topic.clone(),
+                                    },
+                                )),
+                                false,
+                            );
+                        }
+
+                        // Catch the peer up on the messages it just missed.
+                        let recent = self
+                            .recent_messages
+                            .get(&subscription.topic)
+                            .into_iter()
+                            .flatten()
+                            .filter(|m| m.source != peer_id)
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        if !recent.is_empty() && self.target_peers.contains(&peer_id) {
+                            recent_to_send.push(recent);
+                        }
+                        self.push_event(
+                            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Subscribed {
+                                peer_id,
+                                topic: subscription.topic,
+                            }),
+                            false,
+                        );
+                    }
+                }
+                FloodsubSubscriptionAction::Unsubscribe => {
+                    // Unsubscribing from a topic the peer isn't subscribed to is a no-op.
+                    let pos = match remote_peer_topics
+                        .iter()
+                        .position(|t| t == &subscription.topic)
+                    {
+                        Some(pos) => pos,
+                        None => continue,
+                    };
+                    remote_peer_topics.remove(pos);
+                    let now_empty = remote_peer_topics.is_empty();
+                    remove_subscriber(&mut self.subscriber_counts, &subscription.topic);
+                    if self.config.emit_topology_changes {
+                        self.push_event(
+                            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                                TopologyChange::Unsubscribed {
+                                    peer_id,
+                                    topic: subscription.topic.clone(),
+                                },
+                            )),
+                            false,
+                        );
+                    }
+                    self.push_event(
+                        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Unsubscribed {
+                            peer_id,
+                            topic: subscription.topic,
+                        }),
+                        false,
+                    );
+                    if now_empty {
+                        self.push_event(
+                            NetworkBehaviourAction::GenerateEvent(
+                                FloodsubEvent::PeerSubscriptionsEmpty { peer_id },
+                            ),
+                            false,
+                        );
+                    }
+                }
+            }
+        }
+
+        for recent in recent_to_send {
+            self.send_messages(peer_id, recent, Vec::new());
+        }
+        if !matching_topics.is_empty() {
+            self.subscribe_many(matching_topics);
+        }
+    }
+
+    /// Applies the subscription changes sent by a peer within
+    /// [`FloodsubConfig::max_subscription_updates_per_peer`], and coalesces the excess ones to
+    /// apply them in later windows.
+    fn limit_subscription_updates(
+        &mut self,
+        peer_id: PeerId,
+        mut subscriptions: Vec<FloodsubSubscription>,
+    ) {
+        let max = match self.config.max_subscription_updates_per_peer {
+            Some(max) => max,
+            None => return self.apply_subscriptions(peer_id, subscriptions),
+        };
+        if subscriptions.is_empty() {
             return;
         }
 
-        // Send to peers we know are subscribed to the topic.
-        for (peer_id, sub_topic) in self.connected_peers.iter() {
-            // Peer must be in a communication list.
-            if !self.target_peers.contains(peer_id) {
-                continue;
-            }
+        let now = Instant::now();
+        let window_len = self.config.subscription_update_window;
+        let rate = self
+            .subscription_rates
+            .entry(peer_id)
+            .or_insert_with(|| SubscriptionRate {
+                start: now,
+                updates: 0,
+                coalesced: Vec::new(),
+            });
+        if now.duration_since(rate.start) >= window_len && rate.coalesced.is_empty() {
+            rate.start = now;
+            rate.updates = 0;
+        }
+
+        let allowed = max.saturating_sub(rate.updates).min(subscriptions.len());
+        rate.updates += allowed;
+        let excess = subscriptions.split_off(allowed);
+        let exceeded = !excess.is_empty();
+        for subscription in excess {
+            // Only the last change for each topic matters.
+            if let Some(pos) = rate
+                .coalesced
+                .iter()
+                .position(|s| s.topic == subscription.topic)
+            {
+                rate.coalesced.remove(pos);
+            } else if rate.coalesced.len() >= max {
+                debug!("Dropping subscription change of {peer_id} over its limit");
+                continue;
+            }
+            rate.coalesced.push(subscription);
+        }
+
+        if exceeded {
+            debug!("{peer_id} exceeded its subscription updates limit");
+            self.penalize(peer_id, self.config.subscription_spam_penalty);
+        }
+        self.apply_subscriptions(peer_id, subscriptions);
+    }
+
+    /// Applies the verdicts reported through the [`DeliveryConfirmation`]s since the last call.
+    fn apply_verdicts(&mut self) {
+        let verdicts = std::mem::take(&mut *self.verdicts.lock().expect("lock not poisoned"));
+        for (source, verdict) in verdicts {
+            if verdict == MessageVerdict::Reject {
+                self.penalize(source, self.config.rejected_message_penalty);
+            }
+        }
+    }
+
+    /// Decreases the score of a connected peer, unless it is pinned.
+    fn penalize(&mut self, peer_id: PeerId, penalty: f64) {
+        if self.connected_peers.contains_key(&peer_id) && !self.pinned_peers.contains(&peer_id) {
+            *self.peer_scores.entry(peer_id).or_default() -= penalty;
+        }
+    }
 
-            // Peer must be subscribed for the topic.
-            if !sub_topic
-                .iter()
-                .any(|t| message.topics.iter().any(|u| t == u))
-            {
-                continue;
+    /// Returns our role in the oldest connection to a peer and its address, if we are connected
+    /// to it.
+    pub fn connection_info(&self, peer_id: &PeerId) -> Option<&ConnectedPoint> {
+        self.peer_connections
+            .get(peer_id)
+            .and_then(|connections| connections.first())
+            .map(|(_, point)| point)
+    }
+
+    /// Returns the score of a connected peer, `0` unless it misbehaved.
+    ///
+    /// Scores decrease as peers misbehave, e.g. by exceeding
+    /// [`FloodsubConfig::max_subscription_updates_per_peer`], according to
+    /// [`FloodsubConfig::spoofed_source_policy`] or when the application rejects their messages,
+    /// and are reset when they disconnect.
+    /// Pinned peers are never penalized.
+    pub fn peer_score(&self, peer_id: &PeerId) -> f64 {
+        self.peer_scores.get(peer_id).copied().unwrap_or(0.0)
+    }
+
+    /// Returns a snapshot of the scores of the connected peers that misbehaved, see
+    /// [`Floodsub::peer_score`]. Peers missing from it have a score of `0`.
+    pub fn peer_scores(&self) -> HashMap<PeerId, f64> {
+        self.peer_scores.clone()
+    }
+
+    /// Removes the topics of a peer that are not part of `subscribed`, as if it had unsubscribed
+    /// from them.
+    fn forget_stale_subscriptions(&mut self, peer_id: PeerId, subscribed: &[Topic]) {
+        let topics = match self.connected_peers.get_mut(&peer_id) {
+            Some(topics) => topics,
+            None => return,
+        };
+        let had_topics = !topics.is_empty();
+        let mut stale = Vec::new();
+        topics.retain(|topic| {
+            let keep = subscribed.contains(topic);
+            if !keep {
+                stale.push(topic.clone());
             }
+            keep
+        });
+        let now_empty = had_topics && topics.is_empty();
 
-            self.events
-                .push_back(NetworkBehaviourAction::NotifyHandler {
-                    peer_id: *peer_id,
-                    handler: NotifyHandler::Any,
-                    event: FloodsubRpc {
-                        subscriptions: Vec::new(),
-                        messages: vec![message.clone()],
-                    },
-                });
+        for topic in stale {
+            remove_subscriber(&mut self.subscriber_counts, &topic);
+            if self.config.emit_topology_changes {
+                self.push_event(
+                    NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                        TopologyChange::Unsubscribed {
+                            peer_id,
+                            topic: topic.clone(),
+                        },
+                    )),
+                    false,
+                );
+            }
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Unsubscribed {
+                    peer_id,
+                    topic,
+                }),
+                false,
+            );
+        }
+        if now_empty {
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::PeerSubscriptionsEmpty {
+                    peer_id,
+                }),
+                false,
+            );
         }
     }
 
-    fn on_connection_established(
-        &mut self,
-        ConnectionEstablished {
-            peer_id,
-            other_established,
-            ..
-        }: ConnectionEstablished,
-    ) {
-        if other_established > 0 {
-            // We only care about the first time a peer connects.
+    /// Sends the digest of our subscriptions to the connected peers of our partial view.
+    fn send_subscription_digests(&mut self) {
+        if self.startup_announce_deadline.is_some() {
             return;
         }
-
-        // We need to send our subscriptions to the newly-connected node.
-        if self.target_peers.contains(&peer_id) {
-            for topic in self.subscribed_topics.iter().cloned() {
-                self.events
-                    .push_back(NetworkBehaviourAction::NotifyHandler {
-                        peer_id,
-                        handler: NotifyHandler::Any,
-                        event: FloodsubRpc {
-                            messages: Vec::new(),
-                            subscriptions: vec![FloodsubSubscription {
-                                topic,
-                                action: FloodsubSubscriptionAction::Subscribe,
-                            }],
-                        },
-                    });
-            }
+        let digest = subscription_digest(&self.subscribed_topics);
+        let peers = self
+            .connected_peers
+            .keys()
+            .filter(|peer_id| {
+                self.target_peers.contains(peer_id) && !self.flapping_peers.contains_key(peer_id)
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        for peer_id in peers {
+            self.send_subscriptions_with_sync(
+                peer_id,
+                Vec::new(),
+                Some(FloodsubSubscriptionSync::Digest(digest.clone())),
+            );
         }
+    }
 
-        self.connected_peers.insert(peer_id, SmallVec::new());
+    /// Sends all our subscriptions to `peer_id`, if it is in our partial view and the startup
+    /// delay has elapsed.
+    fn send_subscription_dump(&mut self, peer_id: PeerId) {
+        if self.target_peers.contains(&peer_id)
+            && self.startup_announce_deadline.is_none()
+            && !self.subscribed_topics.is_empty()
+        {
+            let subscriptions = self
+                .subscribed_topics
+                .iter()
+                .map(|topic| FloodsubSubscription {
+                    topic: topic.clone(),
+                    action: FloodsubSubscriptionAction::Subscribe,
+                })
+                .collect();
+            self.send_subscriptions(peer_id, subscriptions);
+        }
     }
 
     fn on_connection_closed(
         &mut self,
         ConnectionClosed {
             peer_id,
+            connection_id,
+            endpoint,
             remaining_established,
             ..
         }: ConnectionClosed<<Self as NetworkBehaviour>::ConnectionHandler>,
     ) {
+        if let Entry::Occupied(mut entry) = self.peer_connections.entry(peer_id) {
+            let connections = entry.get_mut();
+            let pos = connections.iter().position(|(id, _)| *id == connection_id);
+            debug_assert!(
+                matches!(pos, Some(pos) if connections[pos].1 == *endpoint),
+                "endpoint of the closed connection to {peer_id} changed"
+            );
+            if let Some(pos) = pos {
+                connections.remove(pos);
+            }
+            if connections.is_empty() {
+                entry.remove();
+            }
+        }
+        self.outbound_acks.remove(&(peer_id, connection_id));
+
         if remaining_established > 0 {
             // we only care about peer disconnections
             return;
@@ -320,13 +3383,62 @@ impl Floodsub {
 
         let was_in = self.connected_peers.remove(&peer_id);
         debug_assert!(was_in.is_some());
+        let topics = was_in.unwrap_or_default();
+        for topic in &topics {
+            remove_subscriber(&mut self.subscriber_counts, topic);
+        }
+        self.push_event(
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::PeerDisconnected(peer_id)),
+            false,
+        );
+        if self.config.emit_topology_changes {
+            self.push_event(
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                    TopologyChange::PeerDisconnected {
+                        peer_id,
+                        topics: topics.to_vec(),
+                    },
+                )),
+                false,
+            );
+        }
+        if let Some(window) = self.config.subscription_resume_window {
+            // Only rely on what we announced to the peer if it received all our subscriptions.
+            let announced_all = self.target_peers.contains(&peer_id)
+                && self.startup_announce_deadline.is_none()
+                && !self.flapping_peers.contains_key(&peer_id)
+                && self.pending_subscriptions.is_empty();
+            let expires = Instant::now() + window;
+            self.resumable_peers.insert(
+                peer_id,
+                ResumableState {
+                    topics,
+                    announced: announced_all.then(|| self.subscribed_topics.to_vec()),
+                    expires,
+                },
+            );
+            self.next_resumable_sweep.get_or_insert(expires);
+        }
+        self.highest_sequence_numbers.remove(&peer_id);
+        self.outbound_batches.remove(&peer_id);
+        self.rate_limit_tokens.remove(&peer_id);
+        self.subscription_rates.remove(&peer_id);
+        self.full_subscription_parts.remove(&peer_id);
+        self.truncated_peers.remove(&peer_id);
+        self.peer_scores.remove(&peer_id);
+        for acknowledged in self.unestablished_topics.values_mut() {
+            acknowledged.remove(&peer_id);
+        }
 
         // We can be disconnected by the remote in case of inactivity for example, so we always
         // try to reconnect.
         if self.target_peers.contains(&peer_id) {
-            self.events.push_back(NetworkBehaviourAction::Dial {
-                opts: DialOpts::peer_id(peer_id).build(),
-            });
+            self.push_event(
+                NetworkBehaviourAction::Dial {
+                    opts: DialOpts::peer_id(peer_id).build(),
+                },
+                false,
+            );
         }
     }
 }
@@ -358,132 +3470,243 @@ impl NetworkBehaviour for Floodsub {
     fn on_connection_handler_event(
         &mut self,
         propagation_source: PeerId,
-        _connection_id: ConnectionId,
+        connection_id: ConnectionId,
         event: THandlerOutEvent<Self>,
     ) {
-        // We ignore successful sends or timeouts.
-        let event = match event {
+        let mut event = match event {
             InnerMessage::Rx(event) => event,
-            InnerMessage::Sent => return,
-        };
-
-        // Update connected peers topics
-        for subscription in event.subscriptions {
-            let remote_peer_topics = self.connected_peers
-                .get_mut(&propagation_source)
-                .expect("connected_peers is kept in sync with the peers we are connected to; we are guaranteed to only receive events from connected peers; QED");
-            match subscription.action {
-                FloodsubSubscriptionAction::Subscribe => {
-                    if !remote_peer_topics.contains(&subscription.topic) {
-                        remote_peer_topics.push(subscription.topic.clone());
-                    }
-                    self.events.push_back(NetworkBehaviourAction::GenerateEvent(
-                        FloodsubEvent::Subscribed {
-                            peer_id: propagation_source,
-                            topic: subscription.topic,
-                        },
-                    ));
-                }
-                FloodsubSubscriptionAction::Unsubscribe => {
-                    if let Some(pos) = remote_peer_topics
-                        .iter()
-                        .position(|t| t == &subscription.topic)
-                    {
-                        remote_peer_topics.remove(pos);
-                    }
-                    self.events.push_back(NetworkBehaviourAction::GenerateEvent(
-                        FloodsubEvent::Unsubscribed {
-                            peer_id: propagation_source,
-                            topic: subscription.topic,
-                        },
-                    ));
+            InnerMessage::Sent => {
+                // The handler reports the outbound substream once it is negotiated, the RPC
+                // written and the substream closed.
+                #[cfg(feature = "substream-events")]
+                for event in [
+                    FloodsubEvent::SubstreamOpened {
+                        peer_id: propagation_source,
+                    },
+                    FloodsubEvent::SubstreamClosed {
+                        peer_id: propagation_source,
+                    },
+                ] {
+                    self.push_event(NetworkBehaviourAction::GenerateEvent(event), false);
                 }
+                self.on_rpc_sent(propagation_source, connection_id);
+                return;
             }
-        }
+        };
 
-        // List of messages we're going to propagate on the network.
-        let mut rpcs_to_dispatch: Vec<(PeerId, FloodsubRpc)> = Vec::new();
+        if self.blacklisted_peers.contains(&propagation_source) {
+            debug!("Ignoring RPC from blacklisted peer {propagation_source}");
+            return;
+        }
 
-        for message in event.messages {
-            // Use `self.received` to skip the messages that we have already received in the past.
-            // Note that this can result in false positives.
-            match self.received.test_and_add(&message) {
-                Ok(true) => {}         // Message  was added.
-                Ok(false) => continue, // Message already existed.
-                Err(e @ CuckooError::NotEnoughSpace) => {
-                    // Message added, but some other removed.
-                    warn!(
-                        "Message was added to 'received' Cuckoofilter but some \
-                         other message was removed as a consequence: {}",
-                        e,
+        if !self.connected_peers.contains_key(&propagation_source)
+            && (!event.subscriptions.is_empty() || event.sync.is_some())
+        {
+            if self.config.lazy_peer_insert {
+                debug!("Received subscriptions from {propagation_source} before the connection");
+                self.connected_peers
+                    .insert(propagation_source, SmallVec::new());
+                if self.config.emit_topology_changes {
+                    self.push_event(
+                        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Topology(
+                            TopologyChange::PeerConnected {
+                                peer_id: propagation_source,
+                            },
+                        )),
+                        false,
                     );
                 }
+            } else {
+                warn!("Dropping subscriptions from {propagation_source}, which isn't connected");
+                event.subscriptions.clear();
+                event.sync = None;
             }
+        }
 
-            // Add the message to be dispatched to the user.
-            if self
-                .subscribed_topics
-                .iter()
-                .any(|t| message.topics.iter().any(|u| t == u))
-            {
-                let event = FloodsubEvent::Message(message.clone());
-                self.events
-                    .push_back(NetworkBehaviourAction::GenerateEvent(event));
-            }
+        self.metrics.subscriptions_received += event.subscriptions.len() as u64;
+        #[cfg(feature = "metrics")]
+        recorder::subscriptions_received(event.subscriptions.len() as u64);
 
-            // Propagate the message to everyone else who is subscribed to any of the topics.
-            for (peer_id, subscr_topics) in self.connected_peers.iter() {
-                if peer_id == &propagation_source {
-                    continue;
+        // If the remote sent all its subscriptions, the topics we have to forget about, once we
+        // received the last part of them.
+        let full_subscriptions = match event.sync {
+            Some(FloodsubSubscriptionSync::Full | FloodsubSubscriptionSync::Partial) => {
+                let subscribed = self
+                    .full_subscription_parts
+                    .entry(propagation_source)
+                    .or_default();
+                subscribed.extend(
+                    event
+                        .subscriptions
+                        .iter()
+                        .filter(|sub| sub.action == FloodsubSubscriptionAction::Subscribe)
+                        .map(|sub| sub.topic.clone()),
+                );
+                if event.sync == Some(FloodsubSubscriptionSync::Full) {
+                    self.full_subscription_parts.remove(&propagation_source)
+                } else {
+                    None
                 }
+            }
+            _ => None,
+        };
 
-                // Peer must be in a communication list.
-                if !self.target_peers.contains(peer_id) {
-                    continue;
-                }
+        // Our subscriptions to the new topics matching our matchers are announced along with the
+        // messages of the RPC.
+        self.deferred_announcements = Some(Vec::new());
+        if matches!(
+            event.sync,
+            Some(FloodsubSubscriptionSync::Full | FloodsubSubscriptionSync::Partial)
+        ) {
+            // We asked for all the subscriptions of the peer, they don't count towards its limit.
+            self.apply_subscriptions(propagation_source, event.subscriptions);
+        } else {
+            self.limit_subscription_updates(propagation_source, event.subscriptions);
+        }
 
-                // Peer must be subscribed for the topic.
-                if !subscr_topics
-                    .iter()
-                    .any(|t| message.topics.iter().any(|u| t == u))
-                {
-                    continue;
-                }
+        if let Some(subscribed) = full_subscriptions {
+            self.forget_stale_subscriptions(propagation_source, &subscribed);
+        }
 
-                if let Some(pos) = rpcs_to_dispatch.iter().position(|(p, _)| p == peer_id) {
-                    rpcs_to_dispatch[pos].1.messages.push(message.clone());
-                } else {
-                    rpcs_to_dispatch.push((
-                        *peer_id,
-                        FloodsubRpc {
-                            subscriptions: Vec::new(),
-                            messages: vec![message.clone()],
-                        },
-                    ));
+        match event.sync {
+            Some(FloodsubSubscriptionSync::Digest(_))
+                if self.truncated_peers.contains(&propagation_source) =>
+            {
+                debug!(
+                    "Not reconciling the subscriptions of {propagation_source}, over the maximum number of topics"
+                );
+            }
+            Some(FloodsubSubscriptionSync::Digest(digest)) => {
+                let view = &self.connected_peers[&propagation_source];
+                if subscription_digest(view) != digest {
+                    debug!(
+                        "Subscriptions of {propagation_source} diverged, requesting all of them"
+                    );
+                    self.push_event(
+                        NetworkBehaviourAction::GenerateEvent(
+                            FloodsubEvent::SubscriptionsDiverged {
+                                peer_id: propagation_source,
+                            },
+                        ),
+                        false,
+                    );
+                    self.send_subscriptions_with_sync(
+                        propagation_source,
+                        Vec::new(),
+                        Some(FloodsubSubscriptionSync::RequestFull),
+                    );
+                }
+            }
+            Some(FloodsubSubscriptionSync::RequestFull) => {
+                if self.target_peers.contains(&propagation_source) {
+                    let subscriptions = self
+                        .subscribed_topics
+                        .iter()
+                        .map(|topic| FloodsubSubscription {
+                            topic: topic.clone(),
+                            action: FloodsubSubscriptionAction::Subscribe,
+                        })
+                        .collect();
+                    self.send_subscriptions_with_sync(
+                        propagation_source,
+                        subscriptions,
+                        Some(FloodsubSubscriptionSync::Full),
+                    );
                 }
             }
+            Some(FloodsubSubscriptionSync::Full | FloodsubSubscriptionSync::Partial) | None => {}
         }
 
-        for (peer_id, rpc) in rpcs_to_dispatch {
-            self.events
-                .push_back(NetworkBehaviourAction::NotifyHandler {
-                    peer_id,
-                    handler: NotifyHandler::Any,
-                    event: rpc,
-                });
+        let announcements = self.deferred_announcements.take().unwrap_or_default();
+        if self.config.role == Role::PublishOnly {
+            if !announcements.is_empty() {
+                self.announce_subscriptions(announcements);
+            }
+            return;
+        }
+        let messages = event
+            .messages
+            .into_iter()
+            .map(|message| (propagation_source, message));
+        match self.config.message_processing_budget {
+            Some(budget) => {
+                self.deferred_messages.extend(messages);
+                self.process_deferred_messages(budget);
+                if !announcements.is_empty() {
+                    self.announce_subscriptions(announcements);
+                }
+            }
+            None => self.process_messages(messages, announcements),
         }
     }
 
     fn poll(
         &mut self,
-        _: &mut Context<'_>,
+        cx: &mut Context<'_>,
         _: &mut impl PollParameters,
     ) -> Poll<NetworkBehaviourAction<Self::OutEvent, THandlerInEvent<Self>>> {
-        if let Some(event) = self.events.pop_front() {
-            return Poll::Ready(event);
-        }
+        let now = Instant::now();
+        self.check_idle(now);
+        self.unwoken_since = None;
+        self.apply_verdicts();
+        self.refill_rate_limits(now);
+        loop {
+            self.reload_spilled_rpcs();
+            if let Some(mut event) = self.pop_event() {
+                if let NetworkBehaviourAction::NotifyHandler {
+                    peer_id,
+                    handler,
+                    event,
+                } = &mut event
+                {
+                    if !event.subscriptions.is_empty() {
+                        if let Entry::Occupied(mut queued) =
+                            self.queued_subscription_rpcs.entry(*peer_id)
+                        {
+                            *queued.get_mut() -= 1;
+                            if *queued.get() == 0 {
+                                queued.remove();
+                            }
+                        }
+                    }
+                    if self.config.subscription_quorum.is_some() {
+                        if let Some(connection_id) = self.on_rpc_yielded(*peer_id, event) {
+                            *handler = NotifyHandler::One(connection_id);
+                        }
+                    }
+                    if !event.messages.is_empty() {
+                        self.forget_queued_rpc(peer_id);
+                    }
+                }
+                return Poll::Ready(event);
+            }
+
+            if let Some(budget) = self.config.message_processing_budget {
+                if !self.deferred_messages.is_empty() {
+                    self.process_deferred_messages(budget);
+                    continue;
+                }
+            }
+
+            let now = Instant::now();
+            self.on_tick(now);
+            if !self.events.is_empty() {
+                continue;
+            }
 
-        Poll::Pending
+            // Only return `Pending` once there is nothing left to yield, so that the swarm, which
+            // polls us again right away after every action, drains all the queued events.
+            self.waker = Some(cx.waker().clone());
+            match self.next_deadline() {
+                Some(deadline) => {
+                    self.timer.reset(deadline.saturating_duration_since(now));
+                    if self.timer.poll_unpin(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+                None => return Poll::Pending,
+            }
+        }
     }
 
     fn on_swarm_event(&mut self, event: FromSwarm<Self::ConnectionHandler>) {
@@ -508,6 +3731,80 @@ impl NetworkBehaviour for Floodsub {
     }
 }
 
+/// State of a peer we disconnected from, see [`FloodsubConfig::subscription_resume_window`].
+struct ResumableState {
+    /// Topics the peer was subscribed to.
+    topics: SmallVec<[Topic; 8]>,
+    /// Our subscriptions as last announced to the peer, if it received all of them.
+    announced: Option<Vec<Topic>>,
+    /// When to forget about the peer.
+    expires: Instant,
+}
+
+/// Subscription changes received from a peer during the current window, see
+/// [`FloodsubConfig::max_subscription_updates_per_peer`].
+struct SubscriptionRate {
+    /// When the current window started.
+    start: Instant,
+    /// Number of changes applied during the current window.
+    updates: usize,
+    /// Changes waiting for a later window, at most one per topic.
+    coalesced: Vec<FloodsubSubscription>,
+}
+
+/// RPCs yielded to the handler of a peer, see [`FloodsubConfig::subscription_quorum`].
+#[derive(Default)]
+struct OutboundAcks {
+    /// Number of RPCs yielded to the handler.
+    yielded: u64,
+    /// Number of RPCs the handler reported as sent.
+    sent: u64,
+    /// Index of the RPCs that subscribed to topics that are not established yet, with these
+    /// topics.
+    pending: VecDeque<(u64, Vec<Topic>)>,
+}
+
+/// Messages of a topic delivered to us during the current window, see
+/// [`FloodsubConfig::max_deliveries_per_topic`].
+struct DeliveryWindow {
+    /// When the current window started.
+    start: Instant,
+    /// Number of messages delivered during the current window.
+    delivered: usize,
+    /// Messages waiting for a later window.
+    buffered: VecDeque<FloodsubMessage>,
+}
+
+/// Outcome of publishing a message.
+#[derive(Default)]
+struct Published {
+    /// Number of distinct peers the message was queued for.
+    queued: usize,
+    /// Peers the message was dropped for, see [`Floodsub::try_publish`].
+    dropped: Vec<PeerId>,
+}
+
+/// Decrements the number of subscribers of a topic in [`Floodsub::subscriber_counts`].
+fn remove_subscriber(counts: &mut HashMap<Topic, usize>, topic: &Topic) {
+    if let Entry::Occupied(mut entry) = counts.entry(topic.clone()) {
+        *entry.get_mut() -= 1;
+        if *entry.get() == 0 {
+            entry.remove();
+        }
+    }
+}
+
+/// Compares two sequence numbers as big-endian unsigned integers, regardless of their length.
+fn cmp_sequence_numbers(a: &[u8], b: &[u8]) -> Ordering {
+    fn strip(n: &[u8]) -> &[u8] {
+        let start = n.iter().position(|b| *b != 0).unwrap_or(n.len());
+        &n[start..]
+    }
+
+    let (a, b) = (strip(a), strip(b));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
 /// Transmission between the `OneShotHandler` and the `FloodsubHandler`.
 #[derive(Debug)]
 pub enum InnerMessage {
@@ -531,12 +3828,33 @@ impl From<()> for InnerMessage {
     }
 }
 
+/// Function set with [`Floodsub::set_message_validator`].
+type MessageValidator = dyn Fn(&PeerId, &FloodsubMessage) -> bool + Send;
+
+/// Upper bound of the encoded length of a subscription in an RPC, in addition to its topic.
+const MAX_SUBSCRIPTION_OVERHEAD: usize = 8;
+
+/// Maximum encoded length of the subscriptions of an RPC, leaving room for the other fields.
+const MAX_SUBSCRIPTIONS_LEN: usize = MAX_MESSAGE_LEN_BYTES - 128;
+
 /// Event that can happen on the floodsub behaviour.
 #[derive(Debug)]
 pub enum FloodsubEvent {
     /// A message has been received.
     Message(FloodsubMessage),
 
+    /// A message has been received, which the application reports its verdict on through
+    /// `confirmation`.
+    ///
+    /// Emitted instead of [`FloodsubEvent::Message`] if [`FloodsubConfig::confirm_deliveries`]
+    /// is set.
+    ConfirmableMessage {
+        /// The message.
+        message: FloodsubMessage,
+        /// Handle to report the verdict on the message with.
+        confirmation: DeliveryConfirmation,
+    },
+
     /// A remote subscribed to a topic.
     Subscribed {
         /// Remote that has subscribed.
@@ -552,4 +3870,302 @@ pub enum FloodsubEvent {
         /// The topic it has subscribed from.
         topic: Topic,
     },
+
+    /// We connected to a remote for the first time since we were last disconnected from it.
+    PeerConnected(PeerId),
+
+    /// We were disconnected from a remote, whether or not it was subscribed to any topic.
+    PeerDisconnected(PeerId),
+
+    /// A message was dropped because its sequence number was not higher than the one of a
+    /// previous message of the same source, which indicates a replay or a misbehaving source.
+    ///
+    /// Only emitted if [`FloodsubConfig::validate_sequence_monotonicity`] is set.
+    SuspiciousSource {
+        /// Source of the message.
+        source: PeerId,
+        /// Remote we received the message from.
+        propagation_source: PeerId,
+    },
+
+    /// A remote unsubscribed from the last topic it was subscribed to.
+    PeerSubscriptionsEmpty {
+        /// Remote that is no longer subscribed to any topic.
+        peer_id: PeerId,
+    },
+
+    /// A message was received that we are not interested in and that no peer it could be
+    /// forwarded to is interested in either.
+    ///
+    /// Only emitted if [`FloodsubConfig::emit_undeliverable_messages`] is set.
+    MessageUndeliverable {
+        /// Identifier of the message.
+        message_id: MessageId,
+        /// Topics of the message.
+        topics: Vec<Topic>,
+    },
+
+    /// We unsubscribed from a topic because no message was sent or received on it for
+    /// [`FloodsubConfig::idle_topic_timeout`]. Subscribe again to keep receiving its messages.
+    ///
+    /// Only emitted if [`FloodsubConfig::unsubscribe_idle_topics`] is set.
+    TopicIdle {
+        /// The topic we unsubscribed from.
+        topic: Topic,
+    },
+
+    /// Enough peers acknowledged our subscription to a topic for us to expect to receive its
+    /// messages. Emitted once per subscription.
+    ///
+    /// Only emitted if [`FloodsubConfig::subscription_quorum`] is set.
+    SubscriptionEstablished {
+        /// The topic we subscribed to.
+        topic: Topic,
+    },
+
+    /// The handler of a peer negotiated an outbound substream to send an RPC.
+    ///
+    /// The handler opens one substream for every RPC and closes it once the RPC is written.
+    /// Inbound substreams, and outbound substreams that fail to be negotiated, are not reported.
+    ///
+    /// Requires the `substream-events` feature.
+    #[cfg(feature = "substream-events")]
+    SubstreamOpened {
+        /// Remote the substream is opened to.
+        peer_id: PeerId,
+    },
+
+    /// The handler of a peer wrote an RPC to an outbound substream and closed it. Always follows
+    /// the [`FloodsubEvent::SubstreamOpened`] of the substream.
+    ///
+    /// Requires the `substream-events` feature.
+    #[cfg(feature = "substream-events")]
+    SubstreamClosed {
+        /// Remote the substream was opened to.
+        peer_id: PeerId,
+    },
+
+    /// A peer sent us a message claiming to be published by us, which we didn't publish.
+    ///
+    /// Only emitted if [`FloodsubConfig::spoofed_source_policy`] is
+    /// [`SpoofedSourcePolicy::DropAndReport`].
+    SpoofedSource {
+        /// Remote that sent the message.
+        peer_id: PeerId,
+    },
+
+    /// The sequence numbers of the messages we publish wrapped around and start repeating.
+    ///
+    /// Only emitted if [`FloodsubConfig::sequence_number_wraparound`] is
+    /// [`SequenceNumberWraparound::Report`].
+    SequenceNumbersWrapped,
+
+    /// A decision was made about a message traced with [`Floodsub::trace_message`].
+    Trace {
+        /// Identifier of the message.
+        message_id: MessageId,
+        /// The decision.
+        step: TraceStep,
+    },
+
+    /// The topics of the connected peers changed.
+    ///
+    /// Only emitted if [`FloodsubConfig::emit_topology_changes`] is set.
+    Topology(TopologyChange),
+
+    /// The digest of the subscriptions of a remote didn't match our view of them. We requested
+    /// all its subscriptions to correct our view.
+    SubscriptionsDiverged {
+        /// Remote whose subscriptions diverged.
+        peer_id: PeerId,
+    },
+
+    /// A remote connected too often within [`FloodsubConfig::flap_window`] and is put on hold for
+    /// [`FloodsubConfig::flap_cooldown`].
+    ///
+    /// Only emitted if [`FloodsubConfig::flap_threshold`] is set.
+    PeerFlapping {
+        /// Remote that is flapping.
+        peer_id: PeerId,
+    },
+
+    /// The behaviour looks stuck, because events were queued without waking the task polling it,
+    /// which wasn't polled for [`FloodsubConfig::idle_check_interval`].
+    ///
+    /// Only emitted if [`FloodsubConfig::idle_check_interval`] is set.
+    Warning(String),
+
+    /// A message scheduled with [`Floodsub::publish_at`] couldn't be published once it was due.
+    ScheduledPublishFailed {
+        /// The topic of the message.
+        topic: Topic,
+        /// Why it couldn't be published.
+        error: PublishError,
+    },
+}
+
+/// Handle to report the verdict of the application on a message delivered as
+/// [`FloodsubEvent::ConfirmableMessage`].
+///
+/// Dropping it without calling [`DeliveryConfirmation::confirm`] has no effect.
+#[derive(Debug, Clone)]
+pub struct DeliveryConfirmation {
+    source: PeerId,
+    verdicts: Weak<Mutex<Vec<(PeerId, MessageVerdict)>>>,
+}
+
+impl DeliveryConfirmation {
+    /// Reports the verdict of the application on the message. It is applied to the score of the
+    /// source of the message the next time the behaviour is polled.
+    pub fn confirm(self, verdict: MessageVerdict) {
+        // There is nothing to score if the behaviour was dropped.
+        if let Some(verdicts) = self.verdicts.upgrade() {
+            verdicts
+                .lock()
+                .expect("lock not poisoned")
+                .push((self.source, verdict));
+        }
+    }
+}
+
+/// Verdict of the application on a message, see [`DeliveryConfirmation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageVerdict {
+    /// The message is valid.
+    Accept,
+    /// The message is invalid, which decreases the score of its source by
+    /// [`FloodsubConfig::rejected_message_penalty`].
+    Reject,
+}
+
+/// Error returned when publishing a message fails. Nothing is published in that case.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishError {
+    /// We're not subscribed to any of the topics of the message, see
+    /// [`FloodsubConfig::allow_publish_without_subscription`].
+    #[error("Not subscribed to any of the topics of the message")]
+    NotSubscribed,
+    /// No connected peer is subscribed to any of the topics of the message, and it isn't
+    /// delivered to us either, see [`FloodsubConfig::subscribe_local_messages`].
+    #[error("No peer to send the message to")]
+    NoPeers,
+    /// The data of the message exceeds [`FloodsubConfig::max_transmit_size`], or the message
+    /// exceeds the maximum size of an RPC.
+    #[error("Message too large")]
+    MessageTooLarge,
+    /// Signing the message with the keypair of [`FloodsubConfig::message_authenticity`] failed.
+    #[error("Failed to sign the message")]
+    SigningFailed,
+    /// We're [`Role::SubscribeOnly`].
+    #[error("Publishing is disabled by the role of the node")]
+    SubscribeOnly,
+    /// We're [`Role::Relay`].
+    #[error("Relays only forward messages")]
+    Relay,
+    /// [`Floodsub::shutdown`] was called, see [`FloodsubConfig::calls_after_shutdown`].
+    #[error("Shutting down")]
+    ShuttingDown,
+    /// The duplicates filter is full, see [`FloodsubConfig::filter_full_policy`].
+    #[error("Duplicates filter is full")]
+    FilterFull,
+    /// The queue of events reached [`FloodsubConfig::max_pending_events`].
+    #[error("Too many pending events")]
+    Backpressure,
+    /// The message given to [`Floodsub::publish_message`] was already published or received.
+    #[error("Message already published or received")]
+    Duplicate,
+    /// The message has no topic.
+    #[error("Message without topic")]
+    NoTopics,
+}
+
+/// Decision made about a traced message, see [`Floodsub::trace_message`].
+///
+/// A message is first [`TraceStep::Received`], then either dropped or delivered and forwarded.
+/// With [`MultiTopicFanout::PerTopic`], it is delivered and forwarded once per topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceStep {
+    /// The message was received.
+    Received {
+        /// Remote we received the message from.
+        propagation_source: PeerId,
+    },
+    /// The message was dropped as its data exceeds [`FloodsubConfig::max_transmit_size`].
+    TooLarge,
+    /// The message was dropped as it has no topic.
+    NoTopics,
+    /// The message was dropped as its signature is missing or invalid, see
+    /// [`FloodsubConfig::message_authenticity`].
+    InvalidSignature,
+    /// The message was dropped according to [`FloodsubConfig::spoofed_source_policy`].
+    SpoofedSource,
+    /// The message was dropped as it is one we published, sent back to us.
+    Echo,
+    /// The message was dropped by the validator set with [`Floodsub::set_message_validator`].
+    Invalid,
+    /// The message was dropped as a duplicate.
+    Duplicate,
+    /// The message was dropped by [`FloodsubConfig::validate_sequence_monotonicity`].
+    StaleSequenceNumber,
+    /// The message isn't forwarded as its [`FloodsubMessage::ttl`] expired. It is still
+    /// delivered to us.
+    TtlExpired,
+    /// The message is delivered to us, subject to the local delivery rate limits.
+    Delivered {
+        /// Topics of the message delivered.
+        topics: Vec<Topic>,
+    },
+    /// The message is forwarded to these peers, which can be none.
+    Forwarded {
+        /// Topics of the message forwarded.
+        topics: Vec<Topic>,
+        /// Remotes the message is forwarded to.
+        peers: Vec<PeerId>,
+    },
+}
+
+/// Change to the topics of the connected peers, see [`FloodsubEvent::Topology`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopologyChange {
+    /// We connected to a peer, which isn't subscribed to any topic yet.
+    PeerConnected {
+        /// Remote we connected to.
+        peer_id: PeerId,
+    },
+    /// We disconnected from a peer.
+    PeerDisconnected {
+        /// Remote we disconnected from.
+        peer_id: PeerId,
+        /// Topics the remote was subscribed to.
+        topics: Vec<Topic>,
+    },
+    /// A connected peer subscribed to a topic it wasn't subscribed to.
+    Subscribed {
+        /// Remote that has subscribed.
+        peer_id: PeerId,
+        /// The topic it has subscribed to.
+        topic: Topic,
+    },
+    /// A connected peer unsubscribed from a topic it was subscribed to.
+    Unsubscribed {
+        /// Remote that has unsubscribed.
+        peer_id: PeerId,
+        /// The topic it has unsubscribed from.
+        topic: Topic,
+    },
+}
+
+/// Snapshot of the state of a [`Floodsub`] behaviour, as returned by [`Floodsub::health`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FloodsubHealth {
+    /// Number of peers we are connected to.
+    pub connected_peers: usize,
+    /// Number of topics we are subscribed to.
+    pub subscribed_topics: usize,
+    /// Number of distinct topics at least one connected peer is subscribed to.
+    pub topics_with_subscribers: usize,
+    /// Number of events waiting to be yielded when polling, including the ones spilled to disk.
+    pub pending_events: usize,
+    /// Number of messages currently tracked by the filter used to detect duplicates.
+    pub received_filter_len: usize,
 }
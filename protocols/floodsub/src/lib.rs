@@ -22,21 +22,45 @@
 
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
-use libp2p_identity::PeerId;
+use libp2p_identity::{Keypair, PeerId};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
+pub mod metrics;
 pub mod protocol;
+pub mod router;
+#[cfg(feature = "testing")]
+pub mod testing;
 
+mod compression;
+mod dedup;
 mod layer;
+mod spill;
 mod topic;
 
 mod proto {
     include!("generated/mod.rs");
-    pub use self::floodsub::pb::{mod_RPC::SubOpts, Message, RPC};
+    pub use self::floodsub::pb::{
+        mod_RPC::{SubOpts, SubscriptionSync},
+        Message, RPC,
+    };
 }
 
-pub use self::layer::{Floodsub, FloodsubEvent};
-pub use self::protocol::{FloodsubMessage, FloodsubRpc};
-pub use self::topic::Topic;
+pub use self::layer::{
+    DeliveryConfirmation, Floodsub, FloodsubEvent, FloodsubHealth, MessageVerdict, PublishError,
+    TopologyChange, TraceStep,
+};
+pub use self::metrics::FloodsubMetrics;
+pub use self::protocol::{FloodsubMessage, FloodsubRpc, MessageId};
+pub use self::router::TopicRouter;
+pub use self::topic::{Topic, TopicMatcher};
+
+/// Minimum value of [`FloodsubConfig::sequence_number_len`].
+pub const MIN_SEQUENCE_NUMBER_LEN: usize = 4;
+
+/// Maximum value of [`FloodsubConfig::sequence_number_len`].
+pub const MAX_SEQUENCE_NUMBER_LEN: usize = 32;
 
 /// Configuration options for the Floodsub protocol.
 #[derive(Debug, Clone)]
@@ -47,6 +71,448 @@ pub struct FloodsubConfig {
     /// `true` if messages published by local node should be propagated as messages received from
     /// the network, `false` by default.
     pub subscribe_local_messages: bool,
+
+    /// If set, changes to our own subscriptions are not announced right away but collected for
+    /// the given duration and then sent as a single net diff. Subscribing and unsubscribing from
+    /// the same topic within the window results in nothing being sent.
+    ///
+    /// `None` by default, which announces every change immediately.
+    pub subscription_debounce: Option<Duration>,
+
+    /// Length in bytes of the random sequence numbers of the messages that we publish, `8` by
+    /// default. Must be between [`MIN_SEQUENCE_NUMBER_LEN`] and [`MAX_SEQUENCE_NUMBER_LEN`].
+    ///
+    /// Messages are deduplicated based on their source and sequence number, so this is a
+    /// tradeoff between bandwidth and the probability of two of our messages colliding: with `n`
+    /// bytes, a collision becomes likely after roughly `2^(4n)` messages, i.e. after `2^16`
+    /// messages with `4` bytes and `2^32` messages with `8` bytes.
+    pub sequence_number_len: usize,
+
+    /// How the sequence numbers of the messages that we publish are generated,
+    /// [`SequenceNumbers::Random`] by default.
+    pub sequence_numbers: SequenceNumbers,
+
+    /// What to do when the sequence numbers wrap around with [`SequenceNumbers::Monotonic`],
+    /// [`SequenceNumberWraparound::Warn`] by default.
+    ///
+    /// The counter wraps around once it overflows the [`FloodsubConfig::sequence_number_len`]
+    /// bytes of the sequence numbers, i.e. every `2^(8n)` messages with `n` bytes. The counter
+    /// has `64` bits, so sequence numbers of more than `8` bytes wrap around like the ones of
+    /// `8` bytes, their leading bytes always being zero.
+    ///
+    /// From then on, sequence numbers repeat, and peers that still remember the earlier
+    /// messages with the same sequence numbers drop the new ones as duplicates.
+    pub sequence_number_wraparound: SequenceNumberWraparound,
+
+    /// Whether to drop messages whose sequence number is not higher than the one of a previous
+    /// message of the same source, `false` by default. A
+    /// [`FloodsubEvent::SuspiciousSource`](crate::FloodsubEvent::SuspiciousSource) is emitted for
+    /// every dropped message.
+    ///
+    /// This protects against replays, but only makes sense if all the sources of the network use
+    /// [`SequenceNumbers::Monotonic`]. Note that messages taking different paths through the
+    /// network can arrive out of order, in which case the late ones are dropped as well.
+    pub validate_sequence_monotonicity: bool,
+
+    /// How long to remember the highest sequence number of a source after its last message, if
+    /// [`FloodsubConfig::validate_sequence_monotonicity`] is set. It is also forgotten once we
+    /// disconnect from the source. 2 minutes by default.
+    pub sequence_tracking_ttl: Duration,
+
+    /// Which of the subscribed peers messages are sent to, [`ForwardPolicy::All`] by default.
+    ///
+    /// Can be overridden with a custom [`TopicRouter`] through
+    /// [`Floodsub::set_router`](crate::Floodsub::set_router).
+    pub forward_policy: ForwardPolicy,
+
+    /// Number of recent messages to keep for each topic, `0` by default. When a peer subscribes
+    /// to a topic, the recent messages of that topic are sent to it, so that peers subscribing
+    /// right after a burst of messages still receive its content.
+    pub recent_messages_per_topic: usize,
+
+    /// Whether to record the serialized size of the RPCs we send in [`FloodsubMetrics`],
+    /// `false` by default since it requires encoding every RPC once more.
+    pub record_rpc_sizes: bool,
+
+    /// Maximum number of RPCs carrying messages that can be queued for a single peer, unbounded
+    /// by default. Once reached, [`FloodsubConfig::queue_full_policy`] decides which message is
+    /// dropped. RPCs announcing subscriptions are never dropped.
+    pub max_queued_rpcs_per_peer: Option<usize>,
+
+    /// What to do when the queue of a peer is full, [`QueueFullPolicy::DropNewest`] by default.
+    pub queue_full_policy: QueueFullPolicy,
+
+    /// Maximum number of events queued to be yielded when polling, unbounded by default.
+    ///
+    /// Once reached, publishing fails with
+    /// [`PublishError::Backpressure`](crate::PublishError::Backpressure) and the messages to
+    /// forward are dropped. RPCs carrying messages are dropped, newest first, to make room for
+    /// the other events, such as RPCs announcing subscriptions, which are never dropped.
+    pub max_pending_events: Option<usize>,
+
+    /// Maximum number of messages sent to a single peer per second, unlimited by default.
+    ///
+    /// Each peer has a bucket of as many tokens, refilled continuously as the behaviour is
+    /// polled, so that bursts up to the limit are sent right away. Messages published or
+    /// forwarded to a peer whose bucket is empty are dropped for that peer only. Pinned peers
+    /// are not limited, see [`Floodsub::pin_peer`](crate::Floodsub::pin_peer).
+    pub max_messages_per_peer_per_sec: Option<u32>,
+
+    /// Number of topics to reserve room for in the list of the topics we are subscribed to, `0`
+    /// by default.
+    ///
+    /// Up to 16 topics are stored inline, without allocating. Subscribing to more topics moves
+    /// the list to the heap, and then grows it as needed. Nodes known to subscribe to many topics
+    /// can reserve room for all of them upfront; values of 16 or less have no effect.
+    pub subscribed_topics_capacity: usize,
+
+    /// If set, we don't announce any of our subscriptions until this long after the behaviour
+    /// is created, plus a random delay of up to [`FloodsubConfig::startup_announce_jitter`].
+    /// All our subscriptions are then announced at once to the connected peers.
+    ///
+    /// This avoids flooding the bootstrap peers with announcements when a fleet of nodes restarts
+    /// at the same time. `None` by default, which announces subscriptions right away.
+    pub startup_announce_delay: Option<Duration>,
+
+    /// Upper bound of the random delay added to [`FloodsubConfig::startup_announce_delay`], so
+    /// that nodes started together don't announce their subscriptions together. Zero by default.
+    pub startup_announce_jitter: Duration,
+
+    /// Order in which the checks on received messages are performed,
+    /// [`MessageCheck::DEFAULT_ORDER`] by default. A message failing a check is dropped without
+    /// performing the next ones.
+    ///
+    /// Checks missing from the list are performed after the listed ones, in their default order.
+    pub message_checks: Vec<MessageCheck>,
+
+    /// Number of the most recently received messages whose ids are also tracked exactly, `0`
+    /// (disabled) by default.
+    ///
+    /// Duplicates are detected with a probabilistic filter, which can report a message that was
+    /// never seen as a duplicate. If this is non-zero, such reports are confirmed with the exact
+    /// ids before dropping the message, which eliminates these false positives for messages
+    /// within the window at the cost of keeping the ids in memory. Messages reported as
+    /// duplicates but older than the window are considered new and thus delivered again.
+    pub exact_dedup_window: usize,
+
+    /// Number of messages the probabilistic filter detecting duplicates is sized for,
+    /// `(1 << 20) - 1` by default.
+    ///
+    /// The filter takes one byte per message, with the capacity rounded up to a power of two,
+    /// i.e. 1 MiB by default. Once about as many messages as the capacity
+    /// were received, inserting new ones evicts others, which are then considered new if they
+    /// are received again, and the false-positive rate increases. Size it for the number of
+    /// messages that can loop back to the node, e.g. the rate of messages times the time they
+    /// take to propagate through the network.
+    pub cuckoo_filter_capacity: usize,
+
+    /// What to do when recording a message we publish evicts another message from the full
+    /// duplicates filter, [`FilterFullPolicy::Evict`] by default. Received messages are always
+    /// delivered, and each eviction is logged and counted in
+    /// [`FloodsubMetrics::filter_evictions`](crate::FloodsubMetrics::filter_evictions).
+    pub filter_full_policy: FilterFullPolicy,
+
+    /// How received messages are identified when detecting duplicates,
+    /// [`DedupStrategy::SourceSeq`] by default.
+    pub dedup_strategy: DedupStrategy,
+
+    /// Weights of the topics whose messages are tracked in their own share of
+    /// [`FloodsubConfig::exact_dedup_window`], empty by default.
+    ///
+    /// The window is split between these topics and a pool shared by all the other topics, of
+    /// weight `1`. Messages are assigned according to their first topic. Each share is
+    /// proportional to the weight of its topics multiplied by their traffic, so that busy and
+    /// important topics remember their messages for longer. Shares are resized every time as
+    /// many messages as the window holds are received, and start proportional to the weights
+    /// alone.
+    pub dedup_topic_weights: HashMap<Topic, f64>,
+
+    /// How long received messages are remembered for detecting duplicates, if set, disabled by
+    /// default.
+    ///
+    /// If set, duplicates are detected with a cache of the messages received within that
+    /// duration instead of the probabilistic filter, whose false-positive rate grows with the
+    /// number of messages. Expired entries are removed when the behaviour is polled, so that
+    /// memory is bounded by the rate of messages. A message received again after it expired is
+    /// delivered again. [`FloodsubConfig::exact_dedup_window`] has no effect then.
+    pub duplicate_cache_time: Option<Duration>,
+
+    /// How often to send each connected peer a digest of our subscriptions, disabled by default.
+    ///
+    /// Peers whose view of our subscriptions doesn't match the digest, for example because an
+    /// RPC got lost, request the full list of our subscriptions and replace their view with it.
+    /// Peers always answer digests and requests, regardless of this option.
+    pub subscription_sync_interval: Option<Duration>,
+
+    /// Maximum number of messages of a single topic delivered to us within each
+    /// [`FloodsubConfig::delivery_rate_window`], unlimited by default. Messages are accounted to
+    /// their first topic.
+    ///
+    /// This protects slow consumers from bursts of messages. Messages over the limit are handled
+    /// according to [`FloodsubConfig::delivery_overflow_policy`]. Forwarding is not affected.
+    pub max_deliveries_per_topic: Option<usize>,
+
+    /// Window over which [`FloodsubConfig::max_deliveries_per_topic`] applies, 1 second by
+    /// default.
+    pub delivery_rate_window: Duration,
+
+    /// What to do with the messages over [`FloodsubConfig::max_deliveries_per_topic`],
+    /// [`DeliveryOverflowPolicy::Drop`] by default.
+    pub delivery_overflow_policy: DeliveryOverflowPolicy,
+
+    /// Whether to emit a [`FloodsubEvent::Topology`](crate::FloodsubEvent::Topology) for every
+    /// change to the topics of the connected peers, including connections and disconnections,
+    /// `false` by default since it is verbose on networks with a lot of churn.
+    ///
+    /// Applying these changes in order to an empty map maintains a copy of the topics of all
+    /// connected peers.
+    pub emit_topology_changes: bool,
+
+    /// Whether to estimate the number of distinct topics seen in subscriptions and messages,
+    /// ours included, in [`FloodsubMetrics::unique_topics`]. `false` by default.
+    pub track_unique_topics: bool,
+
+    /// Whether to count the messages published, received and forwarded for each topic, in
+    /// [`FloodsubMetrics::topics`]. `false` by default, since the counts of every topic seen are
+    /// kept.
+    pub track_topic_stats: bool,
+
+    /// Maximum number of received messages processed at once, unlimited by default.
+    ///
+    /// Processing a message, i.e. delivering it to us and forwarding it to the subscribed peers,
+    /// happens synchronously when the RPC carrying it is received. With a budget, the messages
+    /// of large RPCs over it are processed over the next polls instead, a budget at a time, which
+    /// keeps the event loop responsive under bursts.
+    pub message_processing_budget: Option<usize>,
+
+    /// Whether to add our peer id to the [`FloodsubMessage::provenance`] of the messages we
+    /// forward, `false` by default. Messages that already went through
+    /// [`FloodsubConfig::max_provenance_len`] recording peers are forwarded as is.
+    ///
+    /// > **Note**: This reveals to all the subscribers of a topic the path the messages take
+    /// > through the network, and thus which peers are connected to each other and relay the
+    /// > topic. Only enable it on networks where this information isn't sensitive.
+    pub record_provenance: bool,
+
+    /// Maximum length of the [`FloodsubMessage::provenance`] of the messages we forward, `8` by
+    /// default.
+    pub max_provenance_len: usize,
+
+    /// Number of hops the messages we publish are forwarded over, [`u8::MAX`] by default. See
+    /// [`FloodsubMessage::ttl`].
+    ///
+    /// Duplicates detection alone bounds how often a message is forwarded, but not how far. A
+    /// lower TTL limits the reach of messages, and the amplification on densely connected
+    /// networks. With a TTL of `1` or less, messages only reach the peers we send them to.
+    pub default_ttl: u8,
+
+    /// How long to remember the subscriptions of a peer after disconnecting from it, and which
+    /// of ours it knows about, disabled by default.
+    ///
+    /// If the peer reconnects within this window, its subscriptions are restored and we only
+    /// announce the changes to ours since, instead of all of them. This requires all the peers
+    /// of the network to use the same window, otherwise their views of each other's
+    /// subscriptions diverge; consider enabling
+    /// [`FloodsubConfig::subscription_sync_interval`] as well.
+    pub subscription_resume_window: Option<Duration>,
+
+    /// How long to collect the messages for a peer before sending them in a single RPC, `None`
+    /// by default, which sends messages right away. Can be overridden for each peer with
+    /// [`Floodsub::set_peer_flush_interval`](crate::Floodsub::set_peer_flush_interval).
+    pub rpc_flush_interval: Option<Duration>,
+
+    /// Whether to emit a
+    /// [`FloodsubEvent::MessageUndeliverable`](crate::FloodsubEvent::MessageUndeliverable) for
+    /// every received message that is neither delivered to us nor forwarded to any peer, `false`
+    /// by default since there can be many of them.
+    pub emit_undeliverable_messages: bool,
+
+    /// Whether to unsubscribe from the topics on which no message was sent or received for
+    /// [`FloodsubConfig::idle_topic_timeout`], `false` by default. A
+    /// [`FloodsubEvent::TopicIdle`](crate::FloodsubEvent::TopicIdle) is emitted for each of them.
+    pub unsubscribe_idle_topics: bool,
+
+    /// How long a topic can be idle before we unsubscribe from it, if
+    /// [`FloodsubConfig::unsubscribe_idle_topics`] is set. 1 hour by default.
+    pub idle_topic_timeout: Duration,
+
+    /// Number of queued events over which the RPCs to send are spilled to
+    /// [`FloodsubConfig::events_spill_path`] instead of being kept in memory, disabled by default.
+    ///
+    /// Spilled RPCs are reloaded in order as the queue drains, which trades latency for not
+    /// dropping messages under transient backpressure. RPCs carrying messages of
+    /// [priority sources](crate::Floodsub::set_priority_sources) are never spilled, and with
+    /// [`QueueFullPolicy::DropOldest`], the newest message is dropped instead if all the RPCs
+    /// queued for the peer are spilled. If writing to the file fails, RPCs are kept in memory.
+    pub events_spill_threshold: Option<usize>,
+
+    /// File the RPCs are spilled to, if [`FloodsubConfig::events_spill_threshold`] is set. It is
+    /// created when first needed and removed when the behaviour is dropped. Nothing is spilled if
+    /// the path already exists. `floodsub-<local peer id>-<random>.spill` in the temporary
+    /// directory of the system by default.
+    pub events_spill_path: PathBuf,
+
+    /// Number of peers that must acknowledge our subscription to a topic before a
+    /// [`FloodsubEvent::SubscriptionEstablished`](crate::FloodsubEvent::SubscriptionEstablished)
+    /// is emitted for it, disabled by default.
+    ///
+    /// Floodsub has no acknowledgements in its wire protocol: a peer acknowledges a
+    /// subscription once its handler reports the RPC announcing it as sent. RPCs are sent on the
+    /// first connection to each peer, and the ones pending when a connection closes are never
+    /// acknowledged. Peers count until they disconnect.
+    pub subscription_quorum: Option<usize>,
+
+    /// Maximum number of subscription changes applied for each peer within
+    /// [`FloodsubConfig::subscription_update_window`], unlimited by default.
+    ///
+    /// Excess changes are coalesced, keeping the last change for each topic, and applied in later
+    /// windows; changes for more topics than the limit are dropped. Peers exceeding the limit
+    /// are penalized by [`FloodsubConfig::subscription_spam_penalty`], see
+    /// [`Floodsub::peer_score`](crate::Floodsub::peer_score). Subscriptions sent in response
+    /// to our request for all of them don't count.
+    pub max_subscription_updates_per_peer: Option<usize>,
+
+    /// Window over which subscription changes are counted for
+    /// [`FloodsubConfig::max_subscription_updates_per_peer`], 1 second by default.
+    pub subscription_update_window: Duration,
+
+    /// Amount the score of a peer decreases by every time it exceeds
+    /// [`FloodsubConfig::max_subscription_updates_per_peer`], `1.0` by default.
+    pub subscription_spam_penalty: f64,
+
+    /// Maximum number of topics a peer can be subscribed to, unlimited by default.
+    ///
+    /// The subscriptions of a peer to more topics are ignored, but its unsubscriptions are still
+    /// applied, so that it can subscribe to other topics once it unsubscribed from some. Our view
+    /// of the subscriptions of such a peer is no longer reconciled with
+    /// [`FloodsubConfig::subscription_sync_interval`] until it reconnects.
+    pub max_subscriptions_per_peer: Option<usize>,
+
+    /// Whether to track the peers that send us subscriptions before we are notified of the
+    /// connection to them, `false` by default.
+    ///
+    /// This can happen if the connection and the RPC race. If set, the peer is tracked as
+    /// connected right away, with its subscriptions applied. Otherwise, the subscriptions are
+    /// dropped, and the peer has to send them again.
+    pub lazy_peer_insert: bool,
+
+    /// How long the behaviour can hold queued events without being polled before it is
+    /// considered stuck, disabled by default.
+    ///
+    /// When polled, a [`FloodsubEvent::Warning`](crate::FloodsubEvent::Warning) is emitted if
+    /// events were queued without waking the polling task, e.g. because it was already woken,
+    /// and it wasn't polled for this long after the first of them was queued, and another one if
+    /// some of these events are RPCs for peers that disconnected meanwhile.
+    pub idle_check_interval: Option<Duration>,
+
+    /// What to do with the messages we receive that claim to be published by us but that we
+    /// didn't publish, besides dropping them, [`SpoofedSourcePolicy::Drop`] by default.
+    ///
+    /// Floodsub messages are not signed, so any peer can claim to be the source of a message.
+    /// Without signatures, this only catches peers impersonating us: we remember the ids of the
+    /// last [`FloodsubConfig::published_ids_window`] messages we published, and echoes of them
+    /// are dropped without further action. Echoes of older messages, or of messages published by a previous
+    /// instance of the node with the same peer id, are treated as spoofed.
+    pub spoofed_source_policy: SpoofedSourcePolicy,
+
+    /// Number of messages we published whose ids are remembered, 1024 by default. Echoes of them
+    /// are dropped, and other messages claiming to be published by us are handled according to
+    /// [`FloodsubConfig::spoofed_source_policy`].
+    pub published_ids_window: usize,
+
+    /// Whether to record the rate of the messages delivered to us in
+    /// [`FloodsubMetrics::delivery_rate`], `false` by default.
+    pub track_delivery_rate: bool,
+
+    /// Sliding window over which [`FloodsubMetrics::delivery_rate`] is computed, 10 seconds by
+    /// default.
+    pub delivery_rate_tracking_window: Duration,
+
+    /// How the messages with several topics are delivered to us and forwarded,
+    /// [`MultiTopicFanout::Union`] by default.
+    pub multi_topic_fanout: MultiTopicFanout,
+
+    /// Which side of the protocol the node takes part in, [`Role::Full`] by default.
+    pub role: Role,
+
+    /// Whether to record the bytes of message data cloned during fan-out in
+    /// [`FloodsubMetrics::fanout_cloned_bytes`], `false` by default.
+    pub track_fanout_clones: bool,
+
+    /// Whether the RPCs announcing our subscriptions to a peer are sent before the RPCs carrying
+    /// messages queued for it earlier, `true` by default, so that the peer learns about a new
+    /// subscription before receiving messages of its topic from us.
+    ///
+    /// Unset it to send all RPCs in the order they were queued.
+    pub subscriptions_before_messages: bool,
+
+    /// Whether [`Floodsub::publish`](crate::Floodsub::publish) and the other methods publishing
+    /// only to the topics we're subscribed to publish to any topic, like
+    /// [`Floodsub::publish_any`](crate::Floodsub::publish_any), `false` by default.
+    pub allow_publish_without_subscription: bool,
+
+    /// Whether the messages delivered to us come with a [`DeliveryConfirmation`] the application
+    /// reports its verdict on the message with, `false` by default.
+    ///
+    /// If set, messages are delivered as [`FloodsubEvent::ConfirmableMessage`] instead of
+    /// [`FloodsubEvent::Message`]. Rejected messages decrease the score of their source by
+    /// [`FloodsubConfig::rejected_message_penalty`], see
+    /// [`Floodsub::peer_score`](crate::Floodsub::peer_score). Messages delivered through
+    /// [`Floodsub::message_receiver`](crate::Floodsub::message_receiver) can't be confirmed.
+    pub confirm_deliveries: bool,
+
+    /// Amount the score of the source of a message decreases by when the application rejects
+    /// it, `1.0` by default. See [`FloodsubConfig::confirm_deliveries`].
+    pub rejected_message_penalty: f64,
+
+    /// Maximum size in bytes of the data of the messages we publish and process, 2048 bytes by
+    /// default.
+    ///
+    /// Publishing larger messages fails with
+    /// [`PublishError::MessageTooLarge`](crate::PublishError::MessageTooLarge), and larger
+    /// messages received are dropped before being delivered or forwarded. Since RPCs are limited
+    /// to 2048 bytes, raising it doesn't allow larger messages.
+    pub max_transmit_size: usize,
+
+    /// How the messages we publish are authenticated, [`MessageAuthenticity::Anonymous`] by
+    /// default.
+    ///
+    /// With [`MessageAuthenticity::Signed`], received messages are verified and dropped if their
+    /// signature is missing or invalid. Signatures cover the topics of the messages, so
+    /// forwarders with [`MultiTopicFanout::PerTopic`] invalidate them.
+    pub message_authenticity: MessageAuthenticity,
+
+    /// Algorithm the data of the messages we publish is compressed with, [`Compression::None`]
+    /// by default.
+    ///
+    /// Messages that compression doesn't make smaller are sent uncompressed. Received messages
+    /// are decompressed before being delivered, whatever this option, if the algorithm is
+    /// enabled by its feature; those that can't be decompressed, or whose decompressed data
+    /// exceeds [`FloodsubConfig::max_transmit_size`], are not delivered, but still forwarded.
+    /// Messages compressed with an algorithm we don't know are ignored, without affecting the
+    /// rest of their RPC.
+    /// Peers that don't support compression reject the RPCs carrying compressed messages.
+    pub compression: Compression,
+
+    /// How the calls subscribing or publishing after [`Floodsub::shutdown`](crate::Floodsub::shutdown)
+    /// are handled, [`CallsAfterShutdown::Fail`] by default. They never subscribe or publish.
+    pub calls_after_shutdown: CallsAfterShutdown,
+
+    /// Number of connections to a peer within [`FloodsubConfig::flap_window`] after which it is
+    /// considered flapping, disabled by default.
+    ///
+    /// A [`FloodsubEvent::PeerFlapping`](crate::FloodsubEvent::PeerFlapping) is emitted and the
+    /// peer is put on hold for [`FloodsubConfig::flap_cooldown`]: we neither send it our
+    /// subscriptions nor forward messages to it until it stays connected for that long.
+    pub flap_threshold: Option<usize>,
+
+    /// Window over which connections are counted for [`FloodsubConfig::flap_threshold`],
+    /// 1 minute by default.
+    pub flap_window: Duration,
+
+    /// How long a flapping peer is put on hold, 30 seconds by default. Reconnecting during the
+    /// cooldown restarts it.
+    pub flap_cooldown: Duration,
 }
 
 impl FloodsubConfig {
@@ -54,6 +520,313 @@ impl FloodsubConfig {
         Self {
             local_peer_id,
             subscribe_local_messages: false,
+            subscription_debounce: None,
+            sequence_number_len: 8,
+            sequence_numbers: SequenceNumbers::Random,
+            sequence_number_wraparound: SequenceNumberWraparound::Warn,
+            validate_sequence_monotonicity: false,
+            sequence_tracking_ttl: Duration::from_secs(120),
+            forward_policy: ForwardPolicy::All,
+            recent_messages_per_topic: 0,
+            record_rpc_sizes: false,
+            max_queued_rpcs_per_peer: None,
+            queue_full_policy: QueueFullPolicy::DropNewest,
+            max_pending_events: None,
+            max_messages_per_peer_per_sec: None,
+            subscribed_topics_capacity: 0,
+            startup_announce_delay: None,
+            startup_announce_jitter: Duration::ZERO,
+            message_checks: MessageCheck::DEFAULT_ORDER.to_vec(),
+            exact_dedup_window: 0,
+            cuckoo_filter_capacity: cuckoofilter::DEFAULT_CAPACITY,
+            filter_full_policy: FilterFullPolicy::Evict,
+            dedup_strategy: DedupStrategy::SourceSeq,
+            dedup_topic_weights: HashMap::new(),
+            duplicate_cache_time: None,
+            subscription_sync_interval: None,
+            max_deliveries_per_topic: None,
+            delivery_rate_window: Duration::from_secs(1),
+            delivery_overflow_policy: DeliveryOverflowPolicy::Drop,
+            emit_topology_changes: false,
+            track_unique_topics: false,
+            track_topic_stats: false,
+            message_processing_budget: None,
+            record_provenance: false,
+            max_provenance_len: 8,
+            default_ttl: u8::MAX,
+            subscription_resume_window: None,
+            rpc_flush_interval: None,
+            emit_undeliverable_messages: false,
+            unsubscribe_idle_topics: false,
+            idle_topic_timeout: Duration::from_secs(60 * 60),
+            events_spill_threshold: None,
+            events_spill_path: std::env::temp_dir().join(format!(
+                "floodsub-{local_peer_id}-{:016x}.spill",
+                rand::random::<u64>()
+            )),
+            subscription_quorum: None,
+            max_subscription_updates_per_peer: None,
+            max_subscriptions_per_peer: None,
+            lazy_peer_insert: false,
+            idle_check_interval: None,
+            subscription_update_window: Duration::from_secs(1),
+            subscription_spam_penalty: 1.0,
+            spoofed_source_policy: SpoofedSourcePolicy::Drop,
+            published_ids_window: 1024,
+            track_delivery_rate: false,
+            delivery_rate_tracking_window: Duration::from_secs(10),
+            multi_topic_fanout: MultiTopicFanout::Union,
+            role: Role::Full,
+            track_fanout_clones: false,
+            subscriptions_before_messages: true,
+            allow_publish_without_subscription: false,
+            confirm_deliveries: false,
+            rejected_message_penalty: 1.0,
+            max_transmit_size: 2048,
+            message_authenticity: MessageAuthenticity::Anonymous,
+            compression: Compression::None,
+            calls_after_shutdown: CallsAfterShutdown::Fail,
+            flap_threshold: None,
+            flap_window: Duration::from_secs(60),
+            flap_cooldown: Duration::from_secs(30),
         }
     }
 }
+
+/// Policy deciding which of the peers subscribed to the topics of a message it is sent to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForwardPolicy {
+    /// Send every message to all subscribed peers.
+    All,
+    /// Send every message to at most `fanout` of the subscribed peers, chosen through rendezvous
+    /// hashing of the message id and the peer ids.
+    ///
+    /// The choice is deterministic: nodes with the same set of subscribed peers pick the same
+    /// subset for a given message, which bounds redundant transmissions without random sampling.
+    /// With small fan-outs, peers that are not picked by any of their neighbours miss the
+    /// message.
+    ConsistentHash {
+        /// Maximum number of peers a message is sent to.
+        fanout: usize,
+    },
+    /// Send every message to at most `fanout` of the subscribed peers, chosen at random.
+    ///
+    /// Unlike [`ForwardPolicy::ConsistentHash`], every copy of a message a node receives is sent
+    /// to a different subset of peers, which makes it a cheap approximation of gossip.
+    Sampled {
+        /// Maximum number of peers a message is sent to.
+        fanout: usize,
+        /// Seed of the random number generator choosing the peers, e.g. to make the choices
+        /// reproducible in tests. Seeded from the operating system if `None`.
+        seed: Option<u64>,
+    },
+}
+
+/// Which message to drop when queueing one for a peer whose queue is full, see
+/// [`FloodsubConfig::max_queued_rpcs_per_peer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueFullPolicy {
+    /// Drop the message that was about to be queued.
+    DropNewest,
+    /// Drop the oldest message queued for the peer to make room for the new one.
+    DropOldest,
+}
+
+/// Which side of the protocol a node takes part in, see [`FloodsubConfig::role`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Publish, subscribe and forward messages.
+    Full,
+    /// Only publish messages, e.g. for a sensor.
+    ///
+    /// Subscribing does nothing, so we never announce subscriptions, and the messages we receive
+    /// are ignored instead of being forwarded. Messages are published whether we're subscribed to
+    /// their topics or not. The subscriptions of the peers are tracked as usual, to know where to
+    /// send our messages.
+    PublishOnly,
+    /// Only subscribe to topics and receive messages, e.g. for a sink. Received messages are
+    /// still forwarded, but publishing does nothing.
+    SubscribeOnly,
+    /// Only forward the messages we receive, e.g. for a relay between parts of the network.
+    ///
+    /// Subscribing does nothing and publishing fails, so that messages are never delivered to
+    /// us. See [`FloodsubMetrics::messages_relayed`](crate::FloodsubMetrics::messages_relayed).
+    Relay,
+}
+
+/// How the messages with several topics are handled, see
+/// [`FloodsubConfig::multi_topic_fanout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiTopicFanout {
+    /// Handle the message as a whole: it is delivered to us once if we're subscribed to any of
+    /// its topics, and sent once, unchanged, to every peer subscribed to any of them.
+    Union,
+    /// Handle the message as one message per topic, each with that topic only.
+    ///
+    /// Every topic is accounted for separately, at the cost of more traffic: a peer subscribed to
+    /// several of the topics receives a message for each of them, and so do we. These messages
+    /// share the id of the original one, but differ on the wire and are not duplicates of each
+    /// other, including for peers that handle messages as a whole.
+    PerTopic,
+}
+
+/// A check performed on the messages we receive, see [`FloodsubConfig::message_checks`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageCheck {
+    /// Drop the messages we already received. Passing messages are recorded as received.
+    Dedup,
+    /// Drop the messages whose sequence number is not higher than the one of a previous message
+    /// of the same source, if [`FloodsubConfig::validate_sequence_monotonicity`] is set.
+    SequenceMonotonicity,
+}
+
+impl MessageCheck {
+    /// The default order of the checks.
+    ///
+    /// Duplicates are dropped first: it is cheap, and since every message is expected to be
+    /// received several times in a flooded network, most messages are dropped there. Running the
+    /// other checks first would also report every duplicate as a replay.
+    pub const DEFAULT_ORDER: [MessageCheck; 2] =
+        [MessageCheck::Dedup, MessageCheck::SequenceMonotonicity];
+}
+
+/// What to do with the messages delivered to us over
+/// [`FloodsubConfig::max_deliveries_per_topic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryOverflowPolicy {
+    /// Drop the messages.
+    Drop,
+    /// Keep up to `capacity` messages per topic and deliver them in later windows, dropping the
+    /// messages that don't fit.
+    Buffer {
+        /// Maximum number of messages waiting to be delivered for each topic.
+        capacity: usize,
+    },
+}
+
+/// What to do with a received message that claims to be published by us but wasn't, see
+/// [`FloodsubConfig::spoofed_source_policy`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpoofedSourcePolicy {
+    /// Drop the message.
+    Drop,
+    /// Drop the message and decrease the score of the peer that sent it by `penalty`, see
+    /// [`Floodsub::peer_score`].
+    DropAndPenalize {
+        /// Amount the score of the peer is decreased by.
+        penalty: f64,
+    },
+    /// Drop the message and emit a
+    /// [`FloodsubEvent::SpoofedSource`](crate::FloodsubEvent::SpoofedSource).
+    DropAndReport,
+}
+
+/// Role in the connections of the peers messages are forwarded to first, see
+/// [`Floodsub::set_forwarding_endpoint_preference`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EndpointPreference {
+    /// No peer is forwarded to first.
+    #[default]
+    NoPreference,
+    /// Forward first to the peers we dialed, whose return paths are usually more stable than the
+    /// ones of peers behind a NAT that dialed us.
+    Outbound,
+    /// Forward first to the peers that dialed us.
+    Inbound,
+}
+
+/// What to do when the sequence numbers of published messages wrap around, see
+/// [`FloodsubConfig::sequence_number_wraparound`]. A warning is logged in all cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceNumberWraparound {
+    /// Keep counting from zero.
+    Warn,
+    /// Keep counting from zero and emit a
+    /// [`FloodsubEvent::SequenceNumbersWrapped`](crate::FloodsubEvent::SequenceNumbersWrapped).
+    Report,
+    /// Switch to [`SequenceNumbers::Random`] for the following messages.
+    SwitchToRandom,
+    /// Keep counting from zero and forget about the messages received so far, so that the
+    /// echoes of the messages we publish with repeated sequence numbers aren't dropped as
+    /// duplicates of the earlier ones. This doesn't affect the duplicates filters of the other
+    /// peers.
+    ResetDedup,
+}
+
+/// Algorithm the data of a message is compressed with, see [`FloodsubConfig::compression`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// The data is not compressed.
+    None,
+    /// The data is compressed with gzip. Requires the `gzip` feature.
+    Gzip,
+    /// The data is compressed with Zstandard. Requires the `zstd` feature.
+    Zstd,
+}
+
+/// How the messages we publish are authenticated, see [`FloodsubConfig::message_authenticity`].
+#[derive(Debug, Clone)]
+pub enum MessageAuthenticity {
+    /// Messages are signed with the keypair, and their source is its peer id, which should be
+    /// [`FloodsubConfig::local_peer_id`]. Received messages must be signed by their source.
+    Signed(Keypair),
+    /// Messages are not signed, and their source is the given peer id.
+    Author(PeerId),
+    /// Messages are not signed, and their source is [`FloodsubConfig::local_peer_id`].
+    Anonymous,
+}
+
+/// What to do when the duplicates filter is full, see [`FloodsubConfig::filter_full_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterFullPolicy {
+    /// Record the message anyway, evicting another random message, which is then processed
+    /// again if it is received again.
+    Evict,
+    /// Fail publishing with [`PublishError::FilterFull`](crate::PublishError::FilterFull),
+    /// without sending the message.
+    Fail,
+}
+
+/// How received messages are identified when detecting duplicates, see
+/// [`FloodsubConfig::dedup_strategy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupStrategy {
+    /// Messages are identified by their source, sequence number, data and topics, so that the
+    /// same data published twice is delivered twice.
+    SourceSeq,
+    /// Messages are identified by their data and topics only, so that messages with the same
+    /// content are delivered once, whatever their source and sequence number.
+    ///
+    /// Messages are remembered as long as the duplicates filter keeps them. Set
+    /// [`FloodsubConfig::duplicate_cache_time`] for repeated content to be delivered again once
+    /// that long passed. [`FloodsubConfig::exact_dedup_window`] has no effect with this strategy.
+    ContentHash,
+}
+
+/// How the calls subscribing or publishing after [`Floodsub::shutdown`](crate::Floodsub::shutdown)
+/// are handled, see [`FloodsubConfig::calls_after_shutdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallsAfterShutdown {
+    /// Publishing fails with [`PublishError::ShuttingDown`](crate::PublishError::ShuttingDown),
+    /// and subscribing returns `false`.
+    Fail,
+    /// The calls do nothing but report success: publishing returns `Ok`,
+    /// [`Floodsub::subscribe`](crate::Floodsub::subscribe) returns `true` and
+    /// [`Floodsub::subscribe_many`](crate::Floodsub::subscribe_many) the number of topics it was
+    /// given.
+    Ignore,
+}
+
+/// How the sequence numbers of published messages are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceNumbers {
+    /// Random bytes, which cannot be predicted by attackers.
+    Random,
+    /// A counter encoded in big-endian, incremented for every message and initialized to the
+    /// current time in nanoseconds, so that it keeps increasing across restarts.
+    ///
+    /// With a [`FloodsubConfig::sequence_number_len`] lower than `8`, only the low bytes of the
+    /// time are kept, so sequence numbers don't keep increasing across restarts and wrap around
+    /// sooner, see [`FloodsubConfig::sequence_number_wraparound`].
+    Monotonic,
+}
@@ -0,0 +1,385 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Statistics collected by the [`Floodsub`](crate::Floodsub) behaviour.
+//!
+//! With the `metrics` feature, they are also recorded through the global recorder of the
+//! [`metrics`](::metrics) crate, see [`recorder`].
+
+use crate::topic::Topic;
+use instant::Instant;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+/// Upper bounds of the buckets of the histogram of RPC sizes, in bytes.
+const RPC_SIZE_BUCKETS: [usize; 6] = [64, 128, 256, 512, 1024, 2048];
+
+/// Statistics about a [`Floodsub`](crate::Floodsub) behaviour, as returned by
+/// [`Floodsub::metrics`](crate::Floodsub::metrics).
+#[derive(Debug, Clone, Default)]
+pub struct FloodsubMetrics {
+    /// Serialized sizes of the subscription RPCs we send. Only recorded if
+    /// [`FloodsubConfig::record_rpc_sizes`](crate::FloodsubConfig::record_rpc_sizes) is set.
+    pub subscription_rpc_sizes: Histogram,
+
+    /// Number of received messages that the duplicates filter wrongly reported as already seen,
+    /// as detected through [`FloodsubConfig::exact_dedup_window`](crate::FloodsubConfig::exact_dedup_window).
+    pub dedup_false_positives: u64,
+
+    /// Estimation of the number of distinct topics seen since the behaviour was created. Only
+    /// recorded if [`FloodsubConfig::track_unique_topics`](crate::FloodsubConfig::track_unique_topics)
+    /// is set.
+    pub unique_topics: CardinalityEstimator,
+
+    /// Rate of the messages delivered to us. Only recorded if
+    /// [`FloodsubConfig::track_delivery_rate`](crate::FloodsubConfig::track_delivery_rate) is
+    /// set.
+    pub delivery_rate: DeliveryRate,
+
+    /// Total number of bytes of message data cloned to send messages to several peers, when
+    /// publishing or forwarding them. Only recorded if
+    /// [`FloodsubConfig::track_fanout_clones`](crate::FloodsubConfig::track_fanout_clones) is
+    /// set.
+    pub fanout_cloned_bytes: u64,
+
+    /// Number of messages evicted from the full duplicates filter to record other messages, see
+    /// [`FloodsubConfig::cuckoo_filter_capacity`](crate::FloodsubConfig::cuckoo_filter_capacity).
+    pub filter_evictions: u64,
+
+    /// Number of messages we published.
+    pub messages_published: u64,
+
+    /// Number of messages received from the network, including the ones dropped afterwards.
+    pub messages_received: u64,
+
+    /// Number of received messages dropped as duplicates.
+    pub messages_deduplicated: u64,
+
+    /// Number of times received messages were forwarded to a peer, i.e. a message forwarded to
+    /// three peers counts three times.
+    pub messages_propagated: u64,
+
+    /// Number of received messages forwarded to at least one peer without being delivered to us.
+    pub messages_relayed: u64,
+
+    /// Number of subscription changes received from the network.
+    pub subscriptions_received: u64,
+
+    /// Message counts of each topic seen. Only recorded if
+    /// [`FloodsubConfig::track_topic_stats`](crate::FloodsubConfig::track_topic_stats) is set.
+    ///
+    /// A message with several topics counts for each of them.
+    pub topics: HashMap<Topic, TopicStats>,
+}
+
+/// Message counts of a topic, see [`FloodsubMetrics::topics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopicStats {
+    /// Number of messages we published.
+    pub published: u64,
+    /// Number of messages received from the network, including the ones dropped afterwards.
+    pub received: u64,
+    /// Number of received messages forwarded to at least one peer.
+    pub forwarded: u64,
+}
+
+/// Histogram of sizes in bytes, with buckets of doubling size from 64 bytes up to the maximum
+/// size of an RPC.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    /// Number of observations for each bucket of [`RPC_SIZE_BUCKETS`], plus one for larger values.
+    counts: [u64; RPC_SIZE_BUCKETS.len() + 1],
+    sum: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram {
+            counts: [0; RPC_SIZE_BUCKETS.len() + 1],
+            sum: 0,
+        }
+    }
+}
+
+impl Histogram {
+    /// Records a single observation.
+    pub(crate) fn observe(&mut self, value: usize) {
+        let bucket = RPC_SIZE_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(RPC_SIZE_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += value as u64;
+    }
+
+    /// Returns the inclusive upper bound of each bucket with the number of observations in it,
+    /// from the smallest to the largest bucket. The last bucket has no upper bound.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<usize>, u64)> + '_ {
+        RPC_SIZE_BUCKETS
+            .iter()
+            .map(|bound| Some(*bound))
+            .chain(std::iter::once(None))
+            .zip(self.counts.iter().copied())
+    }
+
+    /// Returns the total number of observations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the sum of all observed values.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+}
+
+/// Number of bits of the hashes selecting a register of [`CardinalityEstimator`].
+const CARDINALITY_PRECISION: u32 = 10;
+
+/// Number of registers of [`CardinalityEstimator`].
+const CARDINALITY_REGISTERS: usize = 1 << CARDINALITY_PRECISION;
+
+/// HyperLogLog estimator of the number of distinct topics it observed, using a fixed kilobyte of
+/// memory. Estimations are typically within 3% of the actual number.
+#[derive(Debug, Clone)]
+pub struct CardinalityEstimator {
+    /// For each register, the highest rank of the hashes assigned to it.
+    registers: Box<[u8; CARDINALITY_REGISTERS]>,
+}
+
+impl Default for CardinalityEstimator {
+    fn default() -> Self {
+        CardinalityEstimator {
+            registers: Box::new([0; CARDINALITY_REGISTERS]),
+        }
+    }
+}
+
+impl CardinalityEstimator {
+    /// Records a topic.
+    pub(crate) fn observe(&mut self, topic: &Topic) {
+        let mut hasher = DefaultHasher::new();
+        topic.id().hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let index = (hash >> (64 - CARDINALITY_PRECISION)) as usize;
+        let remaining = hash << CARDINALITY_PRECISION;
+        let rank = (remaining.leading_zeros().min(64 - CARDINALITY_PRECISION) + 1) as u8;
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank);
+    }
+
+    /// Returns the estimated number of distinct topics observed.
+    pub fn estimate(&self) -> u64 {
+        let m = CARDINALITY_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum = self
+            .registers
+            .iter()
+            .map(|rank| 2f64.powi(-i32::from(*rank)))
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+
+        // Use linear counting for small cardinalities, for which HyperLogLog is biased.
+        let zeros = self.registers.iter().filter(|rank| **rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+/// Number of buckets the window of [`DeliveryRate`] is split into.
+const RATE_BUCKETS: u64 = 10;
+
+/// Sliding-window rate of the messages delivered to us, in total and for each topic we are
+/// subscribed to.
+///
+/// The window is split into buckets, so that the oldest tenth of the window is dropped at
+/// once; the rate is accurate to within a bucket.
+#[derive(Debug, Clone)]
+pub struct DeliveryRate {
+    window: Duration,
+    /// Start of the first bucket.
+    epoch: Instant,
+    total: RateCounter,
+    topics: HashMap<Topic, RateCounter>,
+}
+
+impl Default for DeliveryRate {
+    fn default() -> Self {
+        DeliveryRate::new(Duration::from_secs(10))
+    }
+}
+
+impl DeliveryRate {
+    pub(crate) fn new(window: Duration) -> Self {
+        DeliveryRate {
+            window,
+            epoch: Instant::now(),
+            total: RateCounter::default(),
+            topics: HashMap::new(),
+        }
+    }
+
+    /// Records a message delivered at `now` for the given topics.
+    pub(crate) fn observe<'a>(
+        &mut self,
+        topics: impl IntoIterator<Item = &'a Topic>,
+        now: Instant,
+    ) {
+        let bucket = self.bucket(now);
+        self.total.observe(bucket);
+        for topic in topics {
+            self.topics
+                .entry(topic.clone())
+                .or_default()
+                .observe(bucket);
+        }
+    }
+
+    /// Stops tracking the rate of a topic.
+    pub(crate) fn forget(&mut self, topic: &Topic) {
+        self.topics.remove(topic);
+    }
+
+    /// Returns the duration over which the rate is computed.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns the number of messages delivered per second over the window.
+    pub fn total(&self) -> f64 {
+        self.rate(&self.total)
+    }
+
+    /// Returns the number of messages of a topic delivered per second over the window.
+    pub fn topic(&self, topic: &Topic) -> f64 {
+        self.topics
+            .get(topic)
+            .map_or(0.0, |counter| self.rate(counter))
+    }
+
+    /// Returns the index of the bucket `now` falls in.
+    fn bucket(&self, now: Instant) -> u64 {
+        let bucket_len = (self.window.as_nanos() / u128::from(RATE_BUCKETS)).max(1);
+        (now.saturating_duration_since(self.epoch).as_nanos() / bucket_len) as u64
+    }
+
+    fn rate(&self, counter: &RateCounter) -> f64 {
+        let current = self.bucket(Instant::now());
+        let count = counter
+            .buckets
+            .iter()
+            .filter(|(bucket, _)| bucket + RATE_BUCKETS > current)
+            .map(|(_, count)| count)
+            .sum::<u64>();
+        count as f64 / self.window.as_secs_f64()
+    }
+}
+
+/// Number of observations in each of the last [`RATE_BUCKETS`] buckets.
+#[derive(Debug, Clone, Default)]
+struct RateCounter {
+    /// Index of the bucket with the number of observations in it, oldest first.
+    buckets: VecDeque<(u64, u64)>,
+}
+
+impl RateCounter {
+    fn observe(&mut self, bucket: u64) {
+        while matches!(self.buckets.front(), Some((oldest, _)) if oldest + RATE_BUCKETS <= bucket) {
+            self.buckets.pop_front();
+        }
+        match self.buckets.back_mut() {
+            Some((last, count)) if *last == bucket => *count += 1,
+            _ => self.buckets.push_back((bucket, 1)),
+        }
+    }
+}
+
+/// Recording of the statistics through the global recorder of the [`metrics`](::metrics) crate.
+///
+/// The following are recorded at the same points as the corresponding [`FloodsubMetrics`], and
+/// only if enabled in the [`FloodsubConfig`](crate::FloodsubConfig):
+///
+/// - `floodsub_subscription_rpc_size_bytes`, histogram of
+///   [`FloodsubMetrics::subscription_rpc_sizes`].
+/// - `floodsub_dedup_false_positives`, counter of [`FloodsubMetrics::dedup_false_positives`].
+/// - `floodsub_fanout_cloned_bytes`, counter of [`FloodsubMetrics::fanout_cloned_bytes`].
+///
+/// The following are always recorded:
+///
+/// - `floodsub_filter_evictions`, counter of [`FloodsubMetrics::filter_evictions`].
+/// - `floodsub_messages_published`, counter of [`FloodsubMetrics::messages_published`].
+/// - `floodsub_messages_received`, counter of [`FloodsubMetrics::messages_received`].
+/// - `floodsub_duplicate_messages`, counter of [`FloodsubMetrics::messages_deduplicated`].
+/// - `floodsub_messages_propagated`, counter of [`FloodsubMetrics::messages_propagated`].
+/// - `floodsub_messages_relayed`, counter of [`FloodsubMetrics::messages_relayed`].
+/// - `floodsub_subscriptions_received`, counter of [`FloodsubMetrics::subscriptions_received`].
+/// - `floodsub_messages_delivered`, counter of the messages delivered to us.
+#[cfg(feature = "metrics")]
+pub mod recorder {
+    pub(crate) fn subscription_rpc_size(size: usize) {
+        ::metrics::histogram!("floodsub_subscription_rpc_size_bytes", size as f64);
+    }
+
+    pub(crate) fn dedup_false_positive() {
+        ::metrics::increment_counter!("floodsub_dedup_false_positives");
+    }
+
+    pub(crate) fn fanout_cloned_bytes(bytes: u64) {
+        ::metrics::counter!("floodsub_fanout_cloned_bytes", bytes);
+    }
+
+    pub(crate) fn filter_eviction() {
+        ::metrics::increment_counter!("floodsub_filter_evictions");
+    }
+
+    pub(crate) fn message_published() {
+        ::metrics::increment_counter!("floodsub_messages_published");
+    }
+
+    pub(crate) fn message_delivered() {
+        ::metrics::increment_counter!("floodsub_messages_delivered");
+    }
+
+    pub(crate) fn duplicate_message() {
+        ::metrics::increment_counter!("floodsub_duplicate_messages");
+    }
+
+    pub(crate) fn message_received() {
+        ::metrics::increment_counter!("floodsub_messages_received");
+    }
+
+    pub(crate) fn messages_propagated(count: u64) {
+        ::metrics::counter!("floodsub_messages_propagated", count);
+    }
+
+    pub(crate) fn message_relayed() {
+        ::metrics::increment_counter!("floodsub_messages_relayed");
+    }
+
+    pub(crate) fn subscriptions_received(count: u64) {
+        ::metrics::counter!("floodsub_subscriptions_received", count);
+    }
+}
@@ -20,17 +20,25 @@
 
 use crate::proto;
 use crate::topic::Topic;
+use crate::{Compression, DedupStrategy};
 use asynchronous_codec::Framed;
+use fnv::FnvHasher;
 use futures::{
     io::{AsyncRead, AsyncWrite},
     Future,
 };
 use futures::{SinkExt, StreamExt};
 use libp2p_core::{InboundUpgrade, OutboundUpgrade, UpgradeInfo};
-use libp2p_identity::PeerId;
+use libp2p_identity::{PeerId, PublicKey};
+use log::debug;
+use quick_protobuf::MessageWrite;
+use std::hash::Hasher;
 use std::{io, iter, pin::Pin};
 
-const MAX_MESSAGE_LEN_BYTES: usize = 2048;
+pub(crate) const MAX_MESSAGE_LEN_BYTES: usize = 2048;
+
+/// Prefix of the bytes signed by the source of a message, see [`FloodsubMessage::signature`].
+const SIGNING_PREFIX: &[u8] = b"libp2p-floodsub:";
 
 const PROTOCOL_NAME: &[u8] = b"/floodsub/1.0.0";
 
@@ -75,32 +83,7 @@ where
                 .ok_or_else(|| FloodsubError::ReadError(io::ErrorKind::UnexpectedEof.into()))?
                 .map_err(CodecError)?;
 
-            let mut messages = Vec::with_capacity(rpc.publish.len());
-            for publish in rpc.publish.into_iter() {
-                messages.push(FloodsubMessage {
-                    source: PeerId::from_bytes(&publish.from.unwrap_or_default())
-                        .map_err(|_| FloodsubError::InvalidPeerId)?,
-                    data: publish.data.unwrap_or_default(),
-                    sequence_number: publish.seqno.unwrap_or_default(),
-                    topics: publish.topic_ids.into_iter().map(Topic::new).collect(),
-                });
-            }
-
-            Ok(FloodsubRpc {
-                messages,
-                subscriptions: rpc
-                    .subscriptions
-                    .into_iter()
-                    .map(|sub| FloodsubSubscription {
-                        action: if Some(true) == sub.subscribe {
-                            FloodsubSubscriptionAction::Subscribe
-                        } else {
-                            FloodsubSubscriptionAction::Unsubscribe
-                        },
-                        topic: Topic::new(sub.topic_id.unwrap_or_default()),
-                    })
-                    .collect(),
-            })
+            FloodsubRpc::from_rpc(rpc)
         })
     }
 }
@@ -130,6 +113,8 @@ pub struct FloodsubRpc {
     pub messages: Vec<FloodsubMessage>,
     /// List of subscriptions.
     pub subscriptions: Vec<FloodsubSubscription>,
+    /// Information for reconciling the views of the subscriptions of each other, if any.
+    pub sync: Option<FloodsubSubscriptionSync>,
 }
 
 impl UpgradeInfo for FloodsubRpc {
@@ -163,8 +148,83 @@ where
 }
 
 impl FloodsubRpc {
+    /// Returns the size of this `FloodsubRpc` once serialized.
+    pub(crate) fn encoded_len(&self) -> usize {
+        self.clone().into_rpc().get_size()
+    }
+
+    /// Turns a message received from a substream into a `FloodsubRpc`.
+    pub(crate) fn from_rpc(rpc: proto::RPC) -> Result<Self, FloodsubError> {
+        let mut messages = Vec::with_capacity(rpc.publish.len());
+        for publish in rpc.publish.into_iter() {
+            // Only the messages compressed with an algorithm we don't know are rejected, not the
+            // rest of the RPC.
+            let compression = match compression_from_wire(publish.compression) {
+                Some(compression) => compression,
+                None => {
+                    debug!(
+                        "Ignoring message compressed with unknown algorithm {:?}",
+                        publish.compression
+                    );
+                    continue;
+                }
+            };
+            messages.push(FloodsubMessage {
+                source: PeerId::from_bytes(&publish.from.unwrap_or_default())
+                    .map_err(|_| FloodsubError::InvalidPeerId)?,
+                data: publish.data.unwrap_or_default(),
+                sequence_number: publish.seqno.unwrap_or_default(),
+                topics: publish.topic_ids.into_iter().map(Topic::new).collect(),
+                signature: publish.signature,
+                key: publish.key,
+                provenance: publish
+                    .provenance
+                    .iter()
+                    .map(|peer_id| PeerId::from_bytes(peer_id))
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| FloodsubError::InvalidPeerId)?,
+                // Peers that don't know about TTLs don't limit the number of hops.
+                ttl: publish
+                    .ttl
+                    .map_or(u8::MAX, |ttl| ttl.min(u8::MAX.into()) as u8),
+                compression,
+            });
+        }
+
+        let sync = rpc.sync.and_then(|sync| {
+            if let Some(digest) = sync.digest {
+                Some(FloodsubSubscriptionSync::Digest(digest))
+            } else if sync.request_full == Some(true) {
+                Some(FloodsubSubscriptionSync::RequestFull)
+            } else if sync.full == Some(true) {
+                Some(FloodsubSubscriptionSync::Full)
+            } else if sync.partial == Some(true) {
+                Some(FloodsubSubscriptionSync::Partial)
+            } else {
+                None
+            }
+        });
+
+        Ok(FloodsubRpc {
+            messages,
+            subscriptions: rpc
+                .subscriptions
+                .into_iter()
+                .map(|sub| FloodsubSubscription {
+                    action: if Some(true) == sub.subscribe {
+                        FloodsubSubscriptionAction::Subscribe
+                    } else {
+                        FloodsubSubscriptionAction::Unsubscribe
+                    },
+                    topic: Topic::new(sub.topic_id.unwrap_or_default()),
+                })
+                .collect(),
+            sync,
+        })
+    }
+
     /// Turns this `FloodsubRpc` into a message that can be sent to a substream.
-    fn into_rpc(self) -> proto::RPC {
+    pub(crate) fn into_rpc(self) -> proto::RPC {
         proto::RPC {
             publish: self
                 .messages
@@ -174,6 +234,15 @@ impl FloodsubRpc {
                     data: Some(msg.data),
                     seqno: Some(msg.sequence_number),
                     topic_ids: msg.topics.into_iter().map(|topic| topic.into()).collect(),
+                    signature: msg.signature,
+                    key: msg.key,
+                    provenance: msg
+                        .provenance
+                        .iter()
+                        .map(|peer_id| peer_id.to_bytes())
+                        .collect(),
+                    ttl: Some(msg.ttl.into()),
+                    compression: compression_to_wire(msg.compression),
                 })
                 .collect(),
 
@@ -185,6 +254,25 @@ impl FloodsubRpc {
                     topic_id: Some(topic.topic.into()),
                 })
                 .collect(),
+
+            sync: self.sync.map(|sync| match sync {
+                FloodsubSubscriptionSync::Digest(digest) => proto::SubscriptionSync {
+                    digest: Some(digest),
+                    ..Default::default()
+                },
+                FloodsubSubscriptionSync::RequestFull => proto::SubscriptionSync {
+                    request_full: Some(true),
+                    ..Default::default()
+                },
+                FloodsubSubscriptionSync::Full => proto::SubscriptionSync {
+                    full: Some(true),
+                    ..Default::default()
+                },
+                FloodsubSubscriptionSync::Partial => proto::SubscriptionSync {
+                    partial: Some(true),
+                    ..Default::default()
+                },
+            }),
         }
     }
 }
@@ -205,6 +293,163 @@ pub struct FloodsubMessage {
     ///
     /// Each message can belong to multiple topics at once.
     pub topics: Vec<Topic>,
+
+    /// Signature of the source over the other fields of the message but the provenance and TTL,
+    /// if signed. See
+    /// [`FloodsubConfig::message_authenticity`](crate::FloodsubConfig::message_authenticity).
+    pub signature: Option<Vec<u8>>,
+
+    /// Protobuf encoding of the public key of the source, if the message is signed and the key
+    /// can't be extracted from the peer id of the source.
+    pub key: Option<Vec<u8>>,
+
+    /// Peers that forwarded this message, from the first to the last, if they record it. See
+    /// [`FloodsubConfig::record_provenance`](crate::FloodsubConfig::record_provenance).
+    ///
+    /// Not taken into account when detecting duplicates.
+    pub provenance: Vec<PeerId>,
+
+    /// Remaining number of hops the message is forwarded over. Every peer forwarding the message
+    /// decrements it, and messages reaching zero are delivered but not forwarded. See
+    /// [`FloodsubConfig::default_ttl`](crate::FloodsubConfig::default_ttl).
+    ///
+    /// Messages sent by peers that don't set it have a TTL of [`u8::MAX`]. Not taken into account
+    /// when detecting duplicates.
+    pub ttl: u8,
+
+    /// Algorithm [`FloodsubMessage::data`] is compressed with. See
+    /// [`FloodsubConfig::compression`](crate::FloodsubConfig::compression).
+    ///
+    /// Messages are delivered decompressed, but forwarded as they were received.
+    pub compression: Compression,
+}
+
+impl FloodsubMessage {
+    /// Returns the bytes the source signs, i.e. [`SIGNING_PREFIX`] followed by the protobuf
+    /// encoding of the message without its signature, key, provenance and TTL.
+    pub(crate) fn signing_bytes(&self) -> Vec<u8> {
+        let message = proto::Message {
+            from: Some(self.source.to_bytes()),
+            data: Some(self.data.clone()),
+            seqno: Some(self.sequence_number.clone()),
+            topic_ids: self
+                .topics
+                .iter()
+                .map(|topic| topic.id().to_owned())
+                .collect(),
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: None,
+            compression: compression_to_wire(self.compression),
+        };
+        let mut bytes = SIGNING_PREFIX.to_vec();
+        bytes.extend(quick_protobuf::serialize_into_vec(&message).expect("Encoding to succeed"));
+        bytes
+    }
+
+    /// Returns true if the message is signed by its source.
+    pub(crate) fn verify_signature(&self) -> bool {
+        let signature = match &self.signature {
+            Some(signature) => signature,
+            None => {
+                debug!("Signature verification failed: No signature provided");
+                return false;
+            }
+        };
+
+        // Use the key of the message if any, or the one inlined in the peer id of the source.
+        let public_key = match self.key.as_deref().map(PublicKey::from_protobuf_encoding) {
+            Some(Ok(key)) => key,
+            _ => match inline_public_key(&self.source) {
+                Some(key) => key,
+                None => {
+                    debug!("Signature verification failed: No valid public key supplied");
+                    return false;
+                }
+            },
+        };
+        if public_key.to_peer_id() != self.source {
+            debug!("Signature verification failed: Public key doesn't match source peer id");
+            return false;
+        }
+
+        public_key.verify(&self.signing_bytes(), signature)
+    }
+
+    /// Returns the identifier of this message.
+    pub fn id(&self) -> MessageId {
+        MessageId {
+            source: self.source,
+            sequence_number: self.sequence_number.clone(),
+        }
+    }
+
+    /// Returns the fields identifying this message when detecting duplicates.
+    pub(crate) fn dedup_key(&self, strategy: DedupStrategy) -> DedupKey<'_> {
+        match strategy {
+            DedupStrategy::SourceSeq => DedupKey::SourceSeq {
+                source: &self.source,
+                data: &self.data,
+                sequence_number: &self.sequence_number,
+                topics: &self.topics,
+            },
+            DedupStrategy::ContentHash => DedupKey::Content {
+                data: &self.data,
+                topics: &self.topics,
+            },
+        }
+    }
+}
+
+/// Fields identifying a message when detecting duplicates, see [`DedupStrategy`].
+#[derive(Debug, Hash)]
+pub(crate) enum DedupKey<'a> {
+    SourceSeq {
+        source: &'a PeerId,
+        data: &'a [u8],
+        sequence_number: &'a [u8],
+        topics: &'a [Topic],
+    },
+    Content {
+        data: &'a [u8],
+        topics: &'a [Topic],
+    },
+}
+
+/// Returns the compression algorithm with the given protobuf value, if known.
+fn compression_from_wire(value: Option<u32>) -> Option<Compression> {
+    match value {
+        None | Some(0) => Some(Compression::None),
+        Some(1) => Some(Compression::Gzip),
+        Some(2) => Some(Compression::Zstd),
+        Some(_) => None,
+    }
+}
+
+/// Returns the protobuf value of a compression algorithm, unset if uncompressed for the messages
+/// to be understood by the peers that don't support compression.
+fn compression_to_wire(compression: Compression) -> Option<u32> {
+    match compression {
+        Compression::None => None,
+        Compression::Gzip => Some(1),
+        Compression::Zstd => Some(2),
+    }
+}
+
+/// Returns the public key inlined in a peer id, if any.
+pub(crate) fn inline_public_key(peer_id: &PeerId) -> Option<PublicKey> {
+    // Peer ids of small keys are the identity multihash of their protobuf encoding.
+    PublicKey::from_protobuf_encoding(peer_id.to_bytes().get(2..)?).ok()
+}
+
+/// Identifier of a message, made of its source and sequence number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MessageId {
+    /// Id of the peer that published the message.
+    pub source: PeerId,
+    /// Sequence number of the message.
+    pub sequence_number: Vec<u8>,
 }
 
 /// A subscription received by the floodsub system.
@@ -224,3 +469,33 @@ pub enum FloodsubSubscriptionAction {
     /// The remote wants to unsubscribe from the given topic.
     Unsubscribe,
 }
+
+/// Part of an RPC used to detect and correct diverging views of the subscriptions of a peer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FloodsubSubscriptionSync {
+    /// Digest of all the subscriptions of the sender, as computed by [`subscription_digest`].
+    Digest(Vec<u8>),
+    /// The sender's view of our subscriptions doesn't match our digest, and it asks for all of
+    /// our subscriptions.
+    RequestFull,
+    /// The subscriptions of the RPC are all the subscriptions of the sender, or the last part of
+    /// them if they were split over several RPCs.
+    Full,
+    /// The subscriptions of the RPC are part of all the subscriptions of the sender, which are
+    /// split over several RPCs. The last one is marked [`FloodsubSubscriptionSync::Full`].
+    Partial,
+}
+
+/// Computes the digest of a set of subscriptions, regardless of their order.
+pub(crate) fn subscription_digest<'a>(topics: impl IntoIterator<Item = &'a Topic>) -> Vec<u8> {
+    let mut ids = topics.into_iter().map(Topic::id).collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+    let mut hasher = FnvHasher::default();
+    for id in ids {
+        hasher.write(id.as_bytes());
+        // Separate the topics so that e.g. `["ab"]` and `["a", "b"]` differ.
+        hasher.write_u8(0);
+    }
+    hasher.finish().to_be_bytes().to_vec()
+}
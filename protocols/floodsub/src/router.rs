@@ -0,0 +1,143 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Selection of the peers a message is sent to.
+
+use crate::protocol::FloodsubMessage;
+use crate::ForwardPolicy;
+use fnv::FnvHasher;
+use libp2p_identity::PeerId;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::hash::Hasher;
+
+/// Decides which of the peers subscribed to the topics of a message it is sent to.
+///
+/// Set on the behaviour with [`Floodsub::set_router`](crate::Floodsub::set_router). By default,
+/// the router corresponding to [`FloodsubConfig::forward_policy`](crate::FloodsubConfig::forward_policy)
+/// is used.
+pub trait TopicRouter: Send + 'static {
+    /// Returns the peers to send `message` to.
+    ///
+    /// `candidates` are the connected peers that are subscribed to at least one of the topics of
+    /// the message, excluding the peer we received it from. Returning peers that are not part of
+    /// `candidates` is allowed but unusual.
+    fn route(&mut self, message: &FloodsubMessage, candidates: Vec<PeerId>) -> Vec<PeerId>;
+}
+
+/// [`TopicRouter`] sending every message to all the candidates, i.e. flooding.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllSubscribed;
+
+impl TopicRouter for AllSubscribed {
+    fn route(&mut self, _: &FloodsubMessage, candidates: Vec<PeerId>) -> Vec<PeerId> {
+        candidates
+    }
+}
+
+/// [`TopicRouter`] sending every message to at most `fanout` candidates, chosen through
+/// rendezvous hashing of the message id and the peer ids. See [`ForwardPolicy::ConsistentHash`].
+#[derive(Debug, Clone, Copy)]
+pub struct ConsistentHash {
+    fanout: usize,
+}
+
+impl ConsistentHash {
+    /// Creates a router sending every message to at most `fanout` peers.
+    pub fn new(fanout: usize) -> Self {
+        ConsistentHash { fanout }
+    }
+}
+
+impl TopicRouter for ConsistentHash {
+    fn route(&mut self, message: &FloodsubMessage, candidates: Vec<PeerId>) -> Vec<PeerId> {
+        // Rank the candidates by the hash of the message id combined with their peer id, and keep
+        // the highest ranked ones. Every node ranks any given set of peers identically for a given
+        // message.
+        let mut ranked = candidates
+            .into_iter()
+            .map(|peer_id| {
+                let mut hasher = FnvHasher::default();
+                hasher.write(&message.source.to_bytes());
+                hasher.write(&message.sequence_number);
+                hasher.write(&peer_id.to_bytes());
+                (hasher.finish(), peer_id)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_unstable_by(|a, b| b.cmp(a));
+        ranked
+            .into_iter()
+            .take(self.fanout)
+            .map(|(_, peer_id)| peer_id)
+            .collect()
+    }
+}
+
+/// [`TopicRouter`] sending every message to at most `fanout` candidates, chosen at random. See
+/// [`ForwardPolicy::Sampled`].
+#[derive(Debug, Clone)]
+pub struct Sampled {
+    fanout: usize,
+    rng: StdRng,
+}
+
+impl Sampled {
+    /// Creates a router sending every message to at most `fanout` peers, with a random number
+    /// generator seeded from the operating system.
+    pub fn new(fanout: usize) -> Self {
+        Sampled {
+            fanout,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Creates a router sending every message to at most `fanout` peers, with a random number
+    /// generator seeded from `seed`. Routers with the same seed make the same choices given the
+    /// same sequence of candidates.
+    pub fn with_seed(fanout: usize, seed: u64) -> Self {
+        Sampled {
+            fanout,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl TopicRouter for Sampled {
+    fn route(&mut self, _: &FloodsubMessage, mut candidates: Vec<PeerId>) -> Vec<PeerId> {
+        // The candidates come in an arbitrary order, sort them for the choices to only depend on
+        // the seed.
+        candidates.sort_unstable();
+        let (sampled, _) = candidates.partial_shuffle(&mut self.rng, self.fanout);
+        sampled.to_vec()
+    }
+}
+
+/// Builds the router implementing a [`ForwardPolicy`].
+pub(crate) fn from_policy(policy: &ForwardPolicy) -> Box<dyn TopicRouter> {
+    match policy {
+        ForwardPolicy::All => Box::new(AllSubscribed),
+        ForwardPolicy::ConsistentHash { fanout } => Box::new(ConsistentHash::new(*fanout)),
+        ForwardPolicy::Sampled { fanout, seed } => match seed {
+            Some(seed) => Box::new(Sampled::with_seed(*fanout, *seed)),
+            None => Box::new(Sampled::new(*fanout)),
+        },
+    }
+}
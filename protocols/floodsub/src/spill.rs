@@ -0,0 +1,143 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Disk-backed overflow of the RPCs queued by the behaviour, see
+//! [`FloodsubConfig::events_spill_threshold`](crate::FloodsubConfig::events_spill_threshold).
+
+use crate::proto;
+use crate::protocol::FloodsubRpc;
+use libp2p_identity::PeerId;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// FIFO queue of RPCs stored in a file.
+///
+/// Records are appended at the end and read from the front. The file is truncated whenever all
+/// the records have been read, so that it only grows as long as the queue doesn't drain.
+#[derive(Debug)]
+pub(crate) struct SpillQueue {
+    file: File,
+    /// Path of the file, removed when the queue is dropped.
+    path: PathBuf,
+    /// Offset of the first record that hasn't been read yet.
+    read_pos: u64,
+    /// Offset at which the next record is written.
+    write_pos: u64,
+    /// Number of records that haven't been read yet.
+    len: usize,
+}
+
+impl SpillQueue {
+    /// Creates the file at `path`, failing if anything already exists there, symlinks included.
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        Ok(SpillQueue {
+            file,
+            path: path.to_owned(),
+            read_pos: 0,
+            write_pos: 0,
+            len: 0,
+        })
+    }
+
+    /// Returns the number of RPCs in the queue.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the queue is empty.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends an RPC for `peer_id` at the end of the queue.
+    pub(crate) fn push(&mut self, peer_id: &PeerId, rpc: FloodsubRpc) -> io::Result<()> {
+        let peer_id = peer_id.to_bytes();
+        let rpc = quick_protobuf::serialize_into_vec(&rpc.into_rpc())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut record = Vec::with_capacity(8 + peer_id.len() + rpc.len());
+        record.extend_from_slice(&(peer_id.len() as u32).to_be_bytes());
+        record.extend_from_slice(&peer_id);
+        record.extend_from_slice(&(rpc.len() as u32).to_be_bytes());
+        record.extend_from_slice(&rpc);
+
+        self.file.seek(SeekFrom::Start(self.write_pos))?;
+        self.file.write_all(&record)?;
+        self.write_pos += record.len() as u64;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the RPC at the front of the queue, if any.
+    pub(crate) fn pop(&mut self) -> io::Result<Option<(PeerId, FloodsubRpc)>> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+
+        self.file.seek(SeekFrom::Start(self.read_pos))?;
+        let peer_id = read_field(&mut self.file)?;
+        let rpc = read_field(&mut self.file)?;
+        self.read_pos += 8 + peer_id.len() as u64 + rpc.len() as u64;
+        self.len -= 1;
+        if self.len == 0 {
+            self.file.set_len(0)?;
+            self.read_pos = 0;
+            self.write_pos = 0;
+        }
+
+        let peer_id = PeerId::from_bytes(&peer_id)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let rpc = quick_protobuf::deserialize_from_slice::<proto::RPC>(&rpc)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let rpc = FloodsubRpc::from_rpc(rpc)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Some((peer_id, rpc)))
+    }
+
+    /// Removes all the RPCs of the queue.
+    pub(crate) fn clear(&mut self) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.read_pos = 0;
+        self.write_pos = 0;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl Drop for SpillQueue {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Reads a field prefixed with its length as a big-endian `u32`.
+fn read_field(file: &mut File) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    file.read_exact(&mut len)?;
+    let mut field = vec![0; u32::from_be_bytes(len) as usize];
+    file.read_exact(&mut field)?;
+    Ok(field)
+}
@@ -0,0 +1,659 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! In-memory network of [`Floodsub`] behaviours, for testing.
+//!
+//! The behaviours are driven directly, without a [`Swarm`](libp2p_swarm::Swarm) or transport:
+//! the RPCs a node emits are handed over to the receiving node synchronously by
+//! [`Network::run_until_idle`]. Links can be cut with [`Network::partition`] to observe how
+//! messages propagate, and how they are deduplicated, once the network heals, and slowed down
+//! with [`Network::set_latency`] to exercise timing-sensitive logic.
+//!
+//! RPCs captured from a single node can also be fed back to a behaviour with [`replay`], and
+//! the deduplication of messages can be checked against reference vectors with [`check_dedup`].
+
+use crate::layer::{Floodsub, FloodsubEvent, InnerMessage};
+use crate::protocol::{
+    FloodsubMessage, FloodsubRpc, FloodsubSubscription, FloodsubSubscriptionAction,
+};
+use crate::topic::Topic;
+use crate::{Compression, FloodsubConfig};
+use instant::Instant;
+use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
+use libp2p_identity::PeerId;
+use libp2p_swarm::behaviour::{ConnectionClosed, ConnectionEstablished, FromSwarm};
+use libp2p_swarm::{
+    AddressRecord, ConnectionId, NetworkBehaviour, NetworkBehaviourAction, PollParameters,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A set of [`Floodsub`] behaviours exchanging RPCs in memory.
+pub struct Network {
+    nodes: HashMap<PeerId, Floodsub>,
+    /// Pairs of nodes that are connected, smallest peer id first.
+    links: HashSet<(PeerId, PeerId)>,
+    /// Pairs of nodes between which RPCs are dropped, smallest peer id first.
+    partitioned: HashSet<(PeerId, PeerId)>,
+    /// Latency of the links that have one, smallest peer id first.
+    latencies: HashMap<(PeerId, PeerId), Duration>,
+    /// Time elapsed on the virtual clock of the links.
+    now: Duration,
+    /// RPCs emitted by a node and not delivered yet, with their sender, receiver and when they
+    /// are delivered.
+    in_flight: Vec<(PeerId, PeerId, FloodsubRpc, Duration)>,
+}
+
+impl Network {
+    /// Creates an empty network.
+    pub fn new() -> Self {
+        Network {
+            nodes: HashMap::new(),
+            links: HashSet::new(),
+            partitioned: HashSet::new(),
+            latencies: HashMap::new(),
+            now: Duration::ZERO,
+            in_flight: Vec::new(),
+        }
+    }
+
+    /// Adds a behaviour to the network and returns its peer id, as found in its configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a behaviour with the same peer id is already part of the network.
+    pub fn add_node(&mut self, floodsub: Floodsub) -> PeerId {
+        let peer_id = *floodsub.local_peer_id();
+        let previous = self.nodes.insert(peer_id, floodsub);
+        assert!(previous.is_none(), "Duplicate node {peer_id}");
+        peer_id
+    }
+
+    /// Returns the behaviour of a node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is not part of the network.
+    pub fn node(&self, peer_id: &PeerId) -> &Floodsub {
+        &self.nodes[peer_id]
+    }
+
+    /// Returns the behaviour of a node, to subscribe or publish for example.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node is not part of the network.
+    pub fn node_mut(&mut self, peer_id: &PeerId) -> &mut Floodsub {
+        self.nodes.get_mut(peer_id).expect("Unknown node")
+    }
+
+    /// Connects two nodes and adds each to the partial view of the other.
+    pub fn connect(&mut self, a: PeerId, b: PeerId) {
+        if !self.links.insert(link(a, b)) {
+            return;
+        }
+        for (local, remote) in [(a, b), (b, a)] {
+            let endpoint = ConnectedPoint::Dialer {
+                address: Multiaddr::empty(),
+                role_override: Endpoint::Dialer,
+            };
+            let node = self.node_mut(&local);
+            node.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id: remote,
+                connection_id: connection_id(),
+                endpoint: &endpoint,
+                failed_addresses: &[],
+                other_established: 0,
+            }));
+            node.add_node_to_partial_view(remote);
+        }
+    }
+
+    /// Closes the connection between two nodes.
+    ///
+    /// The nodes keep each other in their partial view and thus ask to dial again, which the
+    /// network ignores; use [`Network::connect`] to reconnect them.
+    pub fn disconnect(&mut self, a: PeerId, b: PeerId) {
+        if !self.links.remove(&link(a, b)) {
+            return;
+        }
+        for (local, remote) in [(a, b), (b, a)] {
+            let endpoint = ConnectedPoint::Dialer {
+                address: Multiaddr::empty(),
+                role_override: Endpoint::Dialer,
+            };
+            self.node_mut(&local)
+                .on_swarm_event(FromSwarm::ConnectionClosed(ConnectionClosed {
+                    peer_id: remote,
+                    connection_id: connection_id(),
+                    endpoint: &endpoint,
+                    handler: Default::default(),
+                    remaining_established: 0,
+                }));
+        }
+    }
+
+    /// Drops every RPC sent between a node of `side_a` and a node of `side_b`, in both
+    /// directions, until [`Network::heal`] is called.
+    ///
+    /// The connections stay open: the nodes are not aware of the partition.
+    pub fn partition(&mut self, side_a: &[PeerId], side_b: &[PeerId]) {
+        for a in side_a {
+            for b in side_b {
+                self.partitioned.insert(link(*a, *b));
+            }
+        }
+    }
+
+    /// Removes all the partitions. RPCs dropped in the meantime are lost.
+    pub fn heal(&mut self) {
+        self.partitioned.clear();
+    }
+
+    /// Returns true if RPCs sent between the two nodes are currently dropped.
+    pub fn is_partitioned(&self, a: PeerId, b: PeerId) -> bool {
+        self.partitioned.contains(&link(a, b))
+    }
+
+    /// Delays the RPCs sent between two nodes, in both directions, by `latency` on the virtual
+    /// clock of the network. A zero latency delivers them right away again.
+    ///
+    /// The delay only applies to the RPCs sent from now on. The virtual clock is moved forward
+    /// with [`Network::advance`]; the timers of the behaviours themselves keep using the system
+    /// clock.
+    pub fn set_latency(&mut self, a: PeerId, b: PeerId, latency: Duration) {
+        if latency.is_zero() {
+            self.latencies.remove(&link(a, b));
+        } else {
+            self.latencies.insert(link(a, b), latency);
+        }
+    }
+
+    /// Returns the time elapsed on the virtual clock of the network.
+    pub fn elapsed(&self) -> Duration {
+        self.now
+    }
+
+    /// Moves the virtual clock of the network forward by `duration`, then runs it until idle and
+    /// returns the events generated in the meantime. See [`Network::run_until_idle`].
+    pub fn advance(&mut self, duration: Duration) -> Vec<(PeerId, FloodsubEvent)> {
+        self.now += duration;
+        self.run_until_idle()
+    }
+
+    /// Returns the number of RPCs that are delayed by the latency of their link.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Polls all the nodes and delivers the RPCs they send until none of them has anything left
+    /// to do, and returns the events they generated in the meantime.
+    ///
+    /// RPCs to nodes that are not connected to the sender, or across a partition, are dropped.
+    /// RPCs between connected nodes are reported as sent to their sender, even across a
+    /// partition. RPCs delayed by [`Network::set_latency`] past the current time of the virtual
+    /// clock stay in flight. Dial requests are ignored.
+    pub fn run_until_idle(&mut self) -> Vec<(PeerId, FloodsubEvent)> {
+        let mut events = Vec::new();
+        let mut peer_ids = self.nodes.keys().copied().collect::<Vec<_>>();
+        peer_ids.sort();
+
+        loop {
+            let mut progress = false;
+
+            for peer_id in &peer_ids {
+                let node = self.nodes.get_mut(peer_id).expect("Known node");
+                for action in drain_actions(node) {
+                    progress = true;
+                    match action {
+                        NetworkBehaviourAction::GenerateEvent(event) => {
+                            events.push((*peer_id, event))
+                        }
+                        NetworkBehaviourAction::NotifyHandler {
+                            peer_id: remote,
+                            event,
+                            ..
+                        } => {
+                            let latency = self
+                                .latencies
+                                .get(&link(*peer_id, remote))
+                                .copied()
+                                .unwrap_or_default();
+                            self.in_flight
+                                .push((*peer_id, remote, event, self.now + latency))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let now = self.now;
+            let (mut due, pending) = std::mem::take(&mut self.in_flight)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(_, _, _, deliver_at)| *deliver_at <= now);
+            self.in_flight = pending;
+            // Stable sort, RPCs due at the same time are delivered in the order they were sent.
+            due.sort_by_key(|(_, _, _, deliver_at)| *deliver_at);
+            for (sender, receiver, rpc, _) in due {
+                progress = true;
+                let link = link(sender, receiver);
+                if !self.links.contains(&link) {
+                    continue;
+                }
+                // The sender isn't aware of partitions and sees the RPC as sent.
+                if let Some(node) = self.nodes.get_mut(&sender) {
+                    node.on_connection_handler_event(receiver, connection_id(), InnerMessage::Sent);
+                }
+                if self.partitioned.contains(&link) {
+                    continue;
+                }
+                if let Some(node) = self.nodes.get_mut(&receiver) {
+                    node.on_connection_handler_event(sender, connection_id(), rpc.into());
+                }
+            }
+
+            if !progress {
+                return events;
+            }
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network::new()
+    }
+}
+
+/// An RPC received by a node, as captured for [`replay`].
+#[derive(Debug, Clone)]
+pub struct CapturedRpc {
+    /// When the RPC was received, relative to the start of the capture.
+    pub at: Duration,
+    /// Remote the RPC was received from.
+    pub source: PeerId,
+    /// The RPC itself.
+    pub rpc: FloodsubRpc,
+}
+
+/// Feeds a captured sequence of inbound RPCs to a behaviour, to reproduce its processing
+/// deterministically, and returns for each RPC the actions the behaviour yielded right after.
+///
+/// The RPCs are replayed in the order of their [`CapturedRpc::at`], without waiting in between.
+/// The behaviour is notified of a connection to each source it isn't connected to yet, and the
+/// actions it yields as a consequence are part of the ones of the first RPC of the source.
+pub fn replay(
+    floodsub: &mut Floodsub,
+    trace: impl IntoIterator<Item = CapturedRpc>,
+) -> Vec<Vec<NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>>> {
+    let mut trace = trace.into_iter().collect::<Vec<_>>();
+    trace.sort_by_key(|captured| captured.at);
+
+    trace
+        .into_iter()
+        .map(|captured| {
+            inject(floodsub, captured.source, captured.rpc);
+            drain_actions(floodsub)
+        })
+        .collect()
+}
+
+/// Feeds an inbound RPC to a behaviour without polling it, notifying it first of a connection to
+/// the source if it isn't connected to it yet.
+pub fn inject(floodsub: &mut Floodsub, source: PeerId, rpc: FloodsubRpc) {
+    if !floodsub.is_connected(&source) {
+        connect_peer(floodsub, source);
+    }
+    receive_rpc(floodsub, source, rpc);
+}
+
+/// Returns an RPC subscribing to `topic`, as sent by a peer joining it.
+pub fn subscribe_rpc(topic: Topic) -> FloodsubRpc {
+    FloodsubRpc {
+        messages: Vec::new(),
+        subscriptions: vec![FloodsubSubscription {
+            action: FloodsubSubscriptionAction::Subscribe,
+            topic,
+        }],
+        sync: None,
+    }
+}
+
+/// Notifies a behaviour of a first connection to a peer, without polling it.
+pub fn connect_peer(floodsub: &mut Floodsub, peer_id: PeerId) {
+    open_connection(floodsub, peer_id, 0);
+}
+
+/// Notifies a behaviour of a connection to a peer it has `other_established` other connections
+/// to, without polling it.
+pub fn open_connection(floodsub: &mut Floodsub, peer_id: PeerId, other_established: usize) {
+    let endpoint = ConnectedPoint::Dialer {
+        address: Multiaddr::empty(),
+        role_override: Endpoint::Dialer,
+    };
+    floodsub.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+        peer_id,
+        connection_id: connection_id(),
+        endpoint: &endpoint,
+        failed_addresses: &[],
+        other_established,
+    }));
+}
+
+/// Notifies a behaviour that a connection to a peer closed, leaving `remaining_established`
+/// other connections to it, without polling it.
+pub fn close_connection(floodsub: &mut Floodsub, peer_id: PeerId, remaining_established: usize) {
+    let endpoint = ConnectedPoint::Dialer {
+        address: Multiaddr::empty(),
+        role_override: Endpoint::Dialer,
+    };
+    floodsub.on_swarm_event(FromSwarm::ConnectionClosed(ConnectionClosed {
+        peer_id,
+        connection_id: connection_id(),
+        endpoint: &endpoint,
+        handler: Default::default(),
+        remaining_established,
+    }));
+}
+
+/// Notifies a behaviour that the handler of a connection to a peer sent an RPC, without polling
+/// it.
+pub fn rpc_sent(floodsub: &mut Floodsub, peer_id: PeerId) {
+    floodsub.on_connection_handler_event(peer_id, connection_id(), InnerMessage::Sent);
+}
+
+/// Feeds an inbound RPC to a behaviour without polling it, whether or not it was notified of a
+/// connection to the source, e.g. to reproduce an RPC racing with the connection.
+pub fn receive_rpc(floodsub: &mut Floodsub, source: PeerId, rpc: FloodsubRpc) {
+    floodsub.on_connection_handler_event(source, connection_id(), rpc.into());
+}
+
+/// Sequence of received messages along with whether each of them is expected to be detected as
+/// a duplicate, codifying the deduplication semantics of floodsub.
+///
+/// See [`dedup_vectors`] for the reference vectors and [`check_dedup`] to run them.
+#[derive(Debug, Clone)]
+pub struct DedupVector {
+    /// Name of the vector, describing the property it checks.
+    pub name: &'static str,
+    /// Messages received, in order.
+    pub steps: Vec<DedupStep>,
+}
+
+/// A message of a [`DedupVector`].
+#[derive(Debug, Clone)]
+pub struct DedupStep {
+    /// The message, received from a peer.
+    pub message: FloodsubMessage,
+    /// Whether the message is expected to be dropped as a duplicate of a previous one.
+    pub duplicate: bool,
+}
+
+/// A message of a [`DedupVector`] was not handled as expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupMismatch {
+    /// Name of the vector.
+    pub vector: &'static str,
+    /// Index of the message in the vector.
+    pub step: usize,
+    /// Whether the message was expected to be dropped as a duplicate.
+    pub expected_duplicate: bool,
+}
+
+impl fmt::Display for DedupMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Message {} of vector '{}' was {}expected to be a duplicate",
+            self.step,
+            self.vector,
+            if self.expected_duplicate { "" } else { "not " }
+        )
+    }
+}
+
+impl std::error::Error for DedupMismatch {}
+
+/// Topic of the messages of the [`dedup_vectors`].
+pub const DEDUP_VECTORS_TOPIC: &str = "floodsub-dedup-conformance";
+
+/// Returns the reference vectors of the deduplication semantics of floodsub.
+///
+/// Messages are identified by their source, data, sequence number and topics, but not their
+/// provenance. Note that implementations identifying messages by their source and sequence
+/// number only, as other libp2p implementations do, drop messages that reuse the sequence
+/// number of a previous message with different data.
+pub fn dedup_vectors() -> Vec<DedupVector> {
+    let message = |source: u8, data: &[u8], sequence_number: &[u8]| FloodsubMessage {
+        source: reference_peer(source),
+        data: data.to_vec(),
+        sequence_number: sequence_number.to_vec(),
+        topics: vec![Topic::new(DEDUP_VECTORS_TOPIC)],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    let step = |message: FloodsubMessage, duplicate: bool| DedupStep { message, duplicate };
+
+    vec![
+        DedupVector {
+            name: "identical messages",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(message(1, b"a", &[0, 1]), true),
+                step(message(1, b"a", &[0, 1]), true),
+            ],
+        },
+        DedupVector {
+            name: "different sequence numbers",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(message(1, b"a", &[0, 2]), false),
+                step(message(1, b"a", &[0, 0, 1]), false),
+            ],
+        },
+        DedupVector {
+            name: "different sources",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(message(2, b"a", &[0, 1]), false),
+            ],
+        },
+        DedupVector {
+            name: "same sequence number, different data",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(message(1, b"b", &[0, 1]), false),
+            ],
+        },
+        DedupVector {
+            name: "different provenance",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(
+                    FloodsubMessage {
+                        provenance: vec![reference_peer(3)],
+                        ..message(1, b"a", &[0, 1])
+                    },
+                    true,
+                ),
+            ],
+        },
+        DedupVector {
+            name: "different ttl",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(
+                    FloodsubMessage {
+                        ttl: 1,
+                        ..message(1, b"a", &[0, 1])
+                    },
+                    true,
+                ),
+            ],
+        },
+        DedupVector {
+            name: "interleaved duplicates",
+            steps: vec![
+                step(message(1, b"a", &[0, 1]), false),
+                step(message(2, b"b", &[0, 1]), false),
+                step(message(1, b"a", &[0, 1]), true),
+                step(message(2, b"b", &[0, 1]), true),
+            ],
+        },
+    ]
+}
+
+/// Feeds the messages of a vector to a new behaviour built with `config`, subscribed to their
+/// topics, and checks which of them it delivers.
+///
+/// The configuration must deliver messages as [`FloodsubEvent::Message`]s.
+pub fn check_dedup(config: FloodsubConfig, vector: &DedupVector) -> Result<(), DedupMismatch> {
+    let mut floodsub = Floodsub::from_config(config);
+    for step in &vector.steps {
+        for topic in &step.message.topics {
+            floodsub.subscribe(topic.clone());
+        }
+    }
+    drain_actions(&mut floodsub);
+
+    let trace = vector
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| CapturedRpc {
+            at: Duration::from_millis(i as u64),
+            source: reference_peer(0),
+            rpc: FloodsubRpc {
+                messages: vec![step.message.clone()],
+                subscriptions: Vec::new(),
+                sync: None,
+            },
+        });
+    for (i, (actions, step)) in replay(&mut floodsub, trace)
+        .into_iter()
+        .zip(&vector.steps)
+        .enumerate()
+    {
+        let delivered = actions.iter().any(|action| {
+            matches!(
+                action,
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+            )
+        });
+        if delivered == step.duplicate {
+            return Err(DedupMismatch {
+                vector: vector.name,
+                step: i,
+                expected_duplicate: step.duplicate,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns a fixed peer id, for the vectors to be identical across runs and implementations.
+fn reference_peer(n: u8) -> PeerId {
+    // Identity multihash of a single byte.
+    PeerId::from_bytes(&[0x00, 0x01, n]).expect("Valid identity multihash")
+}
+
+/// Polls a behaviour until it has nothing left to do and returns the actions it yielded, in
+/// order.
+pub fn drain_actions(
+    floodsub: &mut Floodsub,
+) -> Vec<NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>> {
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    let mut actions = Vec::new();
+    while let Poll::Ready(action) = poll_action(floodsub, &mut cx) {
+        actions.push(action);
+    }
+    actions
+}
+
+/// Polls a behaviour once with the given context, e.g. to observe when it wakes its task up.
+pub fn poll_action(
+    floodsub: &mut Floodsub,
+    cx: &mut Context<'_>,
+) -> Poll<NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>> {
+    let local_peer_id = *floodsub.local_peer_id();
+    floodsub.poll(cx, &mut NoPollParameters { local_peer_id })
+}
+
+/// Sets the counter of the sequence numbers of a behaviour, e.g. to bring it close to wrapping
+/// around. The next message it publishes with [`SequenceNumbers::Monotonic`] has the sequence
+/// number `counter + 1`.
+///
+/// [`SequenceNumbers::Monotonic`]: crate::SequenceNumbers::Monotonic
+pub fn set_sequence_number_counter(floodsub: &mut Floodsub, counter: u64) {
+    floodsub.set_sequence_number_counter(counter);
+}
+
+/// Publishes the messages of a behaviour scheduled with
+/// [`Floodsub::publish_at`](crate::Floodsub::publish_at) that are due at `now`, e.g. to drive
+/// them with a virtual clock instead of waiting for them to be due.
+pub fn publish_due_messages(floodsub: &mut Floodsub, now: Instant) {
+    floodsub.publish_due_messages(now);
+}
+
+fn link(a: PeerId, b: PeerId) -> (PeerId, PeerId) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn connection_id() -> ConnectionId {
+    #[allow(deprecated)]
+    ConnectionId::DUMMY
+}
+
+/// [`PollParameters`] of a node without any protocol or address, which [`Floodsub`] doesn't use,
+/// reporting the local peer id of the polled behaviour.
+struct NoPollParameters {
+    local_peer_id: PeerId,
+}
+
+impl PollParameters for NoPollParameters {
+    type SupportedProtocolsIter = std::iter::Empty<Vec<u8>>;
+    type ListenedAddressesIter = std::iter::Empty<Multiaddr>;
+    type ExternalAddressesIter = std::iter::Empty<AddressRecord>;
+
+    fn supported_protocols(&self) -> Self::SupportedProtocolsIter {
+        std::iter::empty()
+    }
+
+    fn listened_addresses(&self) -> Self::ListenedAddressesIter {
+        std::iter::empty()
+    }
+
+    fn external_addresses(&self) -> Self::ExternalAddressesIter {
+        std::iter::empty()
+    }
+
+    fn local_peer_id(&self) -> &PeerId {
+        &self.local_peer_id
+    }
+}
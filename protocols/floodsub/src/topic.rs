@@ -18,27 +18,118 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::hash::{Hash, Hasher};
+
 /// Built topic.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Topic(String);
+///
+/// Topics are identified by their full name, which is sent as is on the wire. Floodsub doesn't
+/// hash topics, so two topics with different names can never be mistaken for one another.
+///
+/// This is what the other floodsub implementations do, so the topics are interoperable with them
+/// as long as the names match. To interoperate with a network identifying topics by a hash of
+/// their name, such as gossipsub with `Sha256Topic`, build the topic from the hash instead of
+/// the name, as that network encodes it on the wire.
+///
+/// A topic can carry a label for the application, e.g. a human-readable name, see
+/// [`Topic::with_label`]. The label is never sent on the wire, and topics are compared by id
+/// only, regardless of their labels.
+#[derive(Debug, Clone)]
+pub struct Topic {
+    id: String,
+    label: Option<String>,
+}
 
 impl Topic {
     /// Returns the id of the topic.
     #[inline]
     pub fn id(&self) -> &str {
-        &self.0
+        &self.id
+    }
+
+    /// Returns the label of the topic, if any.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
     }
 
     pub fn new<S>(name: S) -> Topic
     where
         S: Into<String>,
     {
-        Topic(name.into())
+        Topic {
+            id: name.into(),
+            label: None,
+        }
+    }
+
+    /// Sets the label of the topic. Subscribing to the topic with a label lets us look it up
+    /// with [`Floodsub::topic_label`](crate::Floodsub::topic_label), e.g. when a message of the
+    /// topic is received.
+    pub fn with_label(mut self, label: impl Into<String>) -> Topic {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+impl PartialEq for Topic {
+    fn eq(&self, other: &Topic) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Topic {}
+
+impl Hash for Topic {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
     }
 }
 
 impl From<Topic> for String {
     fn from(topic: Topic) -> String {
-        topic.0
+        topic.id
+    }
+}
+
+/// Matches a family of topics, see
+/// [`Floodsub::subscribe_matching`](crate::Floodsub::subscribe_matching).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TopicMatcher {
+    /// Matches the topics whose id starts with the prefix.
+    Prefix(String),
+    /// Matches the topics whose id matches the pattern, in which `*` stands for any sequence of
+    /// characters, e.g. `sensors/*` or `sensors/*/temperature`.
+    Glob(String),
+}
+
+impl TopicMatcher {
+    /// Returns true if the topic is part of the family.
+    pub fn matches(&self, topic: &Topic) -> bool {
+        match self {
+            TopicMatcher::Prefix(prefix) => topic.id().starts_with(prefix.as_str()),
+            TopicMatcher::Glob(pattern) => glob_matches(pattern, topic.id()),
+        }
+    }
+}
+
+/// Returns true if `id` matches `pattern`, in which `*` stands for any sequence of characters.
+fn glob_matches(pattern: &str, id: &str) -> bool {
+    let parts = pattern.split('*').collect::<Vec<_>>();
+    let (first, last) = match parts.as_slice() {
+        [exact] => return *exact == id,
+        [first, .., last] => (*first, *last),
+        [] => unreachable!("`split` yields at least one part"),
+    };
+    if id.len() < first.len() + last.len() || !id.starts_with(first) || !id.ends_with(last) {
+        return false;
+    }
+
+    // Matching the inner parts as early as possible leaves the most room for the next ones.
+    let mut rest = &id[first.len()..id.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
     }
+    true
 }
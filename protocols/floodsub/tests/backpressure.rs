@@ -0,0 +1,112 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, poll_action, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, FloodsubEvent, PublishError, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+const MAX_PENDING_EVENTS: usize = 16;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns a behaviour whose queue of events is full of messages to forward, and the topic of
+/// the messages.
+fn saturated_floodsub() -> (Floodsub, Topic) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_pending_events = Some(MAX_PENDING_EVENTS);
+    config.idle_check_interval = Some(IDLE_CHECK_INTERVAL);
+    let mut floodsub = Floodsub::from_config(config);
+    let topic = Topic::new("topic");
+
+    let peers = (0..10).map(|_| PeerId::random()).collect::<Vec<_>>();
+    for peer_id in &peers {
+        floodsub.add_node_to_partial_view(*peer_id);
+        inject(&mut floodsub, *peer_id, subscribe_rpc(topic.clone()));
+    }
+    drain_actions(&mut floodsub);
+
+    let source = PeerId::random();
+    for n in 0..100u64 {
+        let message = FloodsubMessage {
+            source,
+            data: b"data".to_vec(),
+            sequence_number: n.to_be_bytes().to_vec(),
+            topics: vec![topic.clone()],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        };
+        inject(
+            &mut floodsub,
+            peers[0],
+            FloodsubRpc {
+                messages: vec![message],
+                subscriptions: Vec::new(),
+                sync: None,
+            },
+        );
+        assert!(floodsub.health().pending_events <= MAX_PENDING_EVENTS);
+    }
+    (floodsub, topic)
+}
+
+#[test]
+fn pending_events_stay_within_bound() {
+    let (mut floodsub, topic) = saturated_floodsub();
+
+    assert!(matches!(
+        floodsub.publish_any(topic, b"data".to_vec()),
+        Err(PublishError::Backpressure)
+    ));
+}
+
+#[test]
+fn generated_events_stay_within_bound() {
+    let (mut floodsub, _) = saturated_floodsub();
+
+    // Subscription events make room by dropping queued messages.
+    for _ in 0..5 {
+        inject(
+            &mut floodsub,
+            PeerId::random(),
+            subscribe_rpc(Topic::new("other")),
+        );
+        assert!(floodsub.health().pending_events <= MAX_PENDING_EVENTS);
+    }
+
+    // So do the warnings about the task not polling us.
+    thread::sleep(IDLE_CHECK_INTERVAL * 2);
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    assert!(matches!(
+        poll_action(&mut floodsub, &mut cx),
+        Poll::Ready(NetworkBehaviourAction::NotifyHandler { .. })
+    ));
+    assert!(floodsub.health().pending_events < MAX_PENDING_EVENTS);
+    assert!(drain_actions(&mut floodsub).iter().any(|action| matches!(
+        action,
+        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Warning(_))
+    )));
+}
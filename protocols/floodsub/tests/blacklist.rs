@@ -0,0 +1,135 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Returns an RPC carrying a message from `source` on "topic".
+fn message_rpc(source: PeerId, seq: u8) -> FloodsubRpc {
+    FloodsubRpc {
+        messages: vec![FloodsubMessage {
+            source,
+            data: b"data".to_vec(),
+            sequence_number: vec![seq; 8],
+            topics: vec![Topic::new("topic")],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        }],
+        subscriptions: Vec::new(),
+        sync: None,
+    }
+}
+
+/// Returns a behaviour subscribed to "topic", with two peers subscribed to it.
+fn floodsub_with_peers() -> (Floodsub, [PeerId; 2]) {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    let peers = [PeerId::random(), PeerId::random()];
+    for peer_id in peers {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("topic")));
+    }
+    drain_actions(&mut floodsub);
+    (floodsub, peers)
+}
+
+/// Returns whether the actions deliver a message to us.
+fn delivers(actions: &[NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>]) -> bool {
+    actions.iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+        )
+    })
+}
+
+/// Returns whether the actions send a message to `peer`.
+fn sends_message_to(
+    actions: &[NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>],
+    peer: PeerId,
+) -> bool {
+    actions.iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if *peer_id == peer && !event.messages.is_empty()
+        )
+    })
+}
+
+#[test]
+fn messages_from_blacklisted_peers_are_ignored() {
+    let (mut floodsub, [bad, good]) = floodsub_with_peers();
+    floodsub.blacklist_peer(bad);
+
+    inject(&mut floodsub, bad, message_rpc(bad, 0));
+    let actions = drain_actions(&mut floodsub);
+    assert!(!delivers(&actions));
+    assert!(!sends_message_to(&actions, good));
+
+    assert!(floodsub.whitelist_peer(&bad));
+    inject(&mut floodsub, bad, message_rpc(bad, 1));
+    let actions = drain_actions(&mut floodsub);
+    assert!(delivers(&actions));
+    assert!(sends_message_to(&actions, good));
+}
+
+#[test]
+fn subscriptions_from_blacklisted_peers_are_ignored() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let bad = PeerId::random();
+    floodsub.add_node_to_partial_view(bad);
+    floodsub.blacklist_peer(bad);
+
+    inject(&mut floodsub, bad, subscribe_rpc(Topic::new("topic")));
+    assert_eq!(floodsub.subscriber_count(&Topic::new("topic")), 0);
+}
+
+#[test]
+fn blacklisted_peers_are_not_sent_messages() {
+    let (mut floodsub, [bad, good]) = floodsub_with_peers();
+    floodsub.blacklist_peer(bad);
+
+    floodsub
+        .publish(Topic::new("topic"), b"data".to_vec())
+        .expect("peers are subscribed");
+    let actions = drain_actions(&mut floodsub);
+    assert!(!sends_message_to(&actions, bad));
+    assert!(sends_message_to(&actions, good));
+
+    // Nor are the messages we forward.
+    let source = PeerId::random();
+    inject(&mut floodsub, source, message_rpc(source, 0));
+    let actions = drain_actions(&mut floodsub);
+    assert!(!sends_message_to(&actions, bad));
+    assert!(sends_message_to(&actions, good));
+}
+
+#[test]
+fn whitelisting_an_unknown_peer_returns_false() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    assert!(!floodsub.whitelist_peer(&PeerId::random()));
+}
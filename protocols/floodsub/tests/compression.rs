@@ -0,0 +1,81 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::executor::block_on;
+use futures::io::Cursor;
+use libp2p_core::upgrade::InboundUpgrade;
+use libp2p_floodsub::protocol::FloodsubProtocol;
+use libp2p_floodsub::{Compression, Topic};
+use libp2p_identity::PeerId;
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn push_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    push_varint(out, field << 3 | 2);
+    push_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Returns the protobuf encoding of a message, with the given compression value if any.
+fn encode_message(source: PeerId, data: &[u8], compression: Option<u64>) -> Vec<u8> {
+    let mut message = Vec::new();
+    push_bytes(&mut message, 1, &source.to_bytes());
+    push_bytes(&mut message, 2, data);
+    push_bytes(&mut message, 3, &[0; 8]);
+    push_bytes(&mut message, 4, b"topic");
+    if let Some(compression) = compression {
+        push_varint(&mut message, 9 << 3);
+        push_varint(&mut message, compression);
+    }
+    message
+}
+
+#[test]
+fn only_messages_with_unknown_compression_are_rejected() {
+    let source = PeerId::random();
+    let mut subscription = Vec::new();
+    push_varint(&mut subscription, 1 << 3);
+    push_varint(&mut subscription, 1);
+    push_bytes(&mut subscription, 2, b"topic");
+
+    let mut rpc = Vec::new();
+    push_bytes(&mut rpc, 1, &subscription);
+    push_bytes(&mut rpc, 2, &encode_message(source, b"unknown", Some(7)));
+    push_bytes(&mut rpc, 2, &encode_message(source, b"known", None));
+    let mut frame = Vec::new();
+    push_varint(&mut frame, rpc.len() as u64);
+    frame.extend_from_slice(&rpc);
+
+    let rpc = block_on(
+        FloodsubProtocol::new().upgrade_inbound(Cursor::new(frame), b"/floodsub/1.0.0".as_ref()),
+    )
+    .expect("the RPC to be decoded");
+    assert_eq!(rpc.subscriptions.len(), 1);
+    assert_eq!(rpc.subscriptions[0].topic, Topic::new("topic"));
+    assert_eq!(rpc.messages.len(), 1);
+    assert_eq!(rpc.messages[0].data, b"known");
+    assert_eq!(rpc.messages[0].compression, Compression::None);
+}
@@ -0,0 +1,39 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::{Floodsub, FloodsubConfig};
+use libp2p_identity::PeerId;
+
+#[test]
+fn new_uses_the_default_config() {
+    let local_peer_id = PeerId::random();
+    let floodsub = Floodsub::new(local_peer_id);
+    assert_eq!(floodsub.local_peer_id(), &local_peer_id);
+
+    let mut config = floodsub.config().clone();
+    let expected = FloodsubConfig::new(local_peer_id);
+    // The name of the default spill file is random.
+    assert_eq!(
+        config.events_spill_path.parent(),
+        expected.events_spill_path.parent()
+    );
+    config.events_spill_path = expected.events_spill_path.clone();
+    assert_eq!(format!("{config:?}"), format!("{expected:?}"));
+}
@@ -0,0 +1,66 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{close_connection, connect_peer, open_connection, Network};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+
+#[test]
+fn connected_peers_are_listed_before_sending_subscriptions() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    assert_eq!(floodsub.num_connected_peers(), 0);
+    assert_eq!(floodsub.connected_peers().count(), 0);
+
+    let peers = [PeerId::random(), PeerId::random()];
+    for peer_id in peers {
+        connect_peer(&mut floodsub, peer_id);
+    }
+    // A second connection to a peer doesn't count.
+    open_connection(&mut floodsub, peers[0], 1);
+
+    assert_eq!(floodsub.num_connected_peers(), 2);
+    let mut connected = floodsub.connected_peers().copied().collect::<Vec<_>>();
+    connected.sort();
+    let mut expected = peers.to_vec();
+    expected.sort();
+    assert_eq!(connected, expected);
+
+    close_connection(&mut floodsub, peers[0], 1);
+    assert_eq!(floodsub.num_connected_peers(), 2);
+    close_connection(&mut floodsub, peers[0], 0);
+    assert_eq!(floodsub.connected_peers().collect::<Vec<_>>(), [&peers[1]]);
+}
+
+#[test]
+fn connected_peers_include_the_ones_not_subscribed_to_anything() {
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::new(PeerId::random()));
+    let subscriber = network.add_node(Floodsub::new(PeerId::random()));
+    let idle = network.add_node(Floodsub::new(PeerId::random()));
+    network.node_mut(&subscriber).subscribe(Topic::new("topic"));
+    network.connect(local, subscriber);
+    network.connect(local, idle);
+    network.run_until_idle();
+
+    let node = network.node(&local);
+    assert_eq!(node.num_connected_peers(), 2);
+    assert_eq!(node.peers_subscribed_to(&Topic::new("topic")).count(), 1);
+    assert!(node.connected_peers().any(|peer_id| *peer_id == idle));
+}
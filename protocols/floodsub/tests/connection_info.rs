@@ -0,0 +1,101 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
+use libp2p_floodsub::Floodsub;
+use libp2p_identity::PeerId;
+use libp2p_swarm::behaviour::{ConnectionClosed, ConnectionEstablished, FromSwarm};
+use libp2p_swarm::{ConnectionId, NetworkBehaviour};
+
+fn dialer(address: &str) -> ConnectedPoint {
+    ConnectedPoint::Dialer {
+        address: address.parse().expect("valid address"),
+        role_override: Endpoint::Dialer,
+    }
+}
+
+fn listener(address: &str) -> ConnectedPoint {
+    ConnectedPoint::Listener {
+        local_addr: Multiaddr::empty(),
+        send_back_addr: address.parse().expect("valid address"),
+    }
+}
+
+#[allow(deprecated)]
+const CONNECTION_ID: ConnectionId = ConnectionId::DUMMY;
+
+fn establish(floodsub: &mut Floodsub, peer_id: PeerId, endpoint: &ConnectedPoint, other: usize) {
+    floodsub.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+        peer_id,
+        connection_id: CONNECTION_ID,
+        endpoint,
+        failed_addresses: &[],
+        other_established: other,
+    }));
+}
+
+fn close(floodsub: &mut Floodsub, peer_id: PeerId, endpoint: &ConnectedPoint, remaining: usize) {
+    floodsub.on_swarm_event(FromSwarm::ConnectionClosed(ConnectionClosed {
+        peer_id,
+        connection_id: CONNECTION_ID,
+        endpoint,
+        handler: Default::default(),
+        remaining_established: remaining,
+    }));
+}
+
+#[test]
+fn connection_info_follows_the_oldest_connection() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let peer_id = PeerId::random();
+    assert_eq!(floodsub.connection_info(&peer_id), None);
+
+    let outbound = dialer("/ip4/127.0.0.1/tcp/1");
+    let inbound = listener("/ip4/127.0.0.1/tcp/2");
+    establish(&mut floodsub, peer_id, &outbound, 0);
+    establish(&mut floodsub, peer_id, &inbound, 1);
+    assert_eq!(floodsub.connection_info(&peer_id), Some(&outbound));
+
+    close(&mut floodsub, peer_id, &outbound, 1);
+    assert_eq!(floodsub.connection_info(&peer_id), Some(&inbound));
+
+    close(&mut floodsub, peer_id, &inbound, 0);
+    assert_eq!(floodsub.connection_info(&peer_id), None);
+}
+
+#[test]
+fn connection_info_is_kept_per_peer() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let peers = [PeerId::random(), PeerId::random()];
+    let endpoints = [
+        dialer("/ip4/127.0.0.1/tcp/1"),
+        listener("/ip4/127.0.0.1/tcp/2"),
+    ];
+    for (peer_id, endpoint) in peers.iter().zip(&endpoints) {
+        establish(&mut floodsub, *peer_id, endpoint, 0);
+    }
+
+    for (peer_id, endpoint) in peers.iter().zip(&endpoints) {
+        assert_eq!(floodsub.connection_info(peer_id), Some(endpoint));
+    }
+    close(&mut floodsub, peers[0], &endpoints[0], 0);
+    assert_eq!(floodsub.connection_info(&peers[0]), None);
+    assert_eq!(floodsub.connection_info(&peers[1]), Some(&endpoints[1]));
+}
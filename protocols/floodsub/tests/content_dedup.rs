@@ -0,0 +1,114 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, DedupStrategy, Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::thread;
+use std::time::Duration;
+
+fn floodsub_with(strategy: DedupStrategy, duplicate_cache_time: Option<Duration>) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.dedup_strategy = strategy;
+    config.duplicate_cache_time = duplicate_cache_time;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("a"));
+    floodsub.subscribe(Topic::new("b"));
+    floodsub
+}
+
+/// Feeds a message from a new source to the behaviour and returns whether it was delivered.
+fn deliver(floodsub: &mut Floodsub, seq: u8, data: &[u8], topic: &str) -> bool {
+    let source = PeerId::random();
+    let message = FloodsubMessage {
+        source,
+        data: data.to_vec(),
+        sequence_number: vec![seq; 8],
+        topics: vec![Topic::new(topic)],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    inject(
+        floodsub,
+        source,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(floodsub).into_iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+        )
+    })
+}
+
+#[test]
+fn same_content_is_delivered_again_by_default() {
+    let mut floodsub = floodsub_with(DedupStrategy::SourceSeq, None);
+
+    assert!(deliver(&mut floodsub, 0, b"data", "a"));
+    assert!(deliver(&mut floodsub, 1, b"data", "a"));
+}
+
+#[test]
+fn same_content_is_delivered_once() {
+    let mut floodsub = floodsub_with(DedupStrategy::ContentHash, None);
+
+    assert!(deliver(&mut floodsub, 0, b"data", "a"));
+    assert!(!deliver(&mut floodsub, 1, b"data", "a"));
+    assert!(deliver(&mut floodsub, 1, b"other", "a"));
+    assert!(deliver(&mut floodsub, 1, b"data", "b"));
+}
+
+#[test]
+fn published_content_is_not_delivered_back() {
+    let mut floodsub = floodsub_with(DedupStrategy::ContentHash, None);
+    let peer_id = PeerId::random();
+    floodsub.add_node_to_partial_view(peer_id);
+    inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("a")));
+    floodsub
+        .publish(Topic::new("a"), b"data".to_vec())
+        .expect("peer is subscribed");
+    drain_actions(&mut floodsub);
+
+    assert!(!deliver(&mut floodsub, 0, b"data", "a"));
+}
+
+#[test]
+fn same_content_is_delivered_again_once_expired() {
+    let cache_time = Duration::from_millis(20);
+    let mut floodsub = floodsub_with(DedupStrategy::ContentHash, Some(cache_time));
+
+    assert!(deliver(&mut floodsub, 0, b"data", "a"));
+    assert!(!deliver(&mut floodsub, 1, b"data", "a"));
+
+    // Expired entries are removed when the behaviour is polled.
+    thread::sleep(cache_time * 2);
+    drain_actions(&mut floodsub);
+    assert!(deliver(&mut floodsub, 2, b"data", "a"));
+}
@@ -0,0 +1,56 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{check_dedup, dedup_vectors};
+use libp2p_floodsub::FloodsubConfig;
+use libp2p_identity::PeerId;
+use std::time::Duration;
+
+#[test]
+fn dedup_matches_reference_vectors() {
+    for vector in dedup_vectors() {
+        let config = FloodsubConfig::new(PeerId::random());
+        if let Err(mismatch) = check_dedup(config, &vector) {
+            panic!("{mismatch}");
+        }
+    }
+}
+
+#[test]
+fn dedup_matches_reference_vectors_with_exact_window() {
+    for vector in dedup_vectors() {
+        let mut config = FloodsubConfig::new(PeerId::random());
+        config.exact_dedup_window = 16;
+        if let Err(mismatch) = check_dedup(config, &vector) {
+            panic!("{mismatch}");
+        }
+    }
+}
+
+#[test]
+fn dedup_matches_reference_vectors_with_duplicate_cache() {
+    for vector in dedup_vectors() {
+        let mut config = FloodsubConfig::new(PeerId::random());
+        config.duplicate_cache_time = Some(Duration::from_secs(60));
+        if let Err(mismatch) = check_dedup(config, &vector) {
+            panic!("{mismatch}");
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{
+    Compression, DeliveryOverflowPolicy, Floodsub, FloodsubConfig, FloodsubEvent, Topic,
+};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::thread;
+use std::time::Duration;
+
+const WINDOW: Duration = Duration::from_millis(30);
+
+/// Messages delivered and sent to the subscribed peer when polling.
+#[derive(Debug, Default, PartialEq)]
+struct Polled {
+    delivered: Vec<u8>,
+    forwarded: Vec<u8>,
+}
+
+fn poll(floodsub: &mut Floodsub) -> Polled {
+    let mut polled = Polled::default();
+    for action in drain_actions(floodsub) {
+        match action {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(message)) => {
+                polled.delivered.push(message.data[0])
+            }
+            NetworkBehaviourAction::NotifyHandler { event, .. } => polled
+                .forwarded
+                .extend(event.messages.iter().map(|message| message.data[0])),
+            _ => {}
+        }
+    }
+    polled
+}
+
+/// Returns a behaviour delivering at most two messages per window, with a peer subscribed to the
+/// topic, after feeding it five messages at once.
+fn flooded_floodsub(policy: DeliveryOverflowPolicy) -> (Floodsub, Polled) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_deliveries_per_topic = Some(2);
+    config.delivery_rate_window = WINDOW;
+    config.delivery_overflow_policy = policy;
+    let mut floodsub = Floodsub::from_config(config);
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    let subscriber = PeerId::random();
+    floodsub.add_node_to_partial_view(subscriber);
+    inject(&mut floodsub, subscriber, subscribe_rpc(topic.clone()));
+    drain_actions(&mut floodsub);
+
+    let source = PeerId::random();
+    let messages = (1..=5)
+        .map(|n| FloodsubMessage {
+            source,
+            data: vec![n],
+            sequence_number: vec![n; 8],
+            topics: vec![topic.clone()],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        })
+        .collect();
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages,
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let polled = poll(&mut floodsub);
+    (floodsub, polled)
+}
+
+#[test]
+fn messages_over_the_limit_are_dropped() {
+    let (mut floodsub, polled) = flooded_floodsub(DeliveryOverflowPolicy::Drop);
+
+    // Forwarding is not limited.
+    assert_eq!(
+        polled,
+        Polled {
+            delivered: vec![1, 2],
+            forwarded: vec![1, 2, 3, 4, 5],
+        }
+    );
+    thread::sleep(WINDOW);
+    assert_eq!(poll(&mut floodsub), Polled::default());
+}
+
+#[test]
+fn messages_over_the_limit_are_buffered() {
+    let (mut floodsub, polled) = flooded_floodsub(DeliveryOverflowPolicy::Buffer { capacity: 2 });
+
+    assert_eq!(polled.delivered, vec![1, 2]);
+    thread::sleep(WINDOW);
+    // The fifth message didn't fit in the buffer.
+    assert_eq!(poll(&mut floodsub).delivered, vec![3, 4]);
+    thread::sleep(WINDOW);
+    assert_eq!(poll(&mut floodsub).delivered, Vec::<u8>::new());
+}
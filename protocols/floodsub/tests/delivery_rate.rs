@@ -0,0 +1,99 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+use std::thread;
+use std::time::Duration;
+
+const WINDOW: Duration = Duration::from_millis(500);
+
+/// Feeds `count` messages of `topic` to a behaviour.
+fn receive(floodsub: &mut Floodsub, topic: &str, count: u8) {
+    let source = PeerId::random();
+    let messages = (0..count)
+        .map(|n| FloodsubMessage {
+            source,
+            data: vec![n],
+            sequence_number: vec![n; 8],
+            topics: vec![Topic::new(topic)],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        })
+        .collect();
+    inject(
+        floodsub,
+        source,
+        FloodsubRpc {
+            messages,
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(floodsub);
+}
+
+fn subscribed_floodsub(track_delivery_rate: bool) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.track_delivery_rate = track_delivery_rate;
+    config.delivery_rate_tracking_window = WINDOW;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("a"));
+    floodsub.subscribe(Topic::new("b"));
+    floodsub
+}
+
+#[test]
+fn delivery_rate_is_tracked_per_topic_and_in_total() {
+    let mut floodsub = subscribed_floodsub(true);
+    receive(&mut floodsub, "a", 5);
+    receive(&mut floodsub, "b", 3);
+    // Not delivered, since we aren't subscribed.
+    receive(&mut floodsub, "c", 4);
+
+    let rate = &floodsub.metrics().delivery_rate;
+    assert_eq!(rate.window(), WINDOW);
+    assert_eq!(rate.total(), 8.0 / WINDOW.as_secs_f64());
+    assert_eq!(rate.topic(&Topic::new("a")), 5.0 / WINDOW.as_secs_f64());
+    assert_eq!(rate.topic(&Topic::new("b")), 3.0 / WINDOW.as_secs_f64());
+    assert_eq!(rate.topic(&Topic::new("c")), 0.0);
+
+    floodsub.unsubscribe(Topic::new("b"));
+    assert_eq!(
+        floodsub.metrics().delivery_rate.topic(&Topic::new("b")),
+        0.0
+    );
+
+    thread::sleep(WINDOW);
+    assert_eq!(floodsub.metrics().delivery_rate.total(), 0.0);
+}
+
+#[test]
+fn delivery_rate_is_not_tracked_by_default() {
+    let mut floodsub = subscribed_floodsub(false);
+    receive(&mut floodsub, "a", 5);
+
+    assert_eq!(floodsub.metrics().delivery_rate.total(), 0.0);
+}
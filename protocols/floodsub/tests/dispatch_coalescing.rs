@@ -0,0 +1,143 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, Topic, TopicMatcher};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn one_rpc_per_peer_for_all_forwarded_messages() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+
+    let subscribers = [PeerId::random(), PeerId::random()];
+    for peer_id in subscribers {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(topic.clone()));
+    }
+    let source = PeerId::random();
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(&mut floodsub);
+
+    let messages = (0..3u64)
+        .map(|n| FloodsubMessage {
+            source,
+            data: b"data".to_vec(),
+            sequence_number: n.to_be_bytes().to_vec(),
+            topics: vec![topic.clone()],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        })
+        .collect();
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages,
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+
+    let rpcs = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } => Some((peer_id, event)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(rpcs.len(), 2);
+    for peer_id in subscribers {
+        let rpc = rpcs
+            .iter()
+            .find(|(p, _)| *p == peer_id)
+            .map(|(_, rpc)| rpc)
+            .expect("an RPC for every subscriber");
+        assert_eq!(rpc.messages.len(), 3);
+    }
+}
+
+#[test]
+fn subscriptions_to_matching_topics_are_sent_with_the_messages() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe_matching(TopicMatcher::Prefix("news/".into()));
+    let topic = Topic::new("topic");
+    let subscribers = [PeerId::random(), PeerId::random()];
+    for peer_id in subscribers {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(topic.clone()));
+    }
+    let source = PeerId::random();
+    floodsub.add_node_to_partial_view(source);
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(&mut floodsub);
+
+    // The source tells us about a topic we subscribe to, and sends a message on it.
+    let news = Topic::new("news/today");
+    let mut rpc = subscribe_rpc(news.clone());
+    rpc.messages.push(FloodsubMessage {
+        source,
+        data: b"data".to_vec(),
+        sequence_number: vec![0; 8],
+        topics: vec![topic, news],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    });
+    inject(&mut floodsub, source, rpc);
+
+    let rpcs = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } => Some((peer_id, event)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(rpcs.len(), 3);
+    for (peer_id, rpc) in &rpcs {
+        assert_eq!(rpc.subscriptions.len(), 1);
+        let expected_messages = usize::from(*peer_id != source);
+        assert_eq!(rpc.messages.len(), expected_messages);
+    }
+}
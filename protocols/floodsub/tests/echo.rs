@@ -0,0 +1,128 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, replay, subscribe_rpc, CapturedRpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, MessageAuthenticity, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::time::Duration;
+
+#[test]
+fn echoes_of_our_messages_are_ignored() {
+    let floodsub = Floodsub::new(PeerId::random());
+    let source = *floodsub.local_peer_id();
+    assert_echoes_are_ignored(floodsub, source);
+}
+
+#[test]
+fn echoes_of_messages_published_for_an_author_are_ignored() {
+    let author = PeerId::random();
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.message_authenticity = MessageAuthenticity::Author(author);
+    assert_echoes_are_ignored(Floodsub::from_config(config), author);
+}
+
+fn assert_echoes_are_ignored(mut floodsub: Floodsub, source: PeerId) {
+    let topic = Topic::new("topic");
+    let peers = [PeerId::random(), PeerId::random()];
+    let subscriptions = peers.iter().enumerate().map(|(i, peer_id)| CapturedRpc {
+        at: Duration::from_millis(i as u64),
+        source: *peer_id,
+        rpc: subscribe_rpc(topic.clone()),
+    });
+    for peer_id in &peers {
+        floodsub.add_node_to_partial_view(*peer_id);
+    }
+    replay(&mut floodsub, subscriptions);
+
+    // We aren't subscribed to the topic, so the message isn't recorded as received.
+    floodsub
+        .publish_any(topic, b"data".to_vec())
+        .expect("peers are subscribed");
+    let message = drain_actions(&mut floodsub)
+        .into_iter()
+        .find_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => event.messages.first().cloned(),
+            _ => None,
+        })
+        .expect("message to be sent");
+    assert_eq!(message.source, source);
+
+    let actions = replay(
+        &mut floodsub,
+        [CapturedRpc {
+            at: Duration::ZERO,
+            source: peers[0],
+            rpc: FloodsubRpc {
+                messages: vec![message],
+                subscriptions: Vec::new(),
+                sync: None,
+            },
+        }],
+    );
+    assert!(actions.iter().all(Vec::is_empty));
+}
+
+#[test]
+fn messages_claiming_our_source_are_ignored() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    let peers = [PeerId::random(), PeerId::random()];
+    for peer_id in &peers {
+        floodsub.add_node_to_partial_view(*peer_id);
+    }
+    replay(
+        &mut floodsub,
+        peers.iter().map(|peer_id| CapturedRpc {
+            at: Duration::ZERO,
+            source: *peer_id,
+            rpc: subscribe_rpc(topic.clone()),
+        }),
+    );
+    drain_actions(&mut floodsub);
+
+    // We never published it, so it is neither delivered nor forwarded.
+    let message = FloodsubMessage {
+        source: *floodsub.local_peer_id(),
+        data: b"data".to_vec(),
+        sequence_number: vec![0; 8],
+        topics: vec![topic],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    let actions = replay(
+        &mut floodsub,
+        [CapturedRpc {
+            at: Duration::ZERO,
+            source: peers[0],
+            rpc: FloodsubRpc {
+                messages: vec![message],
+                subscriptions: Vec::new(),
+                sync: None,
+            },
+        }],
+    );
+    assert!(actions.iter().all(Vec::is_empty));
+}
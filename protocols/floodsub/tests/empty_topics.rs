@@ -0,0 +1,86 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubEvent, PublishError, Topic, TraceStep};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn messages_without_topic_are_not_published() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+
+    assert_eq!(
+        floodsub.publish_many(Vec::<Topic>::new(), b"data".to_vec()),
+        Err(PublishError::NoTopics)
+    );
+    assert_eq!(
+        floodsub.publish_many_any(Vec::<Topic>::new(), b"data".to_vec()),
+        Err(PublishError::NoTopics)
+    );
+    assert_eq!(floodsub.metrics().messages_published, 0);
+}
+
+#[test]
+fn received_messages_without_topic_are_dropped() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    let remote = PeerId::random();
+    let message = FloodsubMessage {
+        source: remote,
+        data: b"data".to_vec(),
+        sequence_number: 1u64.to_be_bytes().to_vec(),
+        topics: Vec::new(),
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    floodsub.trace_message(message.id());
+
+    inject(
+        &mut floodsub,
+        remote,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let steps = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Trace { step, .. }) => Some(step),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        vec![
+            TraceStep::Received {
+                propagation_source: remote
+            },
+            TraceStep::NoTopics,
+        ]
+    );
+}
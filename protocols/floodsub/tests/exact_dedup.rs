@@ -0,0 +1,114 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+const MESSAGES: u32 = 500;
+
+fn message(source: PeerId, n: u32) -> FloodsubMessage {
+    FloodsubMessage {
+        source,
+        data: b"data".to_vec(),
+        sequence_number: n.to_be_bytes().to_vec(),
+        topics: vec![Topic::new("topic")],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    }
+}
+
+/// Returns a behaviour whose duplicates filter is far too small for [`MESSAGES`] messages, and
+/// thus reports many false positives.
+fn saturated_floodsub(exact_dedup_window: usize) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.cuckoo_filter_capacity = 8;
+    config.exact_dedup_window = exact_dedup_window;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("topic"));
+    floodsub
+}
+
+/// Feeds the messages to a behaviour one by one and returns how many of them were delivered.
+fn delivered(floodsub: &mut Floodsub, source: PeerId, messages: Vec<FloodsubMessage>) -> usize {
+    messages
+        .into_iter()
+        .map(|message| {
+            inject(
+                floodsub,
+                source,
+                FloodsubRpc {
+                    messages: vec![message],
+                    subscriptions: Vec::new(),
+                    sync: None,
+                },
+            );
+            drain_actions(floodsub)
+                .into_iter()
+                .filter(|action| {
+                    matches!(
+                        action,
+                        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+                    )
+                })
+                .count()
+        })
+        .sum()
+}
+
+#[test]
+fn exact_window_prevents_false_positives() {
+    let mut floodsub = saturated_floodsub(MESSAGES as usize);
+    let source = PeerId::random();
+
+    let messages = (0..MESSAGES).map(|n| message(source, n)).collect();
+    assert_eq!(
+        delivered(&mut floodsub, source, messages),
+        MESSAGES as usize
+    );
+    assert!(floodsub.metrics().dedup_false_positives > 0);
+}
+
+#[test]
+fn exact_window_still_drops_duplicates() {
+    let mut floodsub = saturated_floodsub(MESSAGES as usize);
+    let source = PeerId::random();
+
+    // Few enough messages for the filter to hold them all.
+    let messages = (0..4).map(|n| message(source, n)).collect();
+    assert_eq!(delivered(&mut floodsub, source, messages), 4);
+    let duplicates = (0..4).map(|n| message(source, n)).collect();
+    assert_eq!(delivered(&mut floodsub, source, duplicates), 0);
+}
+
+#[test]
+fn false_positives_are_dropped_without_exact_window() {
+    let mut floodsub = saturated_floodsub(0);
+    let source = PeerId::random();
+
+    let messages = (0..MESSAGES).map(|n| message(source, n)).collect();
+    assert!(delivered(&mut floodsub, source, messages) < MESSAGES as usize);
+    assert_eq!(floodsub.metrics().dedup_false_positives, 0);
+}
@@ -0,0 +1,67 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Publishes a message to the given subscribed peers and returns the peers it was sent to, in
+/// order.
+fn publish_order(local_peer_id: PeerId, peers: &[PeerId]) -> Vec<PeerId> {
+    let mut floodsub = Floodsub::new(local_peer_id);
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    for peer_id in peers {
+        floodsub.add_node_to_partial_view(*peer_id);
+        inject(&mut floodsub, *peer_id, subscribe_rpc(topic.clone()));
+    }
+    drain_actions(&mut floodsub);
+
+    floodsub
+        .publish(topic, b"data".to_vec())
+        .expect("peers are subscribed");
+    drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if !event.messages.is_empty() =>
+            {
+                Some(peer_id)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn fanout_order_is_deterministic() {
+    let local_peer_id = PeerId::random();
+    let peers = (0..16).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+    let order = publish_order(local_peer_id, &peers);
+    let mut sorted = peers.clone();
+    sorted.sort_by_key(PeerId::to_bytes);
+    assert_eq!(order, sorted);
+
+    for _ in 0..8 {
+        assert_eq!(publish_order(local_peer_id, &peers), order);
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{connect_peer, drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+
+#[test]
+fn publish_many_returns_fanout_size() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+
+    for subscribed in [true, true, false] {
+        let peer_id = PeerId::random();
+        floodsub.add_node_to_partial_view(peer_id);
+        if subscribed {
+            inject(&mut floodsub, peer_id, subscribe_rpc(topic.clone()));
+        } else {
+            connect_peer(&mut floodsub, peer_id);
+        }
+    }
+    drain_actions(&mut floodsub);
+
+    assert_eq!(
+        floodsub.publish_many(vec![topic.clone()], b"data".to_vec()),
+        Ok(2)
+    );
+    // Peers subscribed to several of the topics are counted once.
+    let other = Topic::new("other");
+    floodsub.subscribe(other.clone());
+    assert_eq!(
+        floodsub.publish_many(vec![topic, other], b"data".to_vec()),
+        Ok(2)
+    );
+}
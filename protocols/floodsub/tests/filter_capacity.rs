@@ -0,0 +1,111 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+const MESSAGES: u32 = 200;
+
+/// A fixed source, so that the false positives of the filter are the same on every run.
+fn source() -> PeerId {
+    PeerId::from_bytes(&[0x00, 0x01, 0x00]).expect("Valid identity multihash")
+}
+
+fn floodsub_with_capacity(capacity: usize) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.cuckoo_filter_capacity = capacity;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("topic"));
+    floodsub
+}
+
+/// Feeds [`MESSAGES`] messages from `source` to the behaviour and returns how many of them were
+/// delivered.
+fn feed_messages(floodsub: &mut Floodsub, source: PeerId) -> usize {
+    (0..MESSAGES)
+        .map(|n| {
+            let message = FloodsubMessage {
+                source,
+                data: b"data".to_vec(),
+                sequence_number: n.to_be_bytes().to_vec(),
+                topics: vec![Topic::new("topic")],
+                signature: None,
+                key: None,
+                provenance: Vec::new(),
+                ttl: u8::MAX,
+                compression: Compression::None,
+            };
+            inject(
+                floodsub,
+                source,
+                FloodsubRpc {
+                    messages: vec![message],
+                    subscriptions: Vec::new(),
+                    sync: None,
+                },
+            );
+            drain_actions(floodsub)
+                .into_iter()
+                .filter(|action| {
+                    matches!(
+                        action,
+                        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+                    )
+                })
+                .count()
+        })
+        .sum()
+}
+
+#[test]
+fn large_filter_tracks_every_message() {
+    let mut floodsub = floodsub_with_capacity((1 << 20) - 1);
+    let source = source();
+
+    assert_eq!(feed_messages(&mut floodsub, source), MESSAGES as usize);
+    assert_eq!(floodsub.received_cache_len(), MESSAGES as usize);
+    assert_eq!(feed_messages(&mut floodsub, source), 0);
+}
+
+#[test]
+fn small_filter_is_bounded_by_its_capacity() {
+    let mut floodsub = floodsub_with_capacity(16);
+    let source = source();
+
+    // Once full, new messages evict old ones, some of which are then delivered again.
+    feed_messages(&mut floodsub, source);
+    assert!(floodsub.received_cache_len() <= 16);
+    assert!(feed_messages(&mut floodsub, source) > 0);
+}
+
+#[test]
+fn clearing_keeps_the_configured_capacity() {
+    let mut floodsub = floodsub_with_capacity((1 << 20) - 1);
+    let source = source();
+    feed_messages(&mut floodsub, source);
+
+    floodsub.clear_received_cache();
+    assert_eq!(floodsub.received_cache_len(), 0);
+    assert_eq!(feed_messages(&mut floodsub, source), MESSAGES as usize);
+    assert_eq!(floodsub.received_cache_len(), MESSAGES as usize);
+}
@@ -0,0 +1,114 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{
+    Compression, FilterFullPolicy, Floodsub, FloodsubConfig, PublishError, Topic,
+};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+const MESSAGES: u32 = 100;
+
+/// Returns a behaviour subscribed to "topic", whose duplicates filter is far too small for
+/// [`MESSAGES`] messages, with a peer subscribed to it.
+fn floodsub_with_small_filter(policy: FilterFullPolicy) -> (Floodsub, PeerId) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.cuckoo_filter_capacity = 8;
+    config.filter_full_policy = policy;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("topic"));
+    let peer_id = PeerId::random();
+    floodsub.add_node_to_partial_view(peer_id);
+    inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("topic")));
+    drain_actions(&mut floodsub);
+    (floodsub, peer_id)
+}
+
+#[test]
+fn received_messages_evict_others_from_a_full_filter() {
+    let (mut floodsub, peer_id) = floodsub_with_small_filter(FilterFullPolicy::Fail);
+
+    for n in 0..MESSAGES {
+        let message = FloodsubMessage {
+            source: peer_id,
+            data: b"data".to_vec(),
+            sequence_number: n.to_be_bytes().to_vec(),
+            topics: vec![Topic::new("topic")],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        };
+        inject(
+            &mut floodsub,
+            peer_id,
+            FloodsubRpc {
+                messages: vec![message],
+                subscriptions: Vec::new(),
+                sync: None,
+            },
+        );
+    }
+
+    // The policy only applies to the messages we publish.
+    assert!(floodsub.metrics().filter_evictions > 0);
+}
+
+#[test]
+fn publishing_evicts_others_from_a_full_filter() {
+    let (mut floodsub, _) = floodsub_with_small_filter(FilterFullPolicy::Evict);
+
+    for _ in 0..MESSAGES {
+        floodsub
+            .publish(Topic::new("topic"), b"data".to_vec())
+            .expect("filter to evict other messages");
+    }
+    assert!(floodsub.metrics().filter_evictions > 0);
+}
+
+#[test]
+fn publishing_fails_once_the_filter_is_full() {
+    let (mut floodsub, peer_id) = floodsub_with_small_filter(FilterFullPolicy::Fail);
+
+    let mut failed = false;
+    for _ in 0..MESSAGES {
+        match floodsub.publish(Topic::new("topic"), b"data".to_vec()) {
+            Ok(_) => {
+                drain_actions(&mut floodsub);
+            }
+            Err(PublishError::FilterFull) => {
+                failed = true;
+                break;
+            }
+            Err(e) => panic!("unexpected error: {e:?}"),
+        }
+    }
+    assert!(failed);
+
+    // The message isn't sent, and no message is evicted.
+    assert!(!drain_actions(&mut floodsub).iter().any(|action| matches!(
+        action,
+        NetworkBehaviourAction::NotifyHandler { peer_id: p, .. } if *p == peer_id
+    )));
+    assert_eq!(floodsub.metrics().filter_evictions, 0);
+}
@@ -0,0 +1,100 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, FloodsubRpc, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::thread;
+use std::time::Duration;
+
+const INTERVAL: Duration = Duration::from_millis(30);
+
+/// Returns a behaviour subscribed to a topic along with two peers subscribed to it.
+fn floodsub_with_peers(rpc_flush_interval: Option<Duration>) -> (Floodsub, PeerId, PeerId) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.rpc_flush_interval = rpc_flush_interval;
+    let mut floodsub = Floodsub::from_config(config);
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    let peers = (PeerId::random(), PeerId::random());
+    for peer_id in [peers.0, peers.1] {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(topic.clone()));
+    }
+    drain_actions(&mut floodsub);
+    (floodsub, peers.0, peers.1)
+}
+
+fn publish_twice(floodsub: &mut Floodsub) {
+    for data in [b"1", b"2"] {
+        floodsub
+            .publish(Topic::new("topic"), data.to_vec())
+            .expect("peers are subscribed");
+    }
+}
+
+/// Returns the number of messages of each RPC sent to `peer` when polled.
+fn sent_to(
+    actions: &[NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>],
+    peer: PeerId,
+) -> Vec<usize> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if *peer_id == peer && !event.messages.is_empty() =>
+            {
+                Some(event.messages.len())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn peer_flush_interval_overrides_the_global_one() {
+    let (mut floodsub, batched, immediate) = floodsub_with_peers(Some(INTERVAL));
+    floodsub.set_peer_flush_interval(immediate, Duration::ZERO);
+
+    publish_twice(&mut floodsub);
+    let actions = drain_actions(&mut floodsub);
+    assert_eq!(sent_to(&actions, immediate), vec![1, 1]);
+    assert!(sent_to(&actions, batched).is_empty());
+
+    thread::sleep(INTERVAL);
+    let actions = drain_actions(&mut floodsub);
+    assert_eq!(sent_to(&actions, batched), vec![2]);
+    assert!(sent_to(&actions, immediate).is_empty());
+}
+
+#[test]
+fn peer_flush_interval_batches_without_a_global_one() {
+    let (mut floodsub, batched, immediate) = floodsub_with_peers(None);
+    floodsub.set_peer_flush_interval(batched, INTERVAL);
+
+    publish_twice(&mut floodsub);
+    let actions = drain_actions(&mut floodsub);
+    assert_eq!(sent_to(&actions, immediate), vec![1, 1]);
+    assert!(sent_to(&actions, batched).is_empty());
+
+    thread::sleep(INTERVAL);
+    assert_eq!(sent_to(&drain_actions(&mut floodsub), batched), vec![2]);
+}
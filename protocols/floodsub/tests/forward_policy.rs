@@ -0,0 +1,161 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, ForwardPolicy, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+const FANOUT: usize = 2;
+
+fn message(source: PeerId, sequence_number: u8) -> FloodsubMessage {
+    FloodsubMessage {
+        source,
+        data: b"data".to_vec(),
+        sequence_number: vec![sequence_number; 8],
+        topics: vec![Topic::new("topic")],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    }
+}
+
+/// Returns the sorted peers a fresh behaviour with the given policy forwards `message` to, with
+/// `peers` subscribed to its topic and the message received from the first of them.
+fn forward_targets(
+    policy: ForwardPolicy,
+    peers: &[PeerId],
+    message: FloodsubMessage,
+) -> Vec<PeerId> {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.forward_policy = policy;
+    let mut floodsub = Floodsub::from_config(config);
+    for peer_id in peers {
+        floodsub.add_node_to_partial_view(*peer_id);
+        inject(&mut floodsub, *peer_id, subscribe_rpc(Topic::new("topic")));
+    }
+    drain_actions(&mut floodsub);
+
+    inject(
+        &mut floodsub,
+        peers[0],
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let mut targets = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if !event.messages.is_empty() =>
+            {
+                Some(peer_id)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    targets.sort();
+    targets
+}
+
+fn consistent_hash() -> ForwardPolicy {
+    ForwardPolicy::ConsistentHash { fanout: FANOUT }
+}
+
+fn sampled(seed: u64) -> ForwardPolicy {
+    ForwardPolicy::Sampled {
+        fanout: FANOUT,
+        seed: Some(seed),
+    }
+}
+
+#[test]
+fn consistent_hashing_is_bounded_and_deterministic_across_nodes() {
+    let peers = (0..8).map(|_| PeerId::random()).collect::<Vec<_>>();
+    let source = PeerId::random();
+
+    for sequence_number in 0..4 {
+        let targets = forward_targets(consistent_hash(), &peers, message(source, sequence_number));
+        assert_eq!(targets.len(), FANOUT);
+        assert!(targets.iter().all(|peer_id| peers.contains(peer_id)));
+        // Another node with the same subscribed peers picks the same ones.
+        assert_eq!(
+            forward_targets(consistent_hash(), &peers, message(source, sequence_number)),
+            targets
+        );
+    }
+}
+
+#[test]
+fn consistent_hashing_spreads_messages_over_the_peers() {
+    let peers = (0..8).map(|_| PeerId::random()).collect::<Vec<_>>();
+    let source = PeerId::random();
+
+    let mut reached = (0..32)
+        .flat_map(|sequence_number| {
+            forward_targets(consistent_hash(), &peers, message(source, sequence_number))
+        })
+        .collect::<Vec<_>>();
+    reached.sort();
+    reached.dedup();
+    assert!(reached.len() > FANOUT);
+}
+
+#[test]
+fn sampling_is_bounded_and_reproducible_with_a_seed() {
+    let peers = (0..8).map(|_| PeerId::random()).collect::<Vec<_>>();
+    let source = PeerId::random();
+
+    for seed in 0..4 {
+        let targets = forward_targets(sampled(seed), &peers, message(source, 0));
+        assert_eq!(targets.len(), FANOUT);
+        assert!(targets.iter().all(|peer_id| peers[1..].contains(peer_id)));
+        assert_eq!(
+            forward_targets(sampled(seed), &peers, message(source, 0)),
+            targets
+        );
+    }
+}
+
+#[test]
+fn sampling_picks_different_peers_for_each_seed() {
+    let peers = (0..8).map(|_| PeerId::random()).collect::<Vec<_>>();
+    let source = PeerId::random();
+
+    let mut reached = (0..32)
+        .flat_map(|seed| forward_targets(sampled(seed), &peers, message(source, 0)))
+        .collect::<Vec<_>>();
+    reached.sort();
+    reached.dedup();
+    assert!(reached.len() > FANOUT);
+}
+
+#[test]
+fn sampling_sends_to_all_peers_below_the_fanout() {
+    let peers = (0..FANOUT).map(|_| PeerId::random()).collect::<Vec<_>>();
+
+    let targets = forward_targets(sampled(0), &peers, message(PeerId::random(), 0));
+    assert_eq!(targets, peers[1..]);
+}
@@ -0,0 +1,61 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{replay, subscribe_rpc, CapturedRpc, Network};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+use std::time::Duration;
+
+#[test]
+fn health_counts_topics_with_subscribers_once() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let peers = [PeerId::random(), PeerId::random()];
+    let rpcs = peers.iter().flat_map(|peer_id| {
+        ["a", "b"].into_iter().map(|topic| CapturedRpc {
+            at: Duration::ZERO,
+            source: *peer_id,
+            rpc: subscribe_rpc(Topic::new(topic)),
+        })
+    });
+    replay(&mut floodsub, rpcs);
+    floodsub.subscribe(Topic::new("c"));
+
+    let health = floodsub.health();
+    assert_eq!(health.connected_peers, 2);
+    assert_eq!(health.subscribed_topics, 1);
+    assert_eq!(health.topics_with_subscribers, 2);
+}
+
+#[test]
+fn health_forgets_the_topics_of_disconnected_peers() {
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::new(PeerId::random()));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    network.node_mut(&remote).subscribe(Topic::new("topic"));
+    network.connect(local, remote);
+    network.run_until_idle();
+    assert_eq!(network.node(&local).health().topics_with_subscribers, 1);
+
+    network.disconnect(local, remote);
+    network.run_until_idle();
+    let health = network.node(&local).health();
+    assert_eq!(health.connected_peers, 0);
+    assert_eq!(health.topics_with_subscribers, 0);
+}
@@ -0,0 +1,93 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::thread;
+use std::time::Duration;
+
+use libp2p_floodsub::testing::{drain_actions, inject, receive_rpc, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns a behaviour connected to a peer, with no event left to yield.
+fn idle_floodsub() -> (Floodsub, PeerId) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.idle_check_interval = Some(IDLE_CHECK_INTERVAL);
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("a"));
+    let remote = PeerId::random();
+    floodsub.add_node_to_partial_view(remote);
+    inject(&mut floodsub, remote, subscribe_rpc(Topic::new("a")));
+    drain_actions(&mut floodsub);
+    (floodsub, remote)
+}
+
+fn publish(floodsub: &mut Floodsub) {
+    floodsub
+        .publish(Topic::new("a"), b"data".to_vec())
+        .expect("the remote is subscribed");
+}
+
+fn warnings(floodsub: &mut Floodsub) -> usize {
+    drain_actions(floodsub)
+        .into_iter()
+        .filter(|action| {
+            matches!(
+                action,
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Warning(_))
+            )
+        })
+        .count()
+}
+
+#[test]
+fn warns_about_events_left_without_waking() {
+    let (mut floodsub, _) = idle_floodsub();
+
+    // The first message wakes the task, which doesn't poll us, so the next ones can't wake it.
+    publish(&mut floodsub);
+    publish(&mut floodsub);
+    thread::sleep(IDLE_CHECK_INTERVAL * 2);
+    assert_eq!(warnings(&mut floodsub), 1);
+
+    // The events were drained.
+    thread::sleep(IDLE_CHECK_INTERVAL * 2);
+    assert_eq!(warnings(&mut floodsub), 0);
+}
+
+#[test]
+fn no_warning_when_polled_in_time() {
+    let (mut floodsub, _) = idle_floodsub();
+
+    publish(&mut floodsub);
+    publish(&mut floodsub);
+    assert_eq!(warnings(&mut floodsub), 0);
+}
+
+#[test]
+fn no_warning_after_a_quiet_period() {
+    let (mut floodsub, remote) = idle_floodsub();
+
+    thread::sleep(IDLE_CHECK_INTERVAL * 2);
+    receive_rpc(&mut floodsub, remote, subscribe_rpc(Topic::new("b")));
+    assert_eq!(warnings(&mut floodsub), 0);
+}
@@ -0,0 +1,55 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::drain_actions;
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, PublishError, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn own_messages_are_delivered_without_peers() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.subscribe_local_messages = true;
+    let mut floodsub = Floodsub::from_config(config);
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+
+    assert_eq!(floodsub.publish(topic, b"data".to_vec()), Ok(()));
+    let delivered = drain_actions(&mut floodsub).into_iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(message))
+                if message.data == b"data"
+        )
+    });
+    assert!(delivered);
+}
+
+#[test]
+fn publishing_without_peers_fails_without_local_delivery() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+
+    assert_eq!(
+        floodsub.publish(topic, b"data".to_vec()),
+        Err(PublishError::NoPeers)
+    );
+}
@@ -0,0 +1,94 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, Topic};
+use libp2p_identity::PeerId;
+
+fn message_rpc(source: PeerId) -> FloodsubRpc {
+    FloodsubRpc {
+        messages: vec![FloodsubMessage {
+            source,
+            data: b"data".to_vec(),
+            sequence_number: vec![0; 8],
+            topics: vec![Topic::new("topic")],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        }],
+        subscriptions: Vec::new(),
+        sync: None,
+    }
+}
+
+/// Returns a behaviour with two peers subscribed to "topic".
+fn floodsub_with_peers(subscribe: bool) -> (Floodsub, [PeerId; 2]) {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    if subscribe {
+        floodsub.subscribe(Topic::new("topic"));
+    }
+    let peers = [PeerId::random(), PeerId::random()];
+    for peer_id in peers {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("topic")));
+    }
+    drain_actions(&mut floodsub);
+    (floodsub, peers)
+}
+
+#[test]
+fn messages_are_counted() {
+    let (mut floodsub, peers) = floodsub_with_peers(true);
+    assert_eq!(floodsub.metrics().subscriptions_received, 2);
+
+    floodsub
+        .publish(Topic::new("topic"), b"data".to_vec())
+        .expect("peers are subscribed");
+    assert_eq!(floodsub.metrics().messages_published, 1);
+
+    // Forwarded to the other peer only.
+    inject(&mut floodsub, peers[0], message_rpc(peers[0]));
+    let metrics = floodsub.metrics();
+    assert_eq!(metrics.messages_received, 1);
+    assert_eq!(metrics.messages_deduplicated, 0);
+    assert_eq!(metrics.messages_propagated, 1);
+    assert_eq!(metrics.messages_relayed, 0);
+
+    inject(&mut floodsub, peers[1], message_rpc(peers[0]));
+    let metrics = floodsub.metrics();
+    assert_eq!(metrics.messages_received, 2);
+    assert_eq!(metrics.messages_deduplicated, 1);
+    assert_eq!(metrics.messages_propagated, 1);
+}
+
+#[test]
+fn relayed_messages_are_counted() {
+    let (mut floodsub, peers) = floodsub_with_peers(false);
+    let source = PeerId::random();
+
+    inject(&mut floodsub, source, message_rpc(source));
+    let metrics = floodsub.metrics();
+    assert_eq!(metrics.messages_received, 1);
+    assert_eq!(metrics.messages_propagated, peers.len() as u64);
+    assert_eq!(metrics.messages_relayed, 1);
+}
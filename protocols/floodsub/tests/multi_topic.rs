@@ -0,0 +1,144 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{
+    Compression, Floodsub, FloodsubConfig, FloodsubEvent, MultiTopicFanout, Topic,
+};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+fn topics(ids: &[&str]) -> Vec<Topic> {
+    ids.iter().map(|id| Topic::new(*id)).collect()
+}
+
+fn ids(topics: &[Topic]) -> Vec<String> {
+    topics.iter().map(|topic| topic.id().to_owned()).collect()
+}
+
+/// Returns a behaviour subscribed to topics "a" and "b", with a peer subscribed to both topics
+/// and another to "b" only.
+fn floodsub_with_peers(fanout: MultiTopicFanout) -> (Floodsub, PeerId, PeerId) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.multi_topic_fanout = fanout;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("a"));
+    floodsub.subscribe(Topic::new("b"));
+    let (both, only_b) = (PeerId::random(), PeerId::random());
+    for (peer_id, subscribed) in [(both, topics(&["a", "b"])), (only_b, topics(&["b"]))] {
+        floodsub.add_node_to_partial_view(peer_id);
+        for topic in subscribed {
+            inject(&mut floodsub, peer_id, subscribe_rpc(topic));
+        }
+    }
+    drain_actions(&mut floodsub);
+    (floodsub, both, only_b)
+}
+
+/// Returns the topics of the messages sent to `peer` among `actions`.
+fn sent_to(
+    actions: &[NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>],
+    peer: PeerId,
+) -> Vec<Vec<String>> {
+    actions
+        .iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } if *peer_id == peer => {
+                Some(event.messages.iter().map(|message| ids(&message.topics)))
+            }
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn union_sends_messages_whole() {
+    let (mut floodsub, both, only_b) = floodsub_with_peers(MultiTopicFanout::Union);
+
+    floodsub
+        .publish_many(topics(&["a", "b"]), b"data".to_vec())
+        .expect("peers are subscribed");
+    let actions = drain_actions(&mut floodsub);
+    assert_eq!(sent_to(&actions, both), vec![vec!["a", "b"]]);
+    assert_eq!(sent_to(&actions, only_b), vec![vec!["a", "b"]]);
+}
+
+#[test]
+fn per_topic_sends_a_message_per_topic() {
+    let (mut floodsub, both, only_b) = floodsub_with_peers(MultiTopicFanout::PerTopic);
+
+    floodsub
+        .publish_many(topics(&["a", "b"]), b"data".to_vec())
+        .expect("peers are subscribed");
+    let actions = drain_actions(&mut floodsub);
+    let mut sent = sent_to(&actions, both);
+    sent.sort();
+    assert_eq!(sent, vec![vec!["a"], vec!["b"]]);
+    assert_eq!(sent_to(&actions, only_b), vec![vec!["b"]]);
+}
+
+/// Feeds a message of topics "a" and "b" to a behaviour subscribed to both, and returns the
+/// topics of the messages delivered.
+fn delivered(fanout: MultiTopicFanout) -> Vec<Vec<String>> {
+    let (mut floodsub, _, _) = floodsub_with_peers(fanout);
+    let source = PeerId::random();
+    let message = FloodsubMessage {
+        source,
+        data: b"data".to_vec(),
+        sequence_number: vec![0; 8],
+        topics: topics(&["a", "b"]),
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let mut delivered = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(message)) => {
+                Some(ids(&message.topics))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    delivered.sort();
+    delivered
+}
+
+#[test]
+fn received_messages_are_delivered_according_to_the_fanout() {
+    assert_eq!(delivered(MultiTopicFanout::Union), vec![vec!["a", "b"]]);
+    assert_eq!(
+        delivered(MultiTopicFanout::PerTopic),
+        vec![vec!["a"], vec!["b"]]
+    );
+}
@@ -0,0 +1,113 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{close_connection, drain_actions, open_connection, Network};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, TopologyChange};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn peer_churn_is_reported() {
+    let mut network = Network::new();
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.emit_topology_changes = true;
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+
+    network.connect(local, remote);
+    let connected = network
+        .run_until_idle()
+        .into_iter()
+        .filter(|(peer_id, _)| *peer_id == local)
+        .any(|(_, event)| {
+            matches!(
+                event,
+                FloodsubEvent::Topology(TopologyChange::PeerConnected { peer_id })
+                    if peer_id == remote
+            )
+        });
+    assert!(connected);
+
+    // The remote isn't subscribed to any topic.
+    network.disconnect(local, remote);
+    let disconnected = network
+        .run_until_idle()
+        .into_iter()
+        .filter(|(peer_id, _)| *peer_id == local)
+        .any(|(_, event)| {
+            matches!(
+                event,
+                FloodsubEvent::Topology(TopologyChange::PeerDisconnected { peer_id, topics })
+                    if peer_id == remote && topics.is_empty()
+            )
+        });
+    assert!(disconnected);
+}
+
+#[test]
+fn peers_connecting_and_disconnecting_are_reported_by_default() {
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::new(PeerId::random()));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+
+    network.connect(local, remote);
+    let events = network.run_until_idle();
+    assert!(events.iter().any(|(peer_id, event)| *peer_id == local
+        && matches!(event, FloodsubEvent::PeerConnected(peer_id) if *peer_id == remote)));
+
+    // The remote isn't subscribed to any topic.
+    network.disconnect(local, remote);
+    let events = network.run_until_idle();
+    assert!(events.iter().any(|(peer_id, event)| *peer_id == local
+        && matches!(event, FloodsubEvent::PeerDisconnected(peer_id) if *peer_id == remote)));
+}
+
+#[test]
+fn only_the_first_and_last_connections_are_reported() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+    let mut churn = Vec::new();
+    let mut record = |floodsub: &mut Floodsub| {
+        churn.extend(
+            drain_actions(floodsub)
+                .into_iter()
+                .filter_map(|action| match action {
+                    NetworkBehaviourAction::GenerateEvent(FloodsubEvent::PeerConnected(
+                        peer_id,
+                    )) => Some((true, peer_id)),
+                    NetworkBehaviourAction::GenerateEvent(FloodsubEvent::PeerDisconnected(
+                        peer_id,
+                    )) => Some((false, peer_id)),
+                    _ => None,
+                }),
+        )
+    };
+
+    for other_established in 0..2 {
+        open_connection(&mut floodsub, remote, other_established);
+        record(&mut floodsub);
+    }
+    for remaining_established in (0..2).rev() {
+        close_connection(&mut floodsub, remote, remaining_established);
+        record(&mut floodsub);
+    }
+
+    assert_eq!(churn, vec![(true, remote), (false, remote)]);
+}
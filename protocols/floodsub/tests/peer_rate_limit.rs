@@ -0,0 +1,136 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::FloodsubRpc;
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::thread;
+use std::time::Duration;
+
+const RATE: u32 = 20;
+
+/// Returns a behaviour limiting the messages sent to each peer to [`RATE`] per second,
+/// subscribed to the topics, with a peer subscribed to each of them.
+fn rate_limited_floodsub(topics: &[&str]) -> (Floodsub, Vec<PeerId>) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_messages_per_peer_per_sec = Some(RATE);
+    let mut floodsub = Floodsub::from_config(config);
+    let peers = topics
+        .iter()
+        .map(|topic| {
+            floodsub.subscribe(Topic::new(*topic));
+            let peer_id = PeerId::random();
+            floodsub.add_node_to_partial_view(peer_id);
+            inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new(*topic)));
+            peer_id
+        })
+        .collect();
+    drain_actions(&mut floodsub);
+    (floodsub, peers)
+}
+
+/// Returns the number of messages sent to `peer` among `actions`.
+fn sent_to(actions: &[NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>], peer: PeerId) -> usize {
+    actions
+        .iter()
+        .map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } if *peer_id == peer => {
+                event.messages.len()
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+#[test]
+fn messages_over_the_rate_are_dropped() {
+    let (mut floodsub, peers) = rate_limited_floodsub(&["topic"]);
+
+    for n in 0..RATE + 5 {
+        let dropped = floodsub
+            .try_publish(Topic::new("topic"), n.to_be_bytes().to_vec())
+            .expect("peer is subscribed");
+        assert_eq!(dropped.is_empty(), n < RATE);
+    }
+    assert_eq!(
+        sent_to(&drain_actions(&mut floodsub), peers[0]),
+        RATE as usize
+    );
+}
+
+#[test]
+fn messages_are_dropped_for_the_peers_over_the_rate_only() {
+    let (mut floodsub, peers) = rate_limited_floodsub(&["a", "b"]);
+
+    for n in 0..RATE + 5 {
+        floodsub
+            .publish(Topic::new("a"), n.to_be_bytes().to_vec())
+            .expect("peer is subscribed");
+    }
+    floodsub
+        .publish(Topic::new("b"), b"data".to_vec())
+        .expect("peer is subscribed");
+
+    let actions = drain_actions(&mut floodsub);
+    assert_eq!(sent_to(&actions, peers[0]), RATE as usize);
+    assert_eq!(sent_to(&actions, peers[1]), 1);
+}
+
+#[test]
+fn pinned_peers_are_not_limited() {
+    let (mut floodsub, peers) = rate_limited_floodsub(&["topic"]);
+    floodsub.pin_peer(peers[0]);
+
+    for n in 0..RATE + 5 {
+        floodsub
+            .publish(Topic::new("topic"), n.to_be_bytes().to_vec())
+            .expect("peer is subscribed");
+    }
+    assert_eq!(
+        sent_to(&drain_actions(&mut floodsub), peers[0]),
+        RATE as usize + 5
+    );
+}
+
+#[test]
+fn buckets_are_refilled_over_time() {
+    let (mut floodsub, peers) = rate_limited_floodsub(&["topic"]);
+    for n in 0..RATE {
+        floodsub
+            .publish(Topic::new("topic"), n.to_be_bytes().to_vec())
+            .expect("peer is subscribed");
+    }
+    drain_actions(&mut floodsub);
+    let dropped = floodsub
+        .try_publish(Topic::new("topic"), b"dropped".to_vec())
+        .expect("peer is subscribed");
+    assert_eq!(dropped, peers);
+
+    // Polling refills the bucket with the tokens earned in the meantime.
+    thread::sleep(Duration::from_secs(2) / RATE);
+    drain_actions(&mut floodsub);
+    let dropped = floodsub
+        .try_publish(Topic::new("topic"), b"sent".to_vec())
+        .expect("peer is subscribed");
+    assert!(dropped.is_empty());
+    assert_eq!(sent_to(&drain_actions(&mut floodsub), peers[0]), 1);
+}
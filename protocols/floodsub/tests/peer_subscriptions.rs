@@ -0,0 +1,74 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::FloodsubRpc;
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Floodsub, PublishError, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn injected_subscriptions_are_used_for_fanout() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    let remote = PeerId::random();
+    floodsub.add_node_to_partial_view(remote);
+    inject(
+        &mut floodsub,
+        remote,
+        FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(&mut floodsub);
+
+    floodsub.add_peer_subscription(remote, topic.clone());
+    assert_eq!(floodsub.subscriber_count(&topic), 1);
+    floodsub
+        .publish(topic.clone(), b"data".to_vec())
+        .expect("remote is subscribed");
+    let sent_to_remote = drain_actions(&mut floodsub).into_iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if peer_id == remote && !event.messages.is_empty()
+        )
+    });
+    assert!(sent_to_remote);
+
+    floodsub.remove_peer_subscription(&remote, &topic);
+    assert!(matches!(
+        floodsub.publish(topic, b"data".to_vec()),
+        Err(PublishError::NoPeers)
+    ));
+}
+
+#[test]
+fn subscriptions_of_unknown_peers_are_ignored() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+
+    floodsub.add_peer_subscription(PeerId::random(), topic.clone());
+
+    assert_eq!(floodsub.subscriber_count(&topic), 0);
+}
@@ -0,0 +1,64 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{connect_peer, drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn pending_events_are_counted_per_peer() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    let subscriber = PeerId::random();
+    let idle = PeerId::random();
+    floodsub.add_node_to_partial_view(subscriber);
+    inject(
+        &mut floodsub,
+        subscriber,
+        subscribe_rpc(Topic::new("topic")),
+    );
+    connect_peer(&mut floodsub, idle);
+    drain_actions(&mut floodsub);
+    assert_eq!(floodsub.pending_events_for(&subscriber), 0);
+
+    for n in 0..3u8 {
+        floodsub
+            .publish(Topic::new("topic"), vec![n])
+            .expect("peer is subscribed");
+    }
+    let pending = floodsub.pending_events_for(&subscriber);
+    assert!(pending > 0);
+    assert_eq!(floodsub.pending_events_for(&idle), 0);
+    assert_eq!(floodsub.pending_events_for(&PeerId::random()), 0);
+
+    // Counting doesn't change what is sent.
+    let sent = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter(|action| {
+            matches!(
+                action,
+                NetworkBehaviourAction::NotifyHandler { peer_id, .. } if *peer_id == subscriber
+            )
+        })
+        .count();
+    assert_eq!(sent, pending);
+    assert_eq!(floodsub.pending_events_for(&subscriber), 0);
+}
@@ -0,0 +1,94 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::task::{waker, ArcWake};
+use libp2p_floodsub::testing::{poll_action, publish_due_messages, Network};
+use libp2p_floodsub::{Floodsub, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Waker counting how many times it was woken.
+#[derive(Default)]
+struct CountingWaker(AtomicUsize);
+
+impl ArcWake for CountingWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn publishing_wakes_the_task_and_all_events_are_drained() {
+    const MESSAGES: usize = 50;
+
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::new(PeerId::random()));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    let topic = Topic::new("topic");
+    network.node_mut(&remote).subscribe(topic.clone());
+    network.connect(local, remote);
+    network.run_until_idle();
+
+    let counter = Arc::new(CountingWaker::default());
+    let waker = waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+    let node = network.node_mut(&local);
+    assert!(poll_action(node, &mut cx).is_pending());
+
+    for i in 0..MESSAGES {
+        node.publish_any(topic.clone(), vec![i as u8])
+            .expect("remote is subscribed");
+    }
+    assert!(counter.0.load(Ordering::SeqCst) > 0);
+
+    let mut sent = 0;
+    while let Poll::Ready(action) = poll_action(node, &mut cx) {
+        if matches!(action, NetworkBehaviourAction::NotifyHandler { peer_id, .. } if peer_id == remote)
+        {
+            sent += 1;
+        }
+    }
+    assert_eq!(sent, MESSAGES);
+}
+
+#[test]
+fn failed_scheduled_publications_wake_the_task() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let at = Instant::now() + Duration::from_secs(60);
+    floodsub.publish_at(Topic::new("topic"), b"data".to_vec(), at);
+
+    let counter = Arc::new(CountingWaker::default());
+    let waker = waker(counter.clone());
+    let mut cx = Context::from_waker(&waker);
+    assert!(poll_action(&mut floodsub, &mut cx).is_pending());
+
+    publish_due_messages(&mut floodsub, at);
+    assert!(counter.0.load(Ordering::SeqCst) > 0);
+    assert!(matches!(
+        poll_action(&mut floodsub, &mut cx),
+        Poll::Ready(NetworkBehaviourAction::GenerateEvent(
+            FloodsubEvent::ScheduledPublishFailed { .. }
+        ))
+    ));
+}
@@ -0,0 +1,88 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Feeds an RPC carrying five messages to a behaviour subscribed to their topic, and returns the
+/// number of events pending right after along with the data of the messages delivered once
+/// polled.
+fn receive_large_rpc(budget: Option<usize>) -> (usize, Vec<u8>) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.message_processing_budget = budget;
+    let mut floodsub = Floodsub::from_config(config);
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+
+    let source = PeerId::random();
+    let messages = (1..=5)
+        .map(|n| FloodsubMessage {
+            source,
+            data: vec![n],
+            sequence_number: vec![n; 8],
+            topics: vec![topic.clone()],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        })
+        .collect();
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages,
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let pending = floodsub.health().pending_events;
+    let delivered = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(message)) => {
+                Some(message.data[0])
+            }
+            _ => None,
+        })
+        .collect();
+    (pending, delivered)
+}
+
+#[test]
+fn messages_over_the_budget_are_processed_when_polled() {
+    let (pending, delivered) = receive_large_rpc(Some(2));
+
+    // The connection event and the first two messages.
+    assert_eq!(pending, 3);
+    assert_eq!(delivered, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn messages_are_processed_at_once_by_default() {
+    let (pending, delivered) = receive_large_rpc(None);
+
+    assert_eq!(pending, 6);
+    assert_eq!(delivered, vec![1, 2, 3, 4, 5]);
+}
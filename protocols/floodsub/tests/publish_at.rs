@@ -0,0 +1,103 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::time::{Duration, Instant};
+
+use libp2p_floodsub::testing::{drain_actions, inject, publish_due_messages, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubEvent, PublishError, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Returns a behaviour subscribed to `topic`, connected to a peer subscribed to it too.
+fn floodsub(topic: &Topic) -> Floodsub {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(topic.clone());
+    let remote = PeerId::random();
+    floodsub.add_node_to_partial_view(remote);
+    inject(&mut floodsub, remote, subscribe_rpc(topic.clone()));
+    drain_actions(&mut floodsub);
+    floodsub
+}
+
+/// Returns the data of the messages sent by a behaviour, in order.
+fn sent_messages(floodsub: &mut Floodsub) -> Vec<Vec<u8>> {
+    drain_actions(floodsub)
+        .into_iter()
+        .flat_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => event.messages,
+            _ => Vec::new(),
+        })
+        .map(|message| message.data)
+        .collect()
+}
+
+#[test]
+fn scheduled_messages_are_published_once_due() {
+    let topic = Topic::new("topic");
+    let mut floodsub = floodsub(&topic);
+    let start = Instant::now();
+    floodsub.publish_at(
+        topic.clone(),
+        b"second".to_vec(),
+        start + Duration::from_secs(120),
+    );
+    floodsub.publish_at(
+        topic.clone(),
+        b"first".to_vec(),
+        start + Duration::from_secs(60),
+    );
+    floodsub.publish_at(
+        topic,
+        b"first bis".to_vec(),
+        start + Duration::from_secs(60),
+    );
+    assert!(sent_messages(&mut floodsub).is_empty());
+
+    publish_due_messages(&mut floodsub, start + Duration::from_secs(30));
+    assert!(sent_messages(&mut floodsub).is_empty());
+
+    publish_due_messages(&mut floodsub, start + Duration::from_secs(90));
+    assert_eq!(
+        sent_messages(&mut floodsub),
+        vec![b"first".to_vec(), b"first bis".to_vec()]
+    );
+
+    publish_due_messages(&mut floodsub, start + Duration::from_secs(120));
+    assert_eq!(sent_messages(&mut floodsub), vec![b"second".to_vec()]);
+}
+
+#[test]
+fn scheduled_messages_follow_publish_checks() {
+    let mut floodsub = floodsub(&Topic::new("topic"));
+    let start = Instant::now();
+    floodsub.publish_at(Topic::new("other"), b"data".to_vec(), start);
+
+    publish_due_messages(&mut floodsub, start);
+    let failed = drain_actions(&mut floodsub).into_iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::ScheduledPublishFailed {
+                topic,
+                error: PublishError::NotSubscribed,
+            }) if topic == Topic::new("other")
+        )
+    });
+    assert!(failed);
+}
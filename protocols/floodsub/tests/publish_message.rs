@@ -0,0 +1,139 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, PublishError, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Returns a message built by another node.
+fn bridged_message() -> FloodsubMessage {
+    FloodsubMessage {
+        source: PeerId::random(),
+        data: b"data".to_vec(),
+        sequence_number: b"bridged".to_vec(),
+        topics: vec![Topic::new("topic")],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    }
+}
+
+/// Returns a behaviour with a peer subscribed to "topic", subscribed to it too if `subscribe`.
+fn floodsub_with_peer(config: FloodsubConfig, subscribe: bool) -> (Floodsub, PeerId) {
+    let mut floodsub = Floodsub::from_config(config);
+    if subscribe {
+        floodsub.subscribe(Topic::new("topic"));
+    }
+    let peer_id = PeerId::random();
+    floodsub.add_node_to_partial_view(peer_id);
+    inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("topic")));
+    drain_actions(&mut floodsub);
+    (floodsub, peer_id)
+}
+
+/// Returns the messages the behaviour sends when polled.
+fn sent_messages(floodsub: &mut Floodsub) -> Vec<FloodsubMessage> {
+    drain_actions(floodsub)
+        .into_iter()
+        .flat_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => event.messages,
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[test]
+fn messages_are_published_unchanged() {
+    let (mut floodsub, _) = floodsub_with_peer(FloodsubConfig::new(PeerId::random()), true);
+    let message = bridged_message();
+
+    floodsub
+        .publish_message(message.clone())
+        .expect("peer is subscribed");
+    assert_eq!(sent_messages(&mut floodsub), vec![message]);
+}
+
+#[test]
+fn known_messages_are_not_published_again() {
+    let (mut floodsub, peer_id) = floodsub_with_peer(FloodsubConfig::new(PeerId::random()), true);
+    let message = bridged_message();
+    floodsub
+        .publish_message(message.clone())
+        .expect("peer is subscribed");
+    drain_actions(&mut floodsub);
+
+    assert!(matches!(
+        floodsub.publish_message(message.clone()),
+        Err(PublishError::Duplicate)
+    ));
+
+    // Nor delivered if it loops back.
+    inject(
+        &mut floodsub,
+        peer_id,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    assert!(drain_actions(&mut floodsub).is_empty());
+}
+
+#[test]
+fn received_messages_are_not_published() {
+    let (mut floodsub, peer_id) = floodsub_with_peer(FloodsubConfig::new(PeerId::random()), true);
+    let message = bridged_message();
+    inject(
+        &mut floodsub,
+        peer_id,
+        FloodsubRpc {
+            messages: vec![message.clone()],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+
+    assert!(matches!(
+        floodsub.publish_message(message),
+        Err(PublishError::Duplicate)
+    ));
+}
+
+#[test]
+fn messages_are_published_to_our_topics_only() {
+    let (mut floodsub, _) = floodsub_with_peer(FloodsubConfig::new(PeerId::random()), false);
+    assert!(matches!(
+        floodsub.publish_message(bridged_message()),
+        Err(PublishError::NotSubscribed)
+    ));
+
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.allow_publish_without_subscription = true;
+    let (mut floodsub, _) = floodsub_with_peer(config, false);
+    floodsub
+        .publish_message(bridged_message())
+        .expect("peer is subscribed");
+    assert_eq!(sent_messages(&mut floodsub).len(), 1);
+}
@@ -0,0 +1,88 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Returns a behaviour subscribed to "topic", with a peer subscribed to it.
+fn floodsub_with_peer() -> (Floodsub, PeerId) {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    let peer_id = PeerId::random();
+    floodsub.add_node_to_partial_view(peer_id);
+    inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("topic")));
+    drain_actions(&mut floodsub);
+    (floodsub, peer_id)
+}
+
+/// Returns the messages the behaviour sends when polled.
+fn sent_messages(floodsub: &mut Floodsub) -> Vec<FloodsubMessage> {
+    drain_actions(floodsub)
+        .into_iter()
+        .flat_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => event.messages,
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[test]
+fn messages_are_published_with_the_given_sequence_number() {
+    let (mut floodsub, _) = floodsub_with_peer();
+
+    floodsub
+        .publish_with_sequence(Topic::new("topic"), b"data".to_vec(), b"bridged".to_vec())
+        .expect("peer is subscribed");
+    let sent = sent_messages(&mut floodsub);
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].sequence_number, b"bridged");
+
+    // Our counter is left untouched.
+    floodsub
+        .publish(Topic::new("topic"), b"data".to_vec())
+        .expect("peer is subscribed");
+    let sent = sent_messages(&mut floodsub);
+    assert_eq!(sent.len(), 1);
+    assert_ne!(sent[0].sequence_number, b"bridged");
+}
+
+#[test]
+fn messages_published_with_a_sequence_number_are_deduplicated() {
+    let (mut floodsub, peer_id) = floodsub_with_peer();
+    floodsub
+        .publish_with_sequence(Topic::new("topic"), b"data".to_vec(), vec![7; 8])
+        .expect("peer is subscribed");
+    assert_eq!(floodsub.received_cache_len(), 1);
+    let message = sent_messages(&mut floodsub).remove(0);
+
+    inject(
+        &mut floodsub,
+        peer_id,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    assert!(drain_actions(&mut floodsub).is_empty());
+}
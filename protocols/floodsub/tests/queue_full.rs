@@ -0,0 +1,101 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, QueueFullPolicy, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Publishes two messages with [`Floodsub::try_publish`] to two subscribed peers whose queues
+/// hold a single RPC, and returns the peers reported for each of them along with the data sent
+/// to the first peer once polled.
+fn publish_to_full_queues(policy: QueueFullPolicy) -> (Vec<Vec<PeerId>>, Vec<Vec<u8>>) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_queued_rpcs_per_peer = Some(1);
+    config.queue_full_policy = policy;
+    let mut floodsub = Floodsub::from_config(config);
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    let mut peers = [PeerId::random(), PeerId::random()];
+    peers.sort();
+    for peer_id in peers {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(topic.clone()));
+    }
+    drain_actions(&mut floodsub);
+
+    let reported = [b"1", b"2"]
+        .into_iter()
+        .map(|data| {
+            let mut dropped = floodsub
+                .try_publish(topic.clone(), data.to_vec())
+                .expect("peers are subscribed");
+            dropped.sort();
+            dropped
+        })
+        .collect();
+    let sent = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } if peer_id == peers[0] => {
+                Some(event.messages)
+            }
+            _ => None,
+        })
+        .flatten()
+        .map(|message| message.data)
+        .collect();
+    (reported, sent)
+}
+
+#[test]
+fn dropping_the_new_message_is_reported() {
+    let (reported, sent) = publish_to_full_queues(QueueFullPolicy::DropNewest);
+
+    assert!(reported[0].is_empty());
+    assert_eq!(reported[1].len(), 2);
+    assert_eq!(sent, vec![b"1".to_vec()]);
+}
+
+#[test]
+fn dropping_the_oldest_message_is_reported() {
+    let (reported, sent) = publish_to_full_queues(QueueFullPolicy::DropOldest);
+
+    assert!(reported[0].is_empty());
+    assert_eq!(reported[1].len(), 2);
+    assert_eq!(sent, vec![b"2".to_vec()]);
+}
+
+#[test]
+fn nothing_is_dropped_without_a_bound() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic");
+    floodsub.subscribe(topic.clone());
+    let remote = PeerId::random();
+    floodsub.add_node_to_partial_view(remote);
+    inject(&mut floodsub, remote, subscribe_rpc(topic.clone()));
+
+    for _ in 0..8 {
+        assert_eq!(
+            floodsub.try_publish(topic.clone(), b"data".to_vec()),
+            Ok(vec![])
+        );
+    }
+}
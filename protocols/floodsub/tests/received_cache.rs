@@ -0,0 +1,86 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::time::Duration;
+
+/// Feeds a message to the behaviour and returns whether it was delivered.
+fn deliver(floodsub: &mut Floodsub, source: PeerId, seq: u8) -> bool {
+    let message = FloodsubMessage {
+        source,
+        data: b"data".to_vec(),
+        sequence_number: vec![seq; 8],
+        topics: vec![Topic::new("topic")],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    inject(
+        floodsub,
+        source,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(floodsub).into_iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+        )
+    })
+}
+
+fn assert_cache_is_cleared(config: FloodsubConfig) {
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("topic"));
+    let source = PeerId::random();
+
+    assert_eq!(floodsub.received_cache_len(), 0);
+    for seq in 0..3 {
+        assert!(deliver(&mut floodsub, source, seq));
+    }
+    assert_eq!(floodsub.received_cache_len(), 3);
+    assert!(!deliver(&mut floodsub, source, 0));
+
+    floodsub.clear_received_cache();
+    assert_eq!(floodsub.received_cache_len(), 0);
+    assert!(deliver(&mut floodsub, source, 0));
+    assert_eq!(floodsub.received_cache_len(), 1);
+}
+
+#[test]
+fn clearing_the_filter_accepts_messages_again() {
+    assert_cache_is_cleared(FloodsubConfig::new(PeerId::random()));
+}
+
+#[test]
+fn clearing_the_cache_accepts_messages_again() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.duplicate_cache_time = Some(Duration::from_secs(60));
+    assert_cache_is_cleared(config);
+}
@@ -0,0 +1,88 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::Network;
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+
+/// Returns the data of the messages delivered to `node` among `events`.
+fn delivered(events: Vec<(PeerId, FloodsubEvent)>, node: PeerId) -> Vec<Vec<u8>> {
+    events
+        .into_iter()
+        .filter(|(peer_id, _)| *peer_id == node)
+        .filter_map(|(_, event)| match event {
+            FloodsubEvent::Message(message) => Some(message.data),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn late_subscribers_receive_the_recent_messages() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.recent_messages_per_topic = 2;
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let early = network.add_node(Floodsub::new(PeerId::random()));
+    let late = network.add_node(Floodsub::new(PeerId::random()));
+    let topic = Topic::new("topic");
+    network.node_mut(&early).subscribe(topic.clone());
+    network.connect(local, early);
+    network.connect(local, late);
+    network.run_until_idle();
+
+    for data in [b"1", b"2", b"3"] {
+        network
+            .node_mut(&local)
+            .publish_any(topic.clone(), data.to_vec())
+            .expect("early is subscribed");
+    }
+    assert_eq!(
+        delivered(network.run_until_idle(), late),
+        Vec::<Vec<u8>>::new()
+    );
+
+    network.node_mut(&late).subscribe(topic);
+    assert_eq!(
+        delivered(network.run_until_idle(), late),
+        vec![b"2".to_vec(), b"3".to_vec()]
+    );
+}
+
+#[test]
+fn messages_without_subscribers_are_not_remembered() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.recent_messages_per_topic = 2;
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let late = network.add_node(Floodsub::new(PeerId::random()));
+    let topic = Topic::new("topic");
+    network.connect(local, late);
+    network.run_until_idle();
+
+    // Nobody is subscribed to the topic, so the message goes nowhere.
+    let _ = network
+        .node_mut(&local)
+        .publish_any(topic.clone(), b"data".to_vec());
+    network.run_until_idle();
+
+    network.node_mut(&late).subscribe(topic);
+    assert!(delivered(network.run_until_idle(), late).is_empty());
+}
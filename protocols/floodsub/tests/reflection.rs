@@ -0,0 +1,104 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{replay, subscribe_rpc, CapturedRpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::time::Duration;
+
+fn subscribe(source: PeerId, at: u64) -> CapturedRpc {
+    CapturedRpc {
+        at: Duration::from_millis(at),
+        source,
+        rpc: subscribe_rpc(Topic::new("topic")),
+    }
+}
+
+#[test]
+fn reflected_subscriptions_are_ignored() {
+    let config = FloodsubConfig::new(PeerId::random());
+    let local_peer_id = config.local_peer_id;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("topic"));
+
+    let actions = replay(&mut floodsub, [subscribe(local_peer_id, 0)]);
+
+    assert_eq!(floodsub.subscriber_count(&Topic::new("topic")), 0);
+    assert!(!actions.into_iter().flatten().any(|action| matches!(
+        action,
+        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Subscribed { .. })
+    )));
+}
+
+#[test]
+fn messages_are_not_forwarded_back_to_their_source() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    let source = PeerId::random();
+    let forwarder = PeerId::random();
+    let other = PeerId::random();
+    for peer_id in [source, forwarder, other] {
+        floodsub.add_node_to_partial_view(peer_id);
+    }
+
+    let echoed = CapturedRpc {
+        at: Duration::from_millis(3),
+        source: forwarder,
+        rpc: FloodsubRpc {
+            messages: vec![FloodsubMessage {
+                source,
+                data: b"data".to_vec(),
+                sequence_number: 1u64.to_be_bytes().to_vec(),
+                topics: vec![Topic::new("topic")],
+                signature: None,
+                key: None,
+                provenance: Vec::new(),
+                ttl: u8::MAX,
+                compression: Compression::None,
+            }],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    };
+    let actions = replay(
+        &mut floodsub,
+        [
+            subscribe(source, 0),
+            subscribe(forwarder, 1),
+            subscribe(other, 2),
+            echoed,
+        ],
+    );
+
+    let forwarded_to = actions[3]
+        .iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if !event.messages.is_empty() =>
+            {
+                Some(*peer_id)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(forwarded_to, vec![other]);
+}
@@ -0,0 +1,118 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubRpc, FloodsubSubscription, FloodsubSubscriptionAction};
+use libp2p_floodsub::testing::{replay, CapturedRpc};
+use libp2p_floodsub::{Floodsub, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::time::Duration;
+
+/// Returns an RPC from `source` carrying only the given subscription change.
+fn subscription(source: PeerId, at: u64, action: FloodsubSubscriptionAction) -> CapturedRpc {
+    CapturedRpc {
+        at: Duration::from_millis(at),
+        source,
+        rpc: FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: vec![FloodsubSubscription {
+                action,
+                topic: Topic::new("topic"),
+            }],
+            sync: None,
+        },
+    }
+}
+
+#[test]
+fn resubscribing_keeps_a_single_entry() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+    let topic = Topic::new("topic");
+
+    replay(
+        &mut floodsub,
+        [
+            subscription(remote, 0, FloodsubSubscriptionAction::Subscribe),
+            subscription(remote, 1, FloodsubSubscriptionAction::Subscribe),
+        ],
+    );
+
+    assert_eq!(floodsub.subscriber_count(&topic), 1);
+    assert_eq!(floodsub.peers_subscribed_to(&topic).count(), 1);
+
+    replay(
+        &mut floodsub,
+        [subscription(
+            remote,
+            0,
+            FloodsubSubscriptionAction::Unsubscribe,
+        )],
+    );
+
+    assert_eq!(floodsub.subscriber_count(&topic), 0);
+}
+
+#[test]
+fn resubscribing_is_reported_once() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+
+    let actions = replay(
+        &mut floodsub,
+        [
+            subscription(remote, 0, FloodsubSubscriptionAction::Subscribe),
+            subscription(remote, 1, FloodsubSubscriptionAction::Subscribe),
+        ],
+    );
+
+    let subscribed = actions
+        .into_iter()
+        .flatten()
+        .filter(|action| {
+            matches!(
+                action,
+                NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Subscribed { .. })
+            )
+        })
+        .count();
+    assert_eq!(subscribed, 1);
+}
+
+#[test]
+fn unsubscribing_from_unknown_topic_is_a_no_op() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+
+    let actions = replay(
+        &mut floodsub,
+        [subscription(
+            remote,
+            0,
+            FloodsubSubscriptionAction::Unsubscribe,
+        )],
+    );
+
+    assert!(!actions.into_iter().flatten().any(|action| matches!(
+        action,
+        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Unsubscribed { .. })
+    )));
+    assert_eq!(floodsub.subscriber_count(&Topic::new("topic")), 0);
+}
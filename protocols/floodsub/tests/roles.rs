@@ -0,0 +1,103 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{
+    Compression, Floodsub, FloodsubConfig, FloodsubEvent, PublishError, Role, Topic,
+};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+fn floodsub_with_role(role: Role) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.role = role;
+    Floodsub::from_config(config)
+}
+
+#[test]
+fn subscribers_cannot_publish() {
+    let mut floodsub = floodsub_with_role(Role::SubscribeOnly);
+    assert!(matches!(
+        floodsub.publish_any(Topic::new("topic"), b"data".to_vec()),
+        Err(PublishError::SubscribeOnly)
+    ));
+}
+
+#[test]
+fn relays_cannot_publish() {
+    let mut floodsub = floodsub_with_role(Role::Relay);
+    assert!(matches!(
+        floodsub.publish_any(Topic::new("topic"), b"data".to_vec()),
+        Err(PublishError::Relay)
+    ));
+}
+
+#[test]
+fn relays_forward_messages_without_delivering_them() {
+    let mut floodsub = floodsub_with_role(Role::Relay);
+    assert!(!floodsub.subscribe(Topic::new("topic")));
+    let peers = [PeerId::random(), PeerId::random()];
+    for peer_id in peers {
+        floodsub.add_node_to_partial_view(peer_id);
+        inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("topic")));
+    }
+    drain_actions(&mut floodsub);
+
+    let rpc = FloodsubRpc {
+        messages: vec![FloodsubMessage {
+            source: peers[0],
+            data: b"data".to_vec(),
+            sequence_number: vec![0; 8],
+            topics: vec![Topic::new("topic")],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        }],
+        subscriptions: Vec::new(),
+        sync: None,
+    };
+    inject(&mut floodsub, peers[0], rpc.clone());
+    let actions = drain_actions(&mut floodsub);
+    assert!(!actions.iter().any(|action| matches!(
+        action,
+        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+    )));
+    let forwarded_to = actions
+        .iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if !event.messages.is_empty() =>
+            {
+                Some(*peer_id)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(forwarded_to, [peers[1]]);
+    assert_eq!(floodsub.metrics().messages_relayed, 1);
+
+    // Duplicates are still detected, so that messages don't loop through relays.
+    inject(&mut floodsub, peers[1], rpc);
+    assert!(drain_actions(&mut floodsub).is_empty());
+    assert_eq!(floodsub.metrics().messages_relayed, 1);
+}
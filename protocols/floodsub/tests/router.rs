@@ -0,0 +1,110 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, Topic, TopicRouter};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::sync::{Arc, Mutex};
+
+/// Router sending every message to the smallest candidate, recording the candidates it is given.
+struct Smallest(Arc<Mutex<Vec<PeerId>>>);
+
+impl TopicRouter for Smallest {
+    fn route(&mut self, _: &FloodsubMessage, mut candidates: Vec<PeerId>) -> Vec<PeerId> {
+        candidates.sort();
+        *self.0.lock().unwrap() = candidates.clone();
+        candidates.truncate(1);
+        candidates
+    }
+}
+
+/// Feeds a message from `forwarder` to a behaviour with `peers` and `forwarder` subscribed to its
+/// topic, and returns the sorted peers it forwards the message to.
+fn forward(floodsub: &mut Floodsub, peers: &[PeerId], forwarder: PeerId) -> Vec<PeerId> {
+    let topic = Topic::new("topic");
+    for peer_id in peers.iter().chain([&forwarder]) {
+        floodsub.add_node_to_partial_view(*peer_id);
+        inject(floodsub, *peer_id, subscribe_rpc(topic.clone()));
+    }
+    drain_actions(floodsub);
+
+    let message = FloodsubMessage {
+        source: PeerId::random(),
+        data: b"data".to_vec(),
+        sequence_number: vec![0; 8],
+        topics: vec![topic],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    inject(
+        floodsub,
+        forwarder,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let mut targets = drain_actions(floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. }
+                if !event.messages.is_empty() =>
+            {
+                Some(peer_id)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    targets.sort();
+    targets
+}
+
+#[test]
+fn messages_are_forwarded_to_all_subscribers_by_default() {
+    let mut peers = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+    peers.sort();
+    let targets = forward(
+        &mut Floodsub::new(PeerId::random()),
+        &peers,
+        PeerId::random(),
+    );
+    assert_eq!(targets, peers);
+}
+
+#[test]
+fn custom_router_decides_where_messages_are_forwarded() {
+    let mut peers = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+    peers.sort();
+    let candidates = Arc::new(Mutex::new(Vec::new()));
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.set_router(Smallest(candidates.clone()));
+
+    let targets = forward(&mut floodsub, &peers, PeerId::random());
+
+    // The peer we received the message from is never a candidate.
+    assert_eq!(*candidates.lock().unwrap(), peers);
+    assert_eq!(targets, vec![peers[0]]);
+}
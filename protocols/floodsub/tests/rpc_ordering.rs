@@ -0,0 +1,71 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::FloodsubRpc;
+use libp2p_floodsub::testing::{drain_actions, Network};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Publishes a message to a topic a connected peer is subscribed to, then subscribes to the
+/// topic, and returns the RPCs sent to the peer in order.
+fn publish_then_subscribe(config: FloodsubConfig) -> Vec<FloodsubRpc> {
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    let topic = Topic::new("topic");
+    network.node_mut(&remote).subscribe(topic.clone());
+    network.connect(local, remote);
+    network.run_until_idle();
+
+    let node = network.node_mut(&local);
+    node.publish_any(topic.clone(), b"data".to_vec())
+        .expect("remote is subscribed");
+    node.subscribe(topic);
+    drain_actions(node)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } if peer_id == remote => {
+                Some(event)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn subscriptions_are_sent_before_messages() {
+    let rpcs = publish_then_subscribe(FloodsubConfig::new(PeerId::random()));
+
+    assert_eq!(rpcs.len(), 2);
+    assert!(!rpcs[0].subscriptions.is_empty() && rpcs[0].messages.is_empty());
+    assert!(rpcs[1].subscriptions.is_empty() && !rpcs[1].messages.is_empty());
+}
+
+#[test]
+fn rpcs_are_sent_in_order_if_relaxed() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.subscriptions_before_messages = false;
+    let rpcs = publish_then_subscribe(config);
+
+    assert_eq!(rpcs.len(), 2);
+    assert!(!rpcs[0].messages.is_empty());
+    assert!(!rpcs[1].subscriptions.is_empty());
+}
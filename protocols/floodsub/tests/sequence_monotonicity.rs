@@ -0,0 +1,144 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{close_connection, drain_actions, inject, Network};
+use libp2p_floodsub::{
+    Compression, Floodsub, FloodsubConfig, FloodsubEvent, SequenceNumbers, Topic,
+};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+fn validating_floodsub() -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.validate_sequence_monotonicity = true;
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(Topic::new("topic"));
+    floodsub
+}
+
+/// Feeds a message published by `source` to a behaviour and returns whether it was delivered and
+/// whether the source was reported as suspicious.
+fn receive(
+    floodsub: &mut Floodsub,
+    source: PeerId,
+    sequence_number: u8,
+    data: &[u8],
+) -> (bool, bool) {
+    let message = FloodsubMessage {
+        source,
+        data: data.to_vec(),
+        sequence_number: vec![0, 0, 0, 0, 0, 0, 0, sequence_number],
+        topics: vec![Topic::new("topic")],
+        signature: None,
+        key: None,
+        provenance: Vec::new(),
+        ttl: u8::MAX,
+        compression: Compression::None,
+    };
+    inject(
+        floodsub,
+        source,
+        FloodsubRpc {
+            messages: vec![message],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let events = drain_actions(floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::GenerateEvent(event) => Some(event),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    (
+        events
+            .iter()
+            .any(|event| matches!(event, FloodsubEvent::Message(_))),
+        events.iter().any(|event| {
+            matches!(event, FloodsubEvent::SuspiciousSource { source: s, .. } if *s == source)
+        }),
+    )
+}
+
+#[test]
+fn non_increasing_sequence_numbers_are_dropped() {
+    let mut floodsub = validating_floodsub();
+    let source = PeerId::random();
+
+    assert_eq!(receive(&mut floodsub, source, 5, b"a"), (true, false));
+    assert_eq!(receive(&mut floodsub, source, 3, b"b"), (false, true));
+    assert_eq!(receive(&mut floodsub, source, 5, b"c"), (false, true));
+    assert_eq!(receive(&mut floodsub, source, 6, b"d"), (true, false));
+    // Other sources are tracked separately.
+    assert_eq!(
+        receive(&mut floodsub, PeerId::random(), 1, b"e"),
+        (true, false)
+    );
+}
+
+#[test]
+fn sequence_numbers_are_forgotten_on_disconnect() {
+    let mut floodsub = validating_floodsub();
+    let source = PeerId::random();
+
+    assert_eq!(receive(&mut floodsub, source, 5, b"a"), (true, false));
+    close_connection(&mut floodsub, source, 0);
+    assert_eq!(receive(&mut floodsub, source, 1, b"b"), (true, false));
+}
+
+#[test]
+fn sequence_numbers_are_not_validated_by_default() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    let source = PeerId::random();
+
+    assert_eq!(receive(&mut floodsub, source, 5, b"a"), (true, false));
+    assert_eq!(receive(&mut floodsub, source, 3, b"b"), (true, false));
+}
+
+#[test]
+fn monotonic_sequence_numbers_pass_validation() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.sequence_numbers = SequenceNumbers::Monotonic;
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(validating_floodsub());
+    network.node_mut(&local).subscribe(Topic::new("topic"));
+    network.connect(local, remote);
+    network.run_until_idle();
+
+    for data in [b"1", b"2", b"3"] {
+        network
+            .node_mut(&local)
+            .publish(Topic::new("topic"), data.to_vec())
+            .expect("remote is subscribed");
+    }
+    let events = network.run_until_idle();
+    let delivered = events
+        .iter()
+        .filter(|(peer_id, event)| *peer_id == remote && matches!(event, FloodsubEvent::Message(_)))
+        .count();
+    assert_eq!(delivered, 3);
+    assert!(!events
+        .iter()
+        .any(|(_, event)| matches!(event, FloodsubEvent::SuspiciousSource { .. })));
+}
@@ -0,0 +1,143 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{drain_actions, set_sequence_number_counter, Network};
+use libp2p_floodsub::{
+    Floodsub, FloodsubConfig, FloodsubEvent, SequenceNumberWraparound, SequenceNumbers, Topic,
+};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Publishes `count` messages from a node whose sequence numbers are about to wrap around, and
+/// returns their sequence numbers and whether the node reported the wraparound.
+fn publish_across_wraparound(
+    wraparound: SequenceNumberWraparound,
+    count: usize,
+) -> (Vec<Vec<u8>>, bool) {
+    publish_across_wraparound_with_len(wraparound, 8, u64::MAX - 1, count)
+}
+
+/// Publishes `count` messages from a node with sequence numbers of `len` bytes, starting after
+/// `counter`, and returns their sequence numbers and whether the node reported the wraparound.
+fn publish_across_wraparound_with_len(
+    wraparound: SequenceNumberWraparound,
+    len: usize,
+    counter: u64,
+    count: usize,
+) -> (Vec<Vec<u8>>, bool) {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.sequence_numbers = SequenceNumbers::Monotonic;
+    config.sequence_number_len = len;
+    config.sequence_number_wraparound = wraparound;
+
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    let topic = Topic::new("topic");
+    network.node_mut(&local).subscribe(topic.clone());
+    network.node_mut(&remote).subscribe(topic.clone());
+    network.connect(local, remote);
+    network.run_until_idle();
+
+    let node = network.node_mut(&local);
+    set_sequence_number_counter(node, counter);
+    for _ in 0..count {
+        node.publish(topic.clone(), b"data".to_vec())
+            .expect("remote is subscribed");
+    }
+
+    let mut sequence_numbers = Vec::new();
+    let mut wrapped = false;
+    for action in drain_actions(node) {
+        match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => sequence_numbers.extend(
+                event
+                    .messages
+                    .into_iter()
+                    .map(|message| message.sequence_number),
+            ),
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SequenceNumbersWrapped) => {
+                wrapped = true
+            }
+            _ => {}
+        }
+    }
+    (sequence_numbers, wrapped)
+}
+
+#[test]
+fn wraparound_is_reported() {
+    let (sequence_numbers, wrapped) =
+        publish_across_wraparound(SequenceNumberWraparound::Report, 3);
+
+    assert!(wrapped);
+    assert_eq!(
+        sequence_numbers,
+        vec![vec![0xff; 8], vec![0; 8], vec![0, 0, 0, 0, 0, 0, 0, 1]]
+    );
+}
+
+#[test]
+fn wraparound_switches_to_random_sequence_numbers() {
+    let (sequence_numbers, wrapped) =
+        publish_across_wraparound(SequenceNumberWraparound::SwitchToRandom, 3);
+
+    assert!(!wrapped);
+    assert_eq!(sequence_numbers.len(), 3);
+    assert_eq!(sequence_numbers[0], vec![0xff; 8]);
+    // The counter would have produced these.
+    assert_ne!(
+        sequence_numbers[1..],
+        [vec![0; 8], vec![0, 0, 0, 0, 0, 0, 0, 1]]
+    );
+}
+
+#[test]
+fn wraparound_is_only_logged_by_default() {
+    let (sequence_numbers, wrapped) = publish_across_wraparound(SequenceNumberWraparound::Warn, 2);
+
+    assert!(!wrapped);
+    assert_eq!(sequence_numbers, vec![vec![0xff; 8], vec![0; 8]]);
+}
+
+#[test]
+fn wraparound_depends_on_the_sequence_number_len() {
+    let (sequence_numbers, wrapped) = publish_across_wraparound_with_len(
+        SequenceNumberWraparound::Report,
+        4,
+        u32::MAX as u64 - 1,
+        3,
+    );
+
+    assert!(wrapped);
+    assert_eq!(
+        sequence_numbers,
+        vec![vec![0xff; 4], vec![0; 4], vec![0, 0, 0, 1]]
+    );
+}
+
+#[test]
+fn larger_sequence_numbers_wrap_around_with_the_counter() {
+    let (sequence_numbers, wrapped) =
+        publish_across_wraparound_with_len(SequenceNumberWraparound::Report, 12, u64::MAX, 1);
+
+    assert!(wrapped);
+    assert_eq!(sequence_numbers, vec![vec![0; 12]]);
+}
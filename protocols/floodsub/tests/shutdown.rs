@@ -0,0 +1,120 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::FloodsubSubscriptionAction;
+use libp2p_floodsub::testing::{drain_actions, Network};
+use libp2p_floodsub::{CallsAfterShutdown, Floodsub, FloodsubConfig, PublishError, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Returns a network of two connected nodes subscribed to `topic`, the first of which is shut
+/// down and drained, with the ids of the nodes.
+fn shut_down_network(config: FloodsubConfig, topic: &Topic) -> (Network, PeerId, PeerId) {
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    network.node_mut(&local).subscribe(topic.clone());
+    network.node_mut(&remote).subscribe(topic.clone());
+    network.connect(local, remote);
+    network.run_until_idle();
+
+    network.node_mut(&local).shutdown();
+    drain_actions(network.node_mut(&local));
+    (network, local, remote)
+}
+
+/// Returns the number of RPCs the node sends when polled.
+fn sent_rpcs(node: &mut Floodsub) -> usize {
+    drain_actions(node)
+        .into_iter()
+        .filter(|action| matches!(action, NetworkBehaviourAction::NotifyHandler { .. }))
+        .count()
+}
+
+#[test]
+fn calls_after_shutdown_fail_by_default() {
+    let topic = Topic::new("topic");
+    let (mut network, local, _) = shut_down_network(FloodsubConfig::new(PeerId::random()), &topic);
+    let node = network.node_mut(&local);
+
+    assert!(!node.subscribe(Topic::new("other")));
+    assert_eq!(node.subscribe_many([Topic::new("a"), Topic::new("b")]), 0);
+    assert_eq!(
+        node.publish_any(topic.clone(), b"data".to_vec()),
+        Err(PublishError::ShuttingDown)
+    );
+    assert_eq!(node.topics().count(), 0);
+    assert_eq!(sent_rpcs(node), 0);
+}
+
+#[test]
+fn calls_after_shutdown_can_be_ignored() {
+    let topic = Topic::new("topic");
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.calls_after_shutdown = CallsAfterShutdown::Ignore;
+    let (mut network, local, _) = shut_down_network(config, &topic);
+    let node = network.node_mut(&local);
+
+    assert!(node.subscribe(Topic::new("other")));
+    assert_eq!(node.subscribe_many([Topic::new("a"), Topic::new("b")]), 2);
+    assert_eq!(node.publish_any(topic.clone(), b"data".to_vec()), Ok(()));
+    assert_eq!(node.topics().count(), 0);
+    assert_eq!(sent_rpcs(node), 0);
+}
+
+#[test]
+fn shutdown_unsubscribes_every_peer_from_every_topic() {
+    let topics = [Topic::new("a"), Topic::new("b")];
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::new(PeerId::random()));
+    let remotes = [
+        network.add_node(Floodsub::new(PeerId::random())),
+        network.add_node(Floodsub::new(PeerId::random())),
+    ];
+    for topic in &topics {
+        network.node_mut(&local).subscribe(topic.clone());
+    }
+    for remote in remotes {
+        network.connect(local, remote);
+    }
+    network.run_until_idle();
+
+    network.node_mut(&local).shutdown();
+    let mut unsubscribed = drain_actions(network.node_mut(&local))
+        .into_iter()
+        .flat_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } => event
+                .subscriptions
+                .into_iter()
+                .filter(|sub| sub.action == FloodsubSubscriptionAction::Unsubscribe)
+                .map(|sub| (peer_id, sub.topic.id().to_owned()))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    unsubscribed.sort();
+
+    let mut expected = remotes
+        .iter()
+        .flat_map(|remote| topics.iter().map(|topic| (*remote, topic.id().to_owned())))
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(unsubscribed, expected);
+}
@@ -0,0 +1,72 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::fs;
+use std::path::Path;
+
+fn spilling_floodsub(path: &Path, topic: &Topic) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.events_spill_threshold = Some(0);
+    config.events_spill_path = path.to_owned();
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(topic.clone());
+
+    let peer_id = PeerId::random();
+    floodsub.add_node_to_partial_view(peer_id);
+    inject(&mut floodsub, peer_id, subscribe_rpc(topic.clone()));
+    drain_actions(&mut floodsub);
+    floodsub
+}
+
+#[test]
+fn existing_files_are_not_overwritten() {
+    let path = std::env::temp_dir().join(format!("floodsub-existing-{}.spill", PeerId::random()));
+    fs::write(&path, b"keep").unwrap();
+    let topic = Topic::new("topic");
+    let mut floodsub = spilling_floodsub(&path, &topic);
+
+    floodsub.publish(topic, b"data".to_vec()).unwrap();
+
+    // The RPC is kept in memory instead.
+    assert!(drain_actions(&mut floodsub)
+        .iter()
+        .any(|action| matches!(action, NetworkBehaviourAction::NotifyHandler { event, .. } if !event.messages.is_empty())));
+    assert_eq!(fs::read(&path).unwrap(), b"keep");
+    drop(floodsub);
+    assert_eq!(fs::read(&path).unwrap(), b"keep");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn spill_file_is_removed_on_drop() {
+    let path = std::env::temp_dir().join(format!("floodsub-removed-{}.spill", PeerId::random()));
+    let topic = Topic::new("topic");
+    let mut floodsub = spilling_floodsub(&path, &topic);
+
+    floodsub.publish(topic, b"data".to_vec()).unwrap();
+
+    assert!(path.exists());
+    drop(floodsub);
+    assert!(!path.exists());
+}
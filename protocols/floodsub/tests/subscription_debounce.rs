@@ -0,0 +1,81 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubRpc, FloodsubSubscriptionAction};
+use libp2p_floodsub::testing::{connect_peer, drain_actions};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::thread;
+use std::time::Duration;
+
+const DEBOUNCE: Duration = Duration::from_millis(20);
+
+/// Returns a behaviour debouncing its announcements, connected to a peer.
+fn debounced_floodsub() -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.subscription_debounce = Some(DEBOUNCE);
+    let mut floodsub = Floodsub::from_config(config);
+    connect_peer(&mut floodsub, PeerId::random());
+    drain_actions(&mut floodsub);
+    floodsub
+}
+
+/// Returns the RPCs the behaviour sends when polled.
+fn sent_rpcs(floodsub: &mut Floodsub) -> Vec<FloodsubRpc> {
+    drain_actions(floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn subscription_changes_are_announced_as_a_net_diff() {
+    let mut floodsub = debounced_floodsub();
+
+    floodsub.subscribe(Topic::new("a"));
+    floodsub.subscribe(Topic::new("b"));
+    floodsub.unsubscribe(Topic::new("a"));
+    assert!(sent_rpcs(&mut floodsub).is_empty());
+
+    thread::sleep(DEBOUNCE);
+    let rpcs = sent_rpcs(&mut floodsub);
+    assert_eq!(rpcs.len(), 1);
+    assert_eq!(rpcs[0].subscriptions.len(), 1);
+    assert_eq!(rpcs[0].subscriptions[0].topic, Topic::new("b"));
+    assert_eq!(
+        rpcs[0].subscriptions[0].action,
+        FloodsubSubscriptionAction::Subscribe
+    );
+}
+
+#[test]
+fn cancelled_subscription_is_never_announced() {
+    let mut floodsub = debounced_floodsub();
+
+    floodsub.subscribe(Topic::new("a"));
+    floodsub.unsubscribe(Topic::new("a"));
+
+    thread::sleep(DEBOUNCE);
+    assert!(sent_rpcs(&mut floodsub).is_empty());
+}
@@ -0,0 +1,129 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{
+    FloodsubRpc, FloodsubSubscription, FloodsubSubscriptionAction, FloodsubSubscriptionSync,
+};
+use libp2p_floodsub::testing::{replay, CapturedRpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+use std::time::Duration;
+
+const MAX_SUBSCRIPTIONS: usize = 8;
+
+fn subscriptions(
+    source: PeerId,
+    at: u64,
+    action: FloodsubSubscriptionAction,
+    topics: impl IntoIterator<Item = Topic>,
+) -> CapturedRpc {
+    CapturedRpc {
+        at: Duration::from_millis(at),
+        source,
+        rpc: FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: topics
+                .into_iter()
+                .map(|topic| FloodsubSubscription {
+                    action: action.clone(),
+                    topic,
+                })
+                .collect(),
+            sync: None,
+        },
+    }
+}
+
+#[test]
+fn subscriptions_over_the_limit_are_ignored() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_subscriptions_per_peer = Some(MAX_SUBSCRIPTIONS);
+    let mut floodsub = Floodsub::from_config(config);
+    let remote = PeerId::random();
+    let topics = (0..20)
+        .map(|n| Topic::new(format!("topic-{n}")))
+        .collect::<Vec<_>>();
+
+    replay(
+        &mut floodsub,
+        [subscriptions(
+            remote,
+            0,
+            FloodsubSubscriptionAction::Subscribe,
+            topics.clone(),
+        )],
+    );
+    let subscribed = |floodsub: &Floodsub| {
+        topics
+            .iter()
+            .filter(|topic| floodsub.subscriber_count(topic) > 0)
+            .count()
+    };
+    assert_eq!(subscribed(&floodsub), MAX_SUBSCRIPTIONS);
+    assert_eq!(floodsub.subscriber_count(&topics[0]), 1);
+    assert_eq!(floodsub.subscriber_count(&topics[MAX_SUBSCRIPTIONS]), 0);
+
+    // Unsubscribing makes room for other topics.
+    replay(
+        &mut floodsub,
+        [
+            subscriptions(
+                remote,
+                0,
+                FloodsubSubscriptionAction::Unsubscribe,
+                topics[..2].to_vec(),
+            ),
+            subscriptions(
+                remote,
+                1,
+                FloodsubSubscriptionAction::Subscribe,
+                topics[MAX_SUBSCRIPTIONS..].to_vec(),
+            ),
+        ],
+    );
+    assert_eq!(subscribed(&floodsub), MAX_SUBSCRIPTIONS);
+    assert_eq!(floodsub.subscriber_count(&topics[MAX_SUBSCRIPTIONS]), 1);
+}
+
+#[test]
+fn truncated_views_are_not_reconciled() {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_subscriptions_per_peer = Some(MAX_SUBSCRIPTIONS);
+    let mut floodsub = Floodsub::from_config(config);
+    let remote = PeerId::random();
+    let topics = (0..20).map(|n| Topic::new(format!("topic-{n}")));
+    let mut digest = subscriptions(remote, 1, FloodsubSubscriptionAction::Subscribe, []);
+    // Our view of the subscriptions of the remote can't match its digest.
+    digest.rpc.sync = Some(FloodsubSubscriptionSync::Digest(vec![0; 8]));
+
+    let actions = replay(
+        &mut floodsub,
+        [
+            subscriptions(remote, 0, FloodsubSubscriptionAction::Subscribe, topics),
+            digest,
+        ],
+    );
+    assert!(!actions[1].iter().any(|action| matches!(
+        action,
+        NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SubscriptionsDiverged { .. })
+            | NetworkBehaviourAction::NotifyHandler { .. }
+    )));
+}
@@ -0,0 +1,84 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{
+    close_connection, connect_peer, drain_actions, open_connection, rpc_sent,
+};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, FloodsubRpc, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::{NetworkBehaviourAction, NotifyHandler};
+
+/// Returns a behaviour waiting for a single peer to acknowledge its subscriptions, connected
+/// twice to `remote`, with the subscription to `topic` yielded to the handler of one of the
+/// connections.
+fn subscribed_floodsub(remote: PeerId, topic: &Topic) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.subscription_quorum = Some(1);
+    let mut floodsub = Floodsub::from_config(config);
+    connect_peer(&mut floodsub, remote);
+    open_connection(&mut floodsub, remote, 1);
+    drain_actions(&mut floodsub);
+
+    floodsub.subscribe(topic.clone());
+    let yielded = drain_actions(&mut floodsub)
+        .into_iter()
+        .any(|action| match action {
+            NetworkBehaviourAction::NotifyHandler {
+                peer_id,
+                handler: NotifyHandler::One(_),
+                event,
+            } => peer_id == remote && !event.subscriptions.is_empty(),
+            _ => false,
+        });
+    assert!(yielded, "subscription to be sent on a single connection");
+    floodsub
+}
+
+fn is_established(actions: &[NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>]) -> bool {
+    actions.iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::SubscriptionEstablished { .. })
+        )
+    })
+}
+
+#[test]
+fn acknowledged_subscription_is_established() {
+    let remote = PeerId::random();
+    let topic = Topic::new("topic");
+    let mut floodsub = subscribed_floodsub(remote, &topic);
+
+    rpc_sent(&mut floodsub, remote);
+    assert!(is_established(&drain_actions(&mut floodsub)));
+}
+
+#[test]
+fn failed_send_does_not_count_toward_the_quorum() {
+    let remote = PeerId::random();
+    let topic = Topic::new("topic");
+    let mut floodsub = subscribed_floodsub(remote, &topic);
+
+    // The connection the subscription was yielded to closes before sending it, and the other
+    // connection acknowledges an unrelated RPC.
+    close_connection(&mut floodsub, remote, 1);
+    rpc_sent(&mut floodsub, remote);
+    assert!(!is_established(&drain_actions(&mut floodsub)));
+}
@@ -0,0 +1,59 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::{connect_peer, receive_rpc, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+
+fn floodsub(lazy_peer_insert: bool) -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.lazy_peer_insert = lazy_peer_insert;
+    Floodsub::from_config(config)
+}
+
+#[test]
+fn subscriptions_before_connection_are_dropped_by_default() {
+    let mut floodsub = floodsub(false);
+    let topic = Topic::new("topic");
+    let remote = PeerId::random();
+
+    receive_rpc(&mut floodsub, remote, subscribe_rpc(topic.clone()));
+    assert_eq!(floodsub.num_connected_peers(), 0);
+    assert_eq!(floodsub.subscriber_count(&topic), 0);
+
+    connect_peer(&mut floodsub, remote);
+    assert_eq!(floodsub.num_connected_peers(), 1);
+    assert_eq!(floodsub.subscriber_count(&topic), 0);
+}
+
+#[test]
+fn subscriptions_before_connection_are_kept_with_lazy_insert() {
+    let mut floodsub = floodsub(true);
+    let topic = Topic::new("topic");
+    let remote = PeerId::random();
+
+    receive_rpc(&mut floodsub, remote, subscribe_rpc(topic.clone()));
+    assert_eq!(floodsub.num_connected_peers(), 1);
+    assert_eq!(floodsub.subscriber_count(&topic), 1);
+
+    connect_peer(&mut floodsub, remote);
+    assert_eq!(floodsub.num_connected_peers(), 1);
+    assert_eq!(floodsub.subscriber_count(&topic), 1);
+}
@@ -0,0 +1,107 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubRpc, FloodsubSubscription, FloodsubSubscriptionAction};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+use std::thread;
+use std::time::Duration;
+
+const WINDOW: Duration = Duration::from_millis(20);
+
+fn subscriptions(changes: &[(&str, FloodsubSubscriptionAction)]) -> FloodsubRpc {
+    FloodsubRpc {
+        messages: Vec::new(),
+        subscriptions: changes
+            .iter()
+            .map(|(topic, action)| FloodsubSubscription {
+                action: action.clone(),
+                topic: Topic::new(*topic),
+            })
+            .collect(),
+        sync: None,
+    }
+}
+
+fn rate_limited_floodsub() -> Floodsub {
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.max_subscription_updates_per_peer = Some(2);
+    config.subscription_update_window = WINDOW;
+    Floodsub::from_config(config)
+}
+
+#[test]
+fn excess_subscription_changes_are_deferred_and_penalized() {
+    use FloodsubSubscriptionAction::*;
+    let mut floodsub = rate_limited_floodsub();
+    let remote = PeerId::random();
+
+    inject(
+        &mut floodsub,
+        remote,
+        subscriptions(&[("a", Subscribe), ("b", Subscribe), ("c", Subscribe)]),
+    );
+    assert_eq!(floodsub.subscriber_count(&Topic::new("a")), 1);
+    assert_eq!(floodsub.subscriber_count(&Topic::new("b")), 1);
+    assert_eq!(floodsub.subscriber_count(&Topic::new("c")), 0);
+    assert!(floodsub.peer_score(&remote) < 0.0);
+
+    thread::sleep(WINDOW);
+    drain_actions(&mut floodsub);
+    assert_eq!(floodsub.subscriber_count(&Topic::new("c")), 1);
+}
+
+#[test]
+fn excess_subscription_changes_are_coalesced() {
+    use FloodsubSubscriptionAction::*;
+    let mut floodsub = rate_limited_floodsub();
+    let remote = PeerId::random();
+
+    // Only the last change to "c" within the window is applied.
+    inject(
+        &mut floodsub,
+        remote,
+        subscriptions(&[
+            ("a", Subscribe),
+            ("b", Subscribe),
+            ("c", Subscribe),
+            ("c", Unsubscribe),
+        ]),
+    );
+    thread::sleep(WINDOW);
+    drain_actions(&mut floodsub);
+    assert_eq!(floodsub.subscriber_count(&Topic::new("c")), 0);
+}
+
+#[test]
+fn subscription_changes_are_not_limited_by_default() {
+    use FloodsubSubscriptionAction::*;
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+
+    inject(
+        &mut floodsub,
+        remote,
+        subscriptions(&[("a", Subscribe), ("b", Subscribe), ("c", Subscribe)]),
+    );
+    assert_eq!(floodsub.subscriber_count(&Topic::new("c")), 1);
+    assert_eq!(floodsub.peer_score(&remote), 0.0);
+}
@@ -0,0 +1,82 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::Network;
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use std::thread;
+use std::time::Duration;
+
+const SYNC_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Returns a network of two connected nodes, the first of which subscribed to `topic` without
+/// the second one receiving the announcement, with the ids of the nodes.
+fn diverged_network(config: FloodsubConfig, topic: &Topic) -> (Network, PeerId, PeerId) {
+    let mut network = Network::new();
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    network.connect(local, remote);
+    network.run_until_idle();
+
+    network.partition(&[local], &[remote]);
+    network.node_mut(&local).subscribe(topic.clone());
+    network.run_until_idle();
+    network.heal();
+    assert_eq!(network.node(&remote).peers_subscribed_to(topic).count(), 0);
+    (network, local, remote)
+}
+
+#[test]
+fn diverged_views_are_reconciled() {
+    let topic = Topic::new("topic");
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.subscription_sync_interval = Some(SYNC_INTERVAL);
+    let (mut network, local, remote) = diverged_network(config, &topic);
+
+    thread::sleep(SYNC_INTERVAL);
+    let diverged = network.run_until_idle().into_iter().any(|(peer_id, event)| {
+        peer_id == remote
+            && matches!(event, FloodsubEvent::SubscriptionsDiverged { peer_id } if peer_id == local)
+    });
+
+    assert!(diverged);
+    assert_eq!(
+        network
+            .node(&remote)
+            .peers_subscribed_to(&topic)
+            .collect::<Vec<_>>(),
+        vec![&local]
+    );
+}
+
+#[test]
+fn views_are_not_reconciled_by_default() {
+    let topic = Topic::new("topic");
+    let (mut network, _, remote) = diverged_network(FloodsubConfig::new(PeerId::random()), &topic);
+
+    thread::sleep(SYNC_INTERVAL);
+    let diverged = network
+        .run_until_idle()
+        .into_iter()
+        .any(|(_, event)| matches!(event, FloodsubEvent::SubscriptionsDiverged { .. }));
+
+    assert!(!diverged);
+    assert_eq!(network.node(&remote).peers_subscribed_to(&topic).count(), 0);
+}
@@ -0,0 +1,57 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::testing::Network;
+use libp2p_floodsub::{Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+use std::time::Duration;
+
+/// Connects a node to a remote subscribed to a topic, reconnects them right away and returns
+/// the number of subscribers of the topic known to the node before the remote resent its
+/// subscriptions.
+fn subscribers_after_reconnect(subscription_resume_window: Option<Duration>) -> usize {
+    let mut network = Network::new();
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.subscription_resume_window = subscription_resume_window;
+    let local = network.add_node(Floodsub::from_config(config));
+    let remote = network.add_node(Floodsub::new(PeerId::random()));
+    let topic = Topic::new("topic");
+    network.node_mut(&remote).subscribe(topic.clone());
+    network.connect(local, remote);
+    network.run_until_idle();
+    assert_eq!(network.node(&local).subscriber_count(&topic), 1);
+
+    network.disconnect(local, remote);
+    network.connect(local, remote);
+    network.node(&local).subscriber_count(&topic)
+}
+
+#[test]
+fn subscriptions_are_restored_within_resume_window() {
+    assert_eq!(
+        subscribers_after_reconnect(Some(Duration::from_secs(60))),
+        1
+    );
+}
+
+#[test]
+fn subscriptions_are_forgotten_without_resume_window() {
+    assert_eq!(subscribers_after_reconnect(None), 0);
+}
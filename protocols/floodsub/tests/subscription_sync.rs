@@ -0,0 +1,112 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubRpc, FloodsubSubscriptionSync};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Floodsub, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+fn topics() -> Vec<Topic> {
+    (0..200)
+        .map(|n| Topic::new(format!("a-topic-with-a-rather-long-name-{n}")))
+        .collect()
+}
+
+/// Returns the RPCs a behaviour subscribed to [`topics`] sends when asked for all its
+/// subscriptions.
+fn full_subscriptions(requester: PeerId) -> Vec<FloodsubRpc> {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe_many(topics());
+    floodsub.add_node_to_partial_view(requester);
+    inject(
+        &mut floodsub,
+        requester,
+        FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(&mut floodsub);
+
+    inject(
+        &mut floodsub,
+        requester,
+        FloodsubRpc {
+            messages: Vec::new(),
+            subscriptions: Vec::new(),
+            sync: Some(FloodsubSubscriptionSync::RequestFull),
+        },
+    );
+    drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { event, .. } => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn split_subscriptions_mark_the_last_part() {
+    let rpcs = full_subscriptions(PeerId::random());
+    assert!(rpcs.len() > 1);
+
+    let (last, parts) = rpcs.split_last().expect("at least one RPC");
+    assert_eq!(last.sync, Some(FloodsubSubscriptionSync::Full));
+    for part in parts {
+        assert_eq!(part.sync, Some(FloodsubSubscriptionSync::Partial));
+    }
+    let sent = rpcs
+        .iter()
+        .flat_map(|rpc| rpc.subscriptions.iter().map(|sub| sub.topic.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(sent, topics());
+}
+
+#[test]
+fn split_subscriptions_replace_the_view_once_complete() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+    let stale = Topic::new("stale");
+    inject(&mut floodsub, remote, subscribe_rpc(stale.clone()));
+    drain_actions(&mut floodsub);
+
+    let mut unsubscribed = Vec::new();
+    for rpc in full_subscriptions(*floodsub.local_peer_id()) {
+        inject(&mut floodsub, remote, rpc);
+        for action in drain_actions(&mut floodsub) {
+            if let NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Unsubscribed {
+                topic,
+                ..
+            }) = action
+            {
+                unsubscribed.push(topic);
+            }
+        }
+    }
+
+    assert_eq!(unsubscribed, vec![stale.clone()]);
+    assert_eq!(floodsub.subscriber_count(&stale), 0);
+    for topic in topics() {
+        assert_eq!(floodsub.subscriber_count(&topic), 1);
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![cfg(feature = "substream-events")]
+
+use libp2p_floodsub::testing::{connect_peer, drain_actions, rpc_sent};
+use libp2p_floodsub::{Floodsub, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn substream_is_reported_once_the_rpc_is_sent() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let remote = PeerId::random();
+    connect_peer(&mut floodsub, remote);
+    drain_actions(&mut floodsub);
+    floodsub.subscribe(Topic::new("topic"));
+
+    // Yielding the RPC to the handler doesn't open a substream yet.
+    let actions = drain_actions(&mut floodsub);
+    assert!(actions
+        .iter()
+        .any(|action| matches!(action, NetworkBehaviourAction::NotifyHandler { .. })));
+    assert!(!actions
+        .iter()
+        .any(|action| matches!(action, NetworkBehaviourAction::GenerateEvent(_))));
+
+    rpc_sent(&mut floodsub, remote);
+    let events = drain_actions(&mut floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::GenerateEvent(event) => Some(event),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(matches!(
+        events.as_slice(),
+        [
+            FloodsubEvent::SubstreamOpened { peer_id: opened },
+            FloodsubEvent::SubstreamClosed { peer_id: closed },
+        ] if *opened == remote && *closed == remote
+    ));
+}
@@ -0,0 +1,68 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+#[test]
+fn label_is_recovered_for_received_messages() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let topic = Topic::new("topic").with_label("Topic");
+    assert_eq!(topic, Topic::new("topic"));
+    floodsub.subscribe(topic);
+    assert_eq!(floodsub.topic_label(&Topic::new("other")), None);
+
+    let remote = PeerId::random();
+    inject(
+        &mut floodsub,
+        remote,
+        FloodsubRpc {
+            messages: vec![FloodsubMessage {
+                source: remote,
+                data: b"data".to_vec(),
+                sequence_number: 1u64.to_be_bytes().to_vec(),
+                topics: vec![Topic::new("topic")],
+                signature: None,
+                key: None,
+                provenance: Vec::new(),
+                ttl: u8::MAX,
+                compression: Compression::None,
+            }],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    let message = drain_actions(&mut floodsub)
+        .into_iter()
+        .find_map(|action| match action {
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(message)) => Some(message),
+            _ => None,
+        })
+        .expect("the message is delivered");
+    // Labels aren't sent on the wire.
+    assert_eq!(message.topics[0].label(), None);
+    assert_eq!(floodsub.topic_label(&message.topics[0]), Some("Topic"));
+
+    floodsub.unsubscribe(Topic::new("topic"));
+    assert_eq!(floodsub.topic_label(&Topic::new("topic")), None);
+}
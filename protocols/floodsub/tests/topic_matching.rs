@@ -0,0 +1,130 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubEvent, Topic, TopicMatcher};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+fn message_rpc(source: PeerId, topic: &str) -> FloodsubRpc {
+    FloodsubRpc {
+        messages: vec![FloodsubMessage {
+            source,
+            data: b"data".to_vec(),
+            sequence_number: vec![0; 8],
+            topics: vec![Topic::new(topic)],
+            signature: None,
+            key: None,
+            provenance: Vec::new(),
+            ttl: u8::MAX,
+            compression: Compression::None,
+        }],
+        subscriptions: Vec::new(),
+        sync: None,
+    }
+}
+
+/// Returns whether the actions deliver a message to us.
+fn delivers(actions: Vec<NetworkBehaviourAction<FloodsubEvent, FloodsubRpc>>) -> bool {
+    actions.iter().any(|action| {
+        matches!(
+            action,
+            NetworkBehaviourAction::GenerateEvent(FloodsubEvent::Message(_))
+        )
+    })
+}
+
+#[test]
+fn matchers_match_families_of_topics() {
+    let prefix = TopicMatcher::Prefix("sensors/".into());
+    assert!(prefix.matches(&Topic::new("sensors/a")));
+    assert!(!prefix.matches(&Topic::new("sensor")));
+
+    let glob = TopicMatcher::Glob("sensors/*/temperature".into());
+    assert!(glob.matches(&Topic::new("sensors/a/temperature")));
+    assert!(glob.matches(&Topic::new("sensors/a/b/temperature")));
+    assert!(!glob.matches(&Topic::new("sensors/a/humidity")));
+    assert!(!glob.matches(&Topic::new("sensors/temperature")));
+
+    let exact = TopicMatcher::Glob("sensors".into());
+    assert!(exact.matches(&Topic::new("sensors")));
+    assert!(!exact.matches(&Topic::new("sensors/a")));
+}
+
+#[test]
+fn subscribing_to_a_family_subscribes_to_known_and_new_topics() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let peer_id = PeerId::random();
+    floodsub.add_node_to_partial_view(peer_id);
+    inject(
+        &mut floodsub,
+        peer_id,
+        subscribe_rpc(Topic::new("sensors/a")),
+    );
+    inject(&mut floodsub, peer_id, subscribe_rpc(Topic::new("other")));
+
+    assert!(floodsub.subscribe_matching(TopicMatcher::Glob("sensors/*".into())));
+    assert!(!floodsub.subscribe_matching(TopicMatcher::Glob("sensors/*".into())));
+    assert!(floodsub.is_subscribed(&Topic::new("sensors/a")));
+    assert!(!floodsub.is_subscribed(&Topic::new("other")));
+
+    inject(
+        &mut floodsub,
+        peer_id,
+        subscribe_rpc(Topic::new("sensors/b")),
+    );
+    assert!(floodsub.is_subscribed(&Topic::new("sensors/b")));
+
+    // Topics we subscribed to through the matcher are kept, but no new ones are added.
+    assert!(floodsub.unsubscribe_matching(&TopicMatcher::Glob("sensors/*".into())));
+    inject(
+        &mut floodsub,
+        peer_id,
+        subscribe_rpc(Topic::new("sensors/c")),
+    );
+    assert!(floodsub.is_subscribed(&Topic::new("sensors/b")));
+    assert!(!floodsub.is_subscribed(&Topic::new("sensors/c")));
+}
+
+#[test]
+fn messages_of_matching_topics_are_delivered() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe_matching(TopicMatcher::Prefix("sensors/".into()));
+    let source = PeerId::random();
+
+    // Even if no peer announced the topic yet.
+    inject(&mut floodsub, source, message_rpc(source, "sensors/a"));
+    assert!(delivers(drain_actions(&mut floodsub)));
+
+    inject(&mut floodsub, source, message_rpc(source, "other"));
+    assert!(!delivers(drain_actions(&mut floodsub)));
+}
+
+#[test]
+fn exact_subscriptions_are_unaffected_by_matchers() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    floodsub.subscribe(Topic::new("other"));
+    floodsub.subscribe_matching(TopicMatcher::Prefix("sensors/".into()));
+    let source = PeerId::random();
+
+    inject(&mut floodsub, source, message_rpc(source, "other"));
+    assert!(delivers(drain_actions(&mut floodsub)));
+}
@@ -0,0 +1,97 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::metrics::TopicStats;
+use libp2p_floodsub::protocol::{FloodsubMessage, FloodsubRpc};
+use libp2p_floodsub::testing::{drain_actions, inject, subscribe_rpc};
+use libp2p_floodsub::{Compression, Floodsub, FloodsubConfig, Topic};
+use libp2p_identity::PeerId;
+
+/// Publishes a message on both topics, then receives one on the first topic that is forwarded
+/// to a subscribed peer.
+fn exchange_messages(config: FloodsubConfig, a: &Topic, b: &Topic) -> Floodsub {
+    let mut floodsub = Floodsub::from_config(config);
+    floodsub.subscribe(a.clone());
+    floodsub.subscribe(b.clone());
+    let subscriber = PeerId::random();
+    floodsub.add_node_to_partial_view(subscriber);
+    inject(&mut floodsub, subscriber, subscribe_rpc(a.clone()));
+    floodsub
+        .publish_many(vec![a.clone(), b.clone()], b"published".to_vec())
+        .expect("a peer is subscribed");
+
+    let source = PeerId::random();
+    inject(
+        &mut floodsub,
+        source,
+        FloodsubRpc {
+            messages: vec![FloodsubMessage {
+                source,
+                data: b"received".to_vec(),
+                sequence_number: 1u64.to_be_bytes().to_vec(),
+                topics: vec![a.clone()],
+                signature: None,
+                key: None,
+                provenance: Vec::new(),
+                ttl: u8::MAX,
+                compression: Compression::None,
+            }],
+            subscriptions: Vec::new(),
+            sync: None,
+        },
+    );
+    drain_actions(&mut floodsub);
+    floodsub
+}
+
+#[test]
+fn messages_are_counted_for_each_topic() {
+    let (a, b) = (Topic::new("a"), Topic::new("b"));
+    let mut config = FloodsubConfig::new(PeerId::random());
+    config.track_topic_stats = true;
+    let floodsub = exchange_messages(config, &a, &b);
+
+    assert_eq!(
+        floodsub.topic_stats(&a),
+        Some(&TopicStats {
+            published: 1,
+            received: 1,
+            forwarded: 1,
+        })
+    );
+    assert_eq!(
+        floodsub.topic_stats(&b),
+        Some(&TopicStats {
+            published: 1,
+            received: 0,
+            forwarded: 0,
+        })
+    );
+    assert_eq!(floodsub.topic_stats(&Topic::new("c")), None);
+}
+
+#[test]
+fn messages_are_not_counted_by_default() {
+    let (a, b) = (Topic::new("a"), Topic::new("b"));
+    let floodsub = exchange_messages(FloodsubConfig::new(PeerId::random()), &a, &b);
+
+    assert_eq!(floodsub.topic_stats(&a), None);
+    assert!(floodsub.metrics().topics.is_empty());
+}
@@ -0,0 +1,117 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::executor::block_on;
+use futures::io::Cursor;
+use libp2p_core::upgrade::InboundUpgrade;
+use libp2p_floodsub::protocol::FloodsubProtocol;
+use libp2p_floodsub::testing::Network;
+use libp2p_floodsub::{Floodsub, FloodsubConfig, FloodsubEvent, Topic};
+use libp2p_identity::PeerId;
+
+/// Returns a line of `len` nodes subscribed to "topic", each connected to the next one, the
+/// first one publishing with the given TTL.
+fn line(len: usize, ttl: u8) -> (Network, Vec<PeerId>) {
+    let mut network = Network::new();
+    let nodes = (0..len)
+        .map(|i| {
+            let mut config = FloodsubConfig::new(PeerId::random());
+            if i == 0 {
+                config.default_ttl = ttl;
+            }
+            let mut floodsub = Floodsub::from_config(config);
+            floodsub.subscribe(Topic::new("topic"));
+            network.add_node(floodsub)
+        })
+        .collect::<Vec<_>>();
+    for pair in nodes.windows(2) {
+        network.connect(pair[0], pair[1]);
+    }
+    network.run_until_idle();
+    (network, nodes)
+}
+
+/// Publishes a message from the first node and returns the nodes it was delivered to.
+fn reached(network: &mut Network, nodes: &[PeerId]) -> Vec<PeerId> {
+    network
+        .node_mut(&nodes[0])
+        .publish(Topic::new("topic"), b"data".to_vec())
+        .expect("the next node is subscribed");
+    network
+        .run_until_idle()
+        .into_iter()
+        .filter_map(|(peer_id, event)| match event {
+            FloodsubEvent::Message(_) => Some(peer_id),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn messages_are_forwarded_until_their_ttl_expires() {
+    let (mut network, nodes) = line(5, 2);
+
+    let mut reached = reached(&mut network, &nodes);
+    reached.sort();
+    let mut expected = vec![nodes[1], nodes[2]];
+    expected.sort();
+    assert_eq!(reached, expected);
+}
+
+#[test]
+fn messages_with_a_ttl_of_one_only_reach_our_peers() {
+    let (mut network, nodes) = line(3, 1);
+
+    assert_eq!(reached(&mut network, &nodes), vec![nodes[1]]);
+}
+
+#[test]
+fn messages_reach_the_whole_network_by_default() {
+    let (mut network, nodes) = line(5, u8::MAX);
+
+    assert_eq!(reached(&mut network, &nodes).len(), nodes.len() - 1);
+}
+
+#[test]
+fn messages_without_a_ttl_have_the_maximum_one() {
+    fn push_bytes(out: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+        out.push(field << 3 | 2);
+        out.push(bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+
+    // A message encoded by an older peer, which knows nothing about TTLs.
+    let mut message = Vec::new();
+    push_bytes(&mut message, 1, &PeerId::random().to_bytes());
+    push_bytes(&mut message, 2, b"data");
+    push_bytes(&mut message, 3, &[0; 8]);
+    push_bytes(&mut message, 4, b"topic");
+    let mut rpc = Vec::new();
+    push_bytes(&mut rpc, 2, &message);
+    let mut frame = vec![rpc.len() as u8];
+    frame.extend_from_slice(&rpc);
+
+    let rpc = block_on(
+        FloodsubProtocol::new().upgrade_inbound(Cursor::new(frame), b"/floodsub/1.0.0".as_ref()),
+    )
+    .expect("the RPC to be decoded");
+    assert_eq!(rpc.messages.len(), 1);
+    assert_eq!(rpc.messages[0].ttl, u8::MAX);
+}
@@ -0,0 +1,85 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_floodsub::protocol::{FloodsubRpc, FloodsubSubscriptionAction};
+use libp2p_floodsub::testing::{connect_peer, drain_actions};
+use libp2p_floodsub::{Floodsub, Topic};
+use libp2p_identity::PeerId;
+use libp2p_swarm::NetworkBehaviourAction;
+
+/// Returns the RPCs the behaviour sends when polled, with their receiver.
+fn sent_rpcs(floodsub: &mut Floodsub) -> Vec<(PeerId, FloodsubRpc)> {
+    drain_actions(floodsub)
+        .into_iter()
+        .filter_map(|action| match action {
+            NetworkBehaviourAction::NotifyHandler { peer_id, event, .. } => Some((peer_id, event)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn unsubscribing_from_all_topics_is_announced_in_one_rpc_per_peer() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    let peers = [PeerId::random(), PeerId::random()];
+    for peer_id in peers {
+        connect_peer(&mut floodsub, peer_id);
+    }
+    for topic in ["a", "b", "c"] {
+        floodsub.subscribe(Topic::new(topic));
+    }
+    drain_actions(&mut floodsub);
+
+    assert_eq!(floodsub.unsubscribe_all(), 3);
+
+    let rpcs = sent_rpcs(&mut floodsub);
+    assert_eq!(rpcs.len(), peers.len());
+    for peer_id in peers {
+        let (_, rpc) = rpcs
+            .iter()
+            .find(|(receiver, _)| *receiver == peer_id)
+            .expect("peer to be told");
+        let mut topics = rpc
+            .subscriptions
+            .iter()
+            .map(|subscription| {
+                assert_eq!(subscription.action, FloodsubSubscriptionAction::Unsubscribe);
+                subscription.topic.id()
+            })
+            .collect::<Vec<_>>();
+        topics.sort_unstable();
+        assert_eq!(topics, ["a", "b", "c"]);
+    }
+}
+
+#[test]
+fn unsubscribing_from_all_topics_twice_is_a_no_op() {
+    let mut floodsub = Floodsub::new(PeerId::random());
+    connect_peer(&mut floodsub, PeerId::random());
+    floodsub.subscribe(Topic::new("topic"));
+    assert_eq!(floodsub.unsubscribe_all(), 1);
+    drain_actions(&mut floodsub);
+
+    assert_eq!(floodsub.unsubscribe_all(), 0);
+    assert!(sent_rpcs(&mut floodsub).is_empty());
+
+    // We can subscribe again afterwards.
+    assert!(floodsub.subscribe(Topic::new("topic")));
+}